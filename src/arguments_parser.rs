@@ -3,7 +3,14 @@
 //! This module provides CLI argument parsing using clap for configuring
 //! the Solana handshake client and gossip node behavior.
 
+use crate::solana::{AccessPolicy, DiscoveryConfig, IpCidr, OutputFormat, SolanaNodeError};
+use crate::solana::{Backoff, ErrorKind, Jitter, RetryPolicy};
+use crate::solana::{CapabilitySet, HandshakeMode, LivenessConfig, RateLimitConfig, RejectPolicy};
+use crate::telemetry::LogTarget;
 use clap::Parser;
+use solana_sdk::pubkey::Pubkey;
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Duration;
 
@@ -60,12 +67,59 @@ pub struct Args {
     )]
     pub max_retries: u8,
 
+    /// How the delay between handshake retries grows
+    #[arg(
+        long = "retry-backoff",
+        default_value = "exponential",
+        help = "Growth of the delay between retries: exponential, linear or constant"
+    )]
+    pub retry_backoff: Backoff,
+
+    /// Delay before the first retry in milliseconds
+    #[arg(
+        long = "retry-base-delay-ms",
+        default_value = "1000",
+        help = "Delay before the first retry, and the unit of later ones (milliseconds)"
+    )]
+    pub retry_base_delay_ms: u64,
+
+    /// Longest delay between retries in milliseconds
+    #[arg(
+        long = "retry-max-delay-ms",
+        default_value = "30000",
+        help = "Longest delay between two attempts (milliseconds)"
+    )]
+    pub retry_max_delay_ms: u64,
+
+    /// How retry delays are randomized
+    #[arg(
+        long = "retry-jitter",
+        default_value = "full",
+        help = "Randomization of retry delays: none, full (0 to the backoff delay) or decorrelated"
+    )]
+    pub retry_jitter: Jitter,
+
+    /// Time after the first attempt when no more retries are started
+    #[arg(
+        long = "retry-deadline-secs",
+        help = "Seconds after the first attempt after which a handshake is no longer retried"
+    )]
+    pub retry_deadline_secs: Option<u64>,
+
+    /// Error kinds never retried
+    #[arg(
+        long = "no-retry-on",
+        value_delimiter = ',',
+        help = "Comma-separated error kinds never retried, e.g. receive_timeout,remote_rejected"
+    )]
+    pub no_retry_on: Vec<ErrorKind>,
+
     /// Application mode
     #[arg(
         short = 'm',
         long = "mode",
         default_value = "client",
-        help = "Application mode: client (perform handshakes) or server (listen for handshakes)"
+        help = "Application mode: client (perform handshakes), server (listen for handshakes), discover (find peers from the --peers entrypoints) or crawl (discover and write a topology report)"
     )]
     pub mode: ApplicationMode,
 
@@ -102,6 +156,252 @@ pub struct Args {
         help = "Interval between handshake attempts in continuous mode (seconds)"
     )]
     pub interval_secs: u16,
+
+    /// Wire protocol used for client handshakes
+    #[arg(
+        long = "handshake-mode",
+        default_value = "legacy",
+        help = "Handshake wire protocol: legacy (this tool's gossip server) or gossip (real Solana validators)"
+    )]
+    pub handshake_mode: HandshakeMode,
+
+    /// Shred version advertised in gossip pull requests
+    #[arg(
+        long = "shred-version",
+        default_value = "0",
+        help = "Cluster shred version to advertise in gossip mode (validators ignore pull requests with a mismatched shred version)"
+    )]
+    pub shred_version: u16,
+
+    /// Gossip address advertised in gossip pull requests
+    #[arg(
+        long = "advertise-addr",
+        help = "Gossip address (IP:port) to advertise in gossip mode; defaults to the address of the interface used to reach each peer"
+    )]
+    pub advertise_addr: Option<SocketAddr>,
+
+    /// Capabilities peers must agree to for a handshake to succeed
+    #[arg(
+        long = "require-capabilities",
+        help = "Comma-separated capabilities peers must support, e.g. gossip,handshake (client mode)"
+    )]
+    pub required_capabilities: Option<CapabilitySet>,
+
+    /// Maximum number of handshakes performed at once
+    #[arg(
+        long = "concurrency",
+        default_value = "64",
+        help = "Maximum number of peers to handshake with concurrently"
+    )]
+    pub concurrency: usize,
+
+    /// Hops followed from the entrypoints in discover mode
+    #[arg(
+        long = "max-depth",
+        default_value = "2",
+        help = "Hops to follow from the entrypoints, 0 to handshake with the entrypoints only (discover and crawl modes)"
+    )]
+    pub max_depth: u32,
+
+    /// Maximum number of nodes handshaked with in discover mode
+    #[arg(
+        long = "max-peers",
+        default_value = "256",
+        help = "Maximum number of nodes to handshake with, entrypoints included (discover and crawl modes)"
+    )]
+    pub max_peers: usize,
+
+    /// Directory the crawl topology report is written to
+    #[arg(
+        long = "report-dir",
+        default_value = "crawl-report",
+        help = "Directory to write topology.json, topology.csv and topology.dot to (crawl mode)"
+    )]
+    pub report_dir: PathBuf,
+
+    /// Maximum number of packets the server handles at once
+    #[arg(
+        long = "workers",
+        default_value = "64",
+        help = "Maximum number of incoming packets handled concurrently (server mode)"
+    )]
+    pub workers: usize,
+
+    /// Maximum number of received packets waiting for a free worker
+    #[arg(
+        long = "queue-depth",
+        default_value = "1024",
+        help = "Maximum number of received packets queued for a free worker before the server stops reading (server mode)"
+    )]
+    pub queue_depth: usize,
+
+    /// Ceiling on packets per second from all sources
+    #[arg(
+        long = "max-packets-per-sec",
+        default_value = "10000",
+        help = "Maximum packets per second accepted from all sources combined, 0 for no limit (server mode)"
+    )]
+    pub max_packets_per_sec: u32,
+
+    /// Packets per second accepted from one source IP
+    #[arg(
+        long = "max-packets-per-ip",
+        default_value = "200",
+        help = "Maximum packets per second accepted from a single source IP, 0 for no limit (server mode)"
+    )]
+    pub max_packets_per_ip: u32,
+
+    /// Handshake requests per second accepted for one pubkey
+    #[arg(
+        long = "max-requests-per-pubkey",
+        default_value = "20",
+        help = "Maximum handshake requests and responses per second accepted for a single pubkey, 0 for no limit (server mode)"
+    )]
+    pub max_requests_per_pubkey: u32,
+
+    /// Failure responses per second sent to senders that have not answered a ping
+    #[arg(
+        long = "max-unverified-rejections-per-sec",
+        default_value = "100",
+        help = "Maximum failure responses per second sent to senders that have not answered a ping, 0 for no limit (server mode)"
+    )]
+    pub max_unverified_rejections_per_sec: u32,
+
+    /// Whether rejected requests from unverified senders are answered
+    #[arg(
+        long = "reject-policy",
+        default_value = "respond",
+        help = "How to treat rejected requests from senders that have not answered a ping: respond or drop-unverified (server mode)"
+    )]
+    pub reject_policy: RejectPolicy,
+
+    /// Acceptance window for incoming handshake request timestamps
+    #[arg(
+        long = "replay-window",
+        default_value = "120",
+        help = "Maximum age (and clock skew) in seconds of handshake requests accepted in server mode"
+    )]
+    pub replay_window_secs: u16,
+
+    /// Interval between liveness probes of registered peers
+    #[arg(
+        long = "probe-interval",
+        default_value = "30",
+        help = "Interval in seconds between liveness probes of registered peers (server mode)"
+    )]
+    pub probe_interval_secs: u16,
+
+    /// Unanswered probes before a peer is marked disconnected
+    #[arg(
+        long = "max-missed-probes",
+        default_value = "3",
+        help = "Consecutive unanswered probes before a peer is marked disconnected (server mode)"
+    )]
+    pub max_missed_probes: u32,
+
+    /// Time since last seen before an unreachable peer is purged
+    #[arg(
+        long = "peer-ttl",
+        default_value = "300",
+        help = "Seconds since a disconnected peer was last seen before it is removed (server mode)"
+    )]
+    pub peer_ttl_secs: u32,
+
+    /// Path to the node identity keypair file
+    #[arg(
+        long = "identity",
+        help = "Path to a Solana JSON keypair file used as the node identity (an ephemeral identity is used if omitted)"
+    )]
+    pub identity: Option<PathBuf>,
+
+    /// Generate the identity keypair file if it does not exist
+    #[arg(
+        long = "generate-identity",
+        help = "Generate and save a new keypair at the --identity path if the file does not exist"
+    )]
+    pub generate_identity: bool,
+
+    /// File the server's peer registry is saved to and restored from
+    #[arg(
+        long = "peer-store",
+        help = "JSON file the peer registry is saved to periodically and restored from on startup (server mode), or discovered peers are written to (discover and crawl modes)"
+    )]
+    pub peer_store: Option<PathBuf>,
+
+    /// Interval between peer registry snapshots
+    #[arg(
+        long = "peer-store-interval",
+        default_value = "60",
+        help = "Seconds between snapshots of the peer registry to --peer-store (server mode)"
+    )]
+    pub peer_store_interval_secs: u32,
+
+    /// JSON file with pubkey and CIDR allow/deny lists
+    #[arg(
+        long = "access-policy",
+        help = "JSON file with allow_pubkeys, deny_pubkeys, allow_cidrs and deny_cidrs lists (reloaded on SIGHUP)"
+    )]
+    pub access_policy_file: Option<PathBuf>,
+
+    /// Pubkeys allowed to connect, in addition to the access policy file
+    #[arg(
+        long = "allow-pubkey",
+        value_delimiter = ',',
+        help = "Comma-separated pubkeys allowed as peers; when set, all other pubkeys are refused"
+    )]
+    pub allow_pubkeys: Vec<Pubkey>,
+
+    /// Pubkeys refused as peers, in addition to the access policy file
+    #[arg(
+        long = "deny-pubkey",
+        value_delimiter = ',',
+        help = "Comma-separated pubkeys refused as peers"
+    )]
+    pub deny_pubkeys: Vec<Pubkey>,
+
+    /// Address ranges allowed to connect, in addition to the access policy file
+    #[arg(
+        long = "allow-cidr",
+        value_delimiter = ',',
+        help = "Comma-separated IP ranges (e.g. 10.0.0.0/8) allowed as peers; when set, all other addresses are refused"
+    )]
+    pub allow_cidrs: Vec<IpCidr>,
+
+    /// Address ranges refused as peers, in addition to the access policy file
+    #[arg(
+        long = "deny-cidr",
+        value_delimiter = ',',
+        help = "Comma-separated IP ranges refused as peers"
+    )]
+    pub deny_cidrs: Vec<IpCidr>,
+
+    /// Address the Prometheus metrics endpoint listens on
+    #[arg(
+        long = "metrics-addr",
+        help = "Serve Prometheus metrics over HTTP at this address (e.g. 127.0.0.1:9090), under /metrics"
+    )]
+    pub metrics_addr: Option<SocketAddr>,
+
+    /// OTLP/HTTP collector spans are exported to
+    #[arg(
+        long = "otlp-endpoint",
+        help = "Export trace spans to the OpenTelemetry collector at this OTLP/HTTP base URL (e.g. http://localhost:4318)"
+    )]
+    pub otlp_endpoint: Option<String>,
+
+    /// Format the handshake results are written in
+    #[arg(
+        long = "output",
+        help = "Write one record per peer in this format: json, ndjson, csv or table (client mode, single run)"
+    )]
+    pub output: Option<OutputFormat>,
+
+    /// File the handshake results are written to
+    #[arg(
+        long = "output-file",
+        help = "Write the --output records to this file instead of stdout"
+    )]
+    pub output_file: Option<PathBuf>,
 }
 
 impl Args {
@@ -110,11 +410,86 @@ impl Args {
         Duration::from_secs(self.timeout_secs.into())
     }
 
+    /// Get the handshake request acceptance window as a Duration
+    pub fn replay_window(&self) -> Duration {
+        Duration::from_secs(self.replay_window_secs.into())
+    }
+
+    /// Build the access policy from the policy file and command-line lists
+    pub fn access_policy(&self) -> Result<AccessPolicy, SolanaNodeError> {
+        let from_args = AccessPolicy {
+            allow_pubkeys: self.allow_pubkeys.iter().copied().collect(),
+            deny_pubkeys: self.deny_pubkeys.iter().copied().collect(),
+            allow_cidrs: self.allow_cidrs.clone(),
+            deny_cidrs: self.deny_cidrs.clone(),
+        };
+
+        match &self.access_policy_file {
+            Some(path) => Ok(AccessPolicy::load(path)?.merge(from_args)),
+            None => Ok(from_args),
+        }
+    }
+
+    /// Get the interval between peer registry snapshots as a Duration
+    pub fn peer_store_interval(&self) -> Duration {
+        Duration::from_secs(self.peer_store_interval_secs.into())
+    }
+
+    /// Get the server rate limits, with 0 meaning unlimited
+    pub fn rate_limit_config(&self) -> RateLimitConfig {
+        let limit = |rate: u32| (rate > 0).then_some(rate);
+        RateLimitConfig {
+            global_packets_per_sec: limit(self.max_packets_per_sec),
+            ip_packets_per_sec: limit(self.max_packets_per_ip),
+            pubkey_requests_per_sec: limit(self.max_requests_per_pubkey),
+            unverified_rejections_per_sec: limit(self.max_unverified_rejections_per_sec),
+        }
+    }
+
+    /// Get the limits on peer discovery
+    pub fn discovery_config(&self) -> DiscoveryConfig {
+        DiscoveryConfig {
+            max_depth: self.max_depth,
+            max_peers: self.max_peers,
+        }
+    }
+
+    /// Get the peer liveness probing settings
+    pub fn liveness_config(&self) -> LivenessConfig {
+        LivenessConfig {
+            probe_interval: Duration::from_secs(self.probe_interval_secs.into()),
+            max_missed_probes: self.max_missed_probes,
+            peer_ttl: Duration::from_secs(self.peer_ttl_secs.into()),
+        }
+    }
+
+    /// Get the handshake retry policy
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_retries,
+            backoff: self.retry_backoff,
+            base_delay: Duration::from_millis(self.retry_base_delay_ms),
+            max_delay: Duration::from_millis(self.retry_max_delay_ms),
+            jitter: self.retry_jitter,
+            deadline: self.retry_deadline_secs.map(Duration::from_secs),
+            non_retryable: self.no_retry_on.iter().copied().collect(),
+        }
+    }
+
     /// Get the continuous mode interval as a Duration  
     pub fn continuous_interval(&self) -> Duration {
         Duration::from_secs(self.interval_secs.into())
     }
 
+    /// Get where log lines go: stderr when handshake results are written to stdout
+    pub fn log_target(&self) -> LogTarget {
+        if self.output.is_some() && self.output_file.is_none() {
+            LogTarget::Stderr
+        } else {
+            LogTarget::Stdout
+        }
+    }
+
     /// Validate the provided arguments
     pub fn validate(&self) -> Result<(), String> {
         // Validate network timeout
@@ -127,15 +502,102 @@ impl Args {
         }
 
         // Validate max retries
+        if self.max_retries == 0 {
+            return Err("Maximum retries must be at least 1".to_string());
+        }
         if self.max_retries > 10 {
             return Err("Maximum retries cannot exceed 10".to_string());
         }
 
+        if self.retry_base_delay_ms > self.retry_max_delay_ms {
+            return Err("--retry-base-delay-ms cannot exceed --retry-max-delay-ms".to_string());
+        }
+
+        if self.retry_deadline_secs == Some(0) {
+            return Err("--retry-deadline-secs must be greater than 0".to_string());
+        }
+
+        // Validate replay window
+        if self.replay_window_secs == 0 {
+            return Err("Replay window must be greater than 0 seconds".to_string());
+        }
+
+        // Validate peer liveness settings
+        if self.probe_interval_secs == 0 {
+            return Err("Probe interval must be greater than 0 seconds".to_string());
+        }
+
+        if self.max_missed_probes == 0 {
+            return Err("Maximum missed probes must be at least 1".to_string());
+        }
+
+        // Validate batch concurrency
+        if self.concurrency == 0 {
+            return Err("Concurrency must be at least 1".to_string());
+        }
+
+        if self.concurrency > 4096 {
+            return Err("Concurrency cannot exceed 4096".to_string());
+        }
+
+        if self.peer_store_interval_secs == 0 {
+            return Err("Peer store interval must be greater than 0 seconds".to_string());
+        }
+
+        // Validate server packet handling
+        if self.workers == 0 || self.workers > 4096 {
+            return Err("Workers must be between 1 and 4096".to_string());
+        }
+
+        if self.queue_depth == 0 || self.queue_depth > 65536 {
+            return Err("Queue depth must be between 1 and 65536".to_string());
+        }
+
         // Validate peer addresses for client mode
         if matches!(self.mode, ApplicationMode::Client) && self.peer_addresses.is_empty() {
             return Err("Client mode requires at least one peer address (--peers)".to_string());
         }
 
+        // Validate discovery entrypoints and limits
+        if matches!(self.mode, ApplicationMode::Discover) && self.peer_addresses.is_empty() {
+            return Err("Discover mode requires at least one entrypoint (--peers)".to_string());
+        }
+
+        if matches!(self.mode, ApplicationMode::Crawl) && self.peer_addresses.is_empty() {
+            return Err("Crawl mode requires at least one entrypoint (--peers)".to_string());
+        }
+
+        if self.max_depth > 16 {
+            return Err("Maximum discovery depth cannot exceed 16".to_string());
+        }
+
+        if self.max_peers == 0 || self.max_peers > 65536 {
+            return Err("Maximum discovered peers must be between 1 and 65536".to_string());
+        }
+
+        // Validate handshake result output
+        if self.output.is_some() && !matches!(self.mode, ApplicationMode::Client) {
+            return Err("--output is only supported in client mode".to_string());
+        }
+
+        if self.output.is_some() && self.continuous {
+            return Err("--output cannot be combined with --continuous".to_string());
+        }
+
+        if self.output_file.is_some() && self.output.is_none() {
+            return Err("--output-file requires --output".to_string());
+        }
+
+        // Validate the trace collector URL
+        if let Some(endpoint) = &self.otlp_endpoint {
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                return Err(format!(
+                    "Invalid OTLP endpoint '{}'. Must be an http:// or https:// URL",
+                    endpoint
+                ));
+            }
+        }
+
         // Validate peer address formats (can be IP:port or hostname:port)
         for addr in &self.peer_addresses {
             if !addr.contains(':') {
@@ -146,11 +608,24 @@ impl Args {
             }
         }
 
+        if self.generate_identity && self.identity.is_none() {
+            return Err("--generate-identity requires an --identity path".to_string());
+        }
+
         // Validate continuous mode interval
         if self.continuous && self.interval_secs < 10 {
             return Err("Continuous mode interval must be at least 10 seconds".to_string());
         }
 
+        if let Some(addr) = self.advertise_addr {
+            if addr.ip().is_unspecified() || addr.port() == 0 {
+                return Err(format!(
+                    "Advertise address '{}' must have a specific IP and port",
+                    addr
+                ));
+            }
+        }
+
         Ok(())
     }
 }
@@ -212,6 +687,10 @@ pub enum ApplicationMode {
     Client,
     /// Server mode - listen and respond to handshakes
     Server,
+    /// Discover mode - find peers outwards from entrypoints
+    Discover,
+    /// Crawl mode - discover peers and report the cluster topology
+    Crawl,
 }
 
 impl FromStr for ApplicationMode {
@@ -221,8 +700,10 @@ impl FromStr for ApplicationMode {
         match s.to_lowercase().as_str() {
             "client" | "c" => Ok(ApplicationMode::Client),
             "server" | "s" | "listen" => Ok(ApplicationMode::Server),
+            "discover" | "d" | "discovery" => Ok(ApplicationMode::Discover),
+            "crawl" | "crawler" => Ok(ApplicationMode::Crawl),
             _ => Err(format!(
-                "Invalid application mode: '{}'. Valid options: client, server",
+                "Invalid application mode: '{}'. Valid options: client, server, discover, crawl",
                 s
             )),
         }
@@ -321,6 +802,10 @@ mod tests {
             "server".parse::<ApplicationMode>().unwrap(),
             ApplicationMode::Server
         );
+        assert_eq!(
+            "discover".parse::<ApplicationMode>().unwrap(),
+            ApplicationMode::Discover
+        );
         assert!("invalid".parse::<ApplicationMode>().is_err());
     }
 
@@ -332,11 +817,49 @@ mod tests {
             peer_addresses: vec!["127.0.0.1:8001".to_string()],
             timeout_secs: 30,
             max_retries: 3,
+            retry_backoff: Backoff::Exponential,
+            retry_base_delay_ms: 1000,
+            retry_max_delay_ms: 30000,
+            retry_jitter: Jitter::Full,
+            retry_deadline_secs: None,
+            no_retry_on: vec![],
             mode: ApplicationMode::Client,
             log_level: LogLevel::Info,
             log_format: LogFormat::Pretty,
             continuous: false,
             interval_secs: 60,
+            handshake_mode: HandshakeMode::Legacy,
+            shred_version: 0,
+            advertise_addr: None,
+            required_capabilities: None,
+            concurrency: 64,
+            max_depth: 2,
+            max_peers: 256,
+            report_dir: PathBuf::from("crawl-report"),
+            workers: 64,
+            queue_depth: 1024,
+            max_packets_per_sec: 10000,
+            max_packets_per_ip: 200,
+            max_requests_per_pubkey: 20,
+            max_unverified_rejections_per_sec: 100,
+            reject_policy: RejectPolicy::Respond,
+            replay_window_secs: 120,
+            probe_interval_secs: 30,
+            max_missed_probes: 3,
+            peer_ttl_secs: 300,
+            identity: None,
+            generate_identity: false,
+            peer_store: None,
+            peer_store_interval_secs: 60,
+            access_policy_file: None,
+            allow_pubkeys: vec![],
+            deny_pubkeys: vec![],
+            allow_cidrs: vec![],
+            deny_cidrs: vec![],
+            metrics_addr: None,
+            otlp_endpoint: None,
+            output: None,
+            output_file: None,
         };
 
         assert!(args.validate().is_ok());
@@ -366,11 +889,48 @@ mod tests {
             .unwrap_err()
             .contains("Maximum retries cannot exceed 10"));
 
+        // Every handshake needs at least one attempt
+        args.max_retries = 0;
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("Maximum retries must be at least 1"));
+
         // Valid retry count
         args.max_retries = 5;
         assert!(args.validate().is_ok());
     }
 
+    #[test]
+    fn test_args_retry_policy() {
+        let mut args = create_valid_args();
+        assert_eq!(args.retry_policy(), RetryPolicy::from(3));
+
+        args.retry_backoff = Backoff::Linear;
+        args.retry_jitter = Jitter::Decorrelated;
+        args.retry_base_delay_ms = 250;
+        args.retry_deadline_secs = Some(20);
+        args.no_retry_on = vec![ErrorKind::ReceiveTimeout, ErrorKind::RemoteRejected];
+        assert!(args.validate().is_ok());
+
+        let policy = args.retry_policy();
+        assert_eq!(policy.backoff, Backoff::Linear);
+        assert_eq!(policy.jitter, Jitter::Decorrelated);
+        assert_eq!(policy.base_delay, Duration::from_millis(250));
+        assert_eq!(policy.deadline, Some(Duration::from_secs(20)));
+        assert!(policy.non_retryable.contains(&ErrorKind::RemoteRejected));
+
+        args.retry_base_delay_ms = 60_000;
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("cannot exceed --retry-max-delay-ms"));
+
+        args.retry_base_delay_ms = 250;
+        args.retry_deadline_secs = Some(0);
+        assert!(args.validate().is_err());
+    }
+
     #[test]
     fn test_args_validation_server_mode() {
         let mut args = create_valid_args();
@@ -397,6 +957,236 @@ mod tests {
         assert!(args.validate().is_ok());
     }
 
+    #[test]
+    fn test_args_validation_advertise_addr() {
+        let mut args = create_valid_args();
+        args.advertise_addr = Some("203.0.113.7:8001".parse().unwrap());
+        assert!(args.validate().is_ok());
+
+        args.advertise_addr = Some("0.0.0.0:8001".parse().unwrap());
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("must have a specific IP and port"));
+
+        args.advertise_addr = Some("203.0.113.7:0".parse().unwrap());
+        assert!(args.validate().is_err());
+    }
+
+    #[test]
+    fn test_args_validation_replay_window() {
+        let mut args = create_valid_args();
+        assert_eq!(args.replay_window(), Duration::from_secs(120));
+
+        args.replay_window_secs = 0;
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("Replay window must be greater than 0 seconds"));
+    }
+
+    #[test]
+    fn test_args_validation_liveness() {
+        let mut args = create_valid_args();
+        let config = args.liveness_config();
+        assert_eq!(config.probe_interval, Duration::from_secs(30));
+        assert_eq!(config.max_missed_probes, 3);
+        assert_eq!(config.peer_ttl, Duration::from_secs(300));
+
+        args.probe_interval_secs = 0;
+        assert!(args.validate().is_err());
+
+        args.probe_interval_secs = 30;
+        args.max_missed_probes = 0;
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("Maximum missed probes must be at least 1"));
+    }
+
+    #[test]
+    fn test_args_validation_concurrency() {
+        let mut args = create_valid_args();
+
+        args.concurrency = 0;
+        assert!(args.validate().is_err());
+
+        args.concurrency = 5000;
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("Concurrency cannot exceed 4096"));
+
+        args.concurrency = 256;
+        assert!(args.validate().is_ok());
+    }
+
+    #[test]
+    fn test_args_validation_discover_mode() {
+        let mut args = create_valid_args();
+        args.mode = ApplicationMode::Discover;
+        assert!(args.validate().is_ok());
+        assert_eq!(args.discovery_config().max_depth, 2);
+        assert_eq!(args.discovery_config().max_peers, 256);
+
+        args.max_depth = 17;
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("Maximum discovery depth cannot exceed 16"));
+
+        args.max_depth = 3;
+        args.max_peers = 0;
+        assert!(args.validate().is_err());
+
+        args.max_peers = 1000;
+        args.peer_addresses.clear();
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("Discover mode requires at least one entrypoint"));
+
+        args.mode = ApplicationMode::Crawl;
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("Crawl mode requires at least one entrypoint"));
+    }
+
+    #[test]
+    fn test_args_validation_otlp_endpoint() {
+        let mut args = create_valid_args();
+        args.otlp_endpoint = Some("http://localhost:4318".to_string());
+        assert!(args.validate().is_ok());
+
+        args.otlp_endpoint = Some("localhost:4318".to_string());
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("Must be an http:// or https:// URL"));
+    }
+
+    #[test]
+    fn test_args_validation_output() {
+        let mut args = create_valid_args();
+        assert_eq!(args.log_target(), LogTarget::Stdout);
+
+        args.output = Some(OutputFormat::Ndjson);
+        assert!(args.validate().is_ok());
+        // Results on stdout move the logs out of the way
+        assert_eq!(args.log_target(), LogTarget::Stderr);
+
+        args.output_file = Some(PathBuf::from("results.ndjson"));
+        assert!(args.validate().is_ok());
+        assert_eq!(args.log_target(), LogTarget::Stdout);
+
+        args.continuous = true;
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("cannot be combined with --continuous"));
+
+        args.continuous = false;
+        args.mode = ApplicationMode::Server;
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("only supported in client mode"));
+
+        args.mode = ApplicationMode::Client;
+        args.output = None;
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("--output-file requires --output"));
+    }
+
+    #[test]
+    fn test_args_validation_workers_and_queue_depth() {
+        let mut args = create_valid_args();
+
+        args.workers = 0;
+        assert!(args.validate().is_err());
+
+        args.workers = 8;
+        args.queue_depth = 0;
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("Queue depth must be between 1 and 65536"));
+
+        args.queue_depth = 100_000;
+        assert!(args.validate().is_err());
+
+        args.queue_depth = 4096;
+        assert!(args.validate().is_ok());
+    }
+
+    #[test]
+    fn test_args_validation_peer_store_interval() {
+        let mut args = create_valid_args();
+        assert_eq!(args.peer_store_interval(), Duration::from_secs(60));
+
+        args.peer_store_interval_secs = 0;
+        assert!(args
+            .validate()
+            .unwrap_err()
+            .contains("Peer store interval must be greater than 0 seconds"));
+    }
+
+    #[test]
+    fn test_rate_limit_config() {
+        let mut args = create_valid_args();
+        let config = args.rate_limit_config();
+        assert_eq!(config.global_packets_per_sec, Some(10000));
+        assert_eq!(config.ip_packets_per_sec, Some(200));
+        assert_eq!(config.pubkey_requests_per_sec, Some(20));
+        assert_eq!(config.unverified_rejections_per_sec, Some(100));
+
+        args.max_packets_per_ip = 0;
+        assert_eq!(args.rate_limit_config().ip_packets_per_sec, None);
+    }
+
+    #[test]
+    fn test_access_policy_from_args_and_file() {
+        let mut args = create_valid_args();
+        assert!(args.access_policy().unwrap().is_open());
+
+        let from_file = Pubkey::new_unique();
+        let from_cli = Pubkey::new_unique();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(
+            file.path(),
+            format!(r#"{{"deny_pubkeys": ["{}"]}}"#, from_file),
+        )
+        .unwrap();
+
+        args.access_policy_file = Some(file.path().to_path_buf());
+        args.deny_pubkeys = vec![from_cli];
+        args.allow_cidrs = vec!["10.0.0.0/8".parse().unwrap()];
+
+        let policy = args.access_policy().unwrap();
+        assert!(policy.deny_pubkeys.contains(&from_file));
+        assert!(policy.deny_pubkeys.contains(&from_cli));
+        assert_eq!(policy.allow_cidrs.len(), 1);
+
+        args.access_policy_file = Some(PathBuf::from("/nonexistent/policy.json"));
+        assert!(args.access_policy().is_err());
+    }
+
+    #[test]
+    fn test_args_validation_generate_identity() {
+        let mut args = create_valid_args();
+        args.generate_identity = true;
+
+        let result = args.validate();
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("requires an --identity path"));
+
+        args.identity = Some(PathBuf::from("identity.json"));
+        assert!(args.validate().is_ok());
+    }
+
     #[test]
     fn test_network_timeout_conversion() {
         let args = create_valid_args();
@@ -467,7 +1257,12 @@ mod tests {
         }
 
         // Ensure we handle all ApplicationMode variants
-        for mode in [ApplicationMode::Client, ApplicationMode::Server] {
+        for mode in [
+            ApplicationMode::Client,
+            ApplicationMode::Server,
+            ApplicationMode::Discover,
+            ApplicationMode::Crawl,
+        ] {
             match mode {
                 ApplicationMode::Client => {
                     assert_eq!("client".parse::<ApplicationMode>().unwrap(), mode);
@@ -475,6 +1270,12 @@ mod tests {
                 ApplicationMode::Server => {
                     assert_eq!("server".parse::<ApplicationMode>().unwrap(), mode);
                 }
+                ApplicationMode::Discover => {
+                    assert_eq!("discover".parse::<ApplicationMode>().unwrap(), mode);
+                }
+                ApplicationMode::Crawl => {
+                    assert_eq!("crawl".parse::<ApplicationMode>().unwrap(), mode);
+                }
             }
         }
 
@@ -504,11 +1305,49 @@ mod tests {
             peer_addresses: vec!["127.0.0.1:8001".to_string()],
             timeout_secs: 30,
             max_retries: 3,
+            retry_backoff: Backoff::Exponential,
+            retry_base_delay_ms: 1000,
+            retry_max_delay_ms: 30000,
+            retry_jitter: Jitter::Full,
+            retry_deadline_secs: None,
+            no_retry_on: vec![],
             mode: ApplicationMode::Client,
             log_level: LogLevel::Info,
             log_format: LogFormat::Pretty,
             continuous: false,
             interval_secs: 60,
+            handshake_mode: HandshakeMode::Legacy,
+            shred_version: 0,
+            advertise_addr: None,
+            required_capabilities: None,
+            concurrency: 64,
+            max_depth: 2,
+            max_peers: 256,
+            report_dir: PathBuf::from("crawl-report"),
+            workers: 64,
+            queue_depth: 1024,
+            max_packets_per_sec: 10000,
+            max_packets_per_ip: 200,
+            max_requests_per_pubkey: 20,
+            max_unverified_rejections_per_sec: 100,
+            reject_policy: RejectPolicy::Respond,
+            replay_window_secs: 120,
+            probe_interval_secs: 30,
+            max_missed_probes: 3,
+            peer_ttl_secs: 300,
+            identity: None,
+            generate_identity: false,
+            peer_store: None,
+            peer_store_interval_secs: 60,
+            access_policy_file: None,
+            allow_pubkeys: vec![],
+            deny_pubkeys: vec![],
+            allow_cidrs: vec![],
+            deny_cidrs: vec![],
+            metrics_addr: None,
+            otlp_endpoint: None,
+            output: None,
+            output_file: None,
         }
     }
 }
@@ -10,6 +10,9 @@ pub mod arguments_parser;
 /// Structured logging and telemetry infrastructure
 pub mod telemetry;
 
+/// Prometheus metrics for handshakes, gossip traffic and peers
+pub mod metrics;
+
 /// Solana-specific networking and protocol implementations
 pub mod solana {
     /// Core gossip node implementation for Solana P2P networking
@@ -21,14 +24,73 @@ pub mod solana {
     /// Domain models and data structures for Solana network communication
     pub mod domain;
 
-    pub use domain::{network, GossipNodeInfo, DEFAULT_CAPABILITIES, DEFAULT_PROTOCOL_VERSION};
+    /// Encoding and decoding of the Solana gossip wire protocol
+    pub mod codec;
+
+    /// Ping/pong challenge tracking for peer address verification
+    pub mod ping_cache;
+
+    /// Loading and generating the node's persistent identity keypair
+    pub mod identity;
+
+    /// Rejection of stale and replayed handshake requests
+    pub mod replay_cache;
+
+    /// Thread-safe registry of known peers shared with the gossip node
+    pub mod peer_registry;
+
+    /// Periodic probing, disconnection and expiry of registered peers
+    pub mod liveness;
+
+    /// Peer lifecycle events published by the gossip node
+    pub mod events;
+
+    /// Flood protection with per-source, per-pubkey and global rate limits
+    pub mod rate_limit;
+
+    /// Counters of received, failed and dropped packets
+    pub mod stats;
+
+    /// Reloadable access control by peer pubkey and IP range
+    pub mod access_policy;
+
+    /// Crawling the cluster outwards from entrypoints
+    pub mod discovery;
+
+    /// JSON, CSV and Graphviz reports of a crawled cluster
+    pub mod topology;
+
+    /// Machine-readable results of client-mode handshakes
+    pub mod handshake_report;
+
+    /// Backoff, jitter and deadlines for retrying failed handshakes
+    pub mod retry_policy;
+
+    pub use access_policy::{AccessControl, AccessPolicy, IpCidr};
+    pub use discovery::{DiscoveredNode, DiscoveryConfig, DiscoveryReport, PeerDiscovery};
+    pub use domain::{
+        network, AdvertisedPeer, GossipNodeInfo, HandshakeOutcome, PeerDetails,
+        DEFAULT_CAPABILITIES, DEFAULT_PROTOCOL_VERSION,
+    };
+    pub use domain::{Capability, CapabilitySet, HandshakeMode, MessageSignature};
+    pub use domain::{ErrorKind, RejectReason};
     pub use domain::{HandshakeRequest, HandshakeResponse, SolanaNodeError};
+    pub use domain::{ProtocolVersion, VersionRange};
+    pub use events::PeerEvent;
     /// Module exports for public API
-    pub use gossip_node::GossipNode;
-    pub use handshake_client::{HandshakeClient, HandshakeResult};
+    pub use gossip_node::{GossipNode, GossipNodeBuilder, RunSummary};
+    pub use handshake_client::{HandshakeClient, HandshakeClientBuilder, HandshakeResult};
+    pub use handshake_report::{HandshakeRecord, HandshakeReport, OutputFormat};
+    pub use liveness::LivenessConfig;
+    pub use peer_registry::{PeerFilter, PeerRegistry};
+    pub use rate_limit::{RateLimitConfig, RejectPolicy};
+    pub use retry_policy::{Backoff, Jitter, RetryPolicy};
+    pub use stats::{NodeStats, NodeStatsSnapshot};
+    pub use topology::{Reachability, TopologyReport};
 }
 
 // Re-export commonly used types for easier access
 pub use arguments_parser::Args;
+pub use metrics::{Metrics, MetricsServer};
 pub use solana::{GossipNode, HandshakeClient, HandshakeRequest, HandshakeResponse};
 pub use telemetry::setup_telemetry;
@@ -1,10 +1,14 @@
 use clap::Parser;
 use p2p_solana_handshake::{
     arguments_parser::{ApplicationMode, Args},
-    solana::{GossipNode, HandshakeClient},
-    telemetry::{log_operation_failure, log_operation_success, setup_telemetry, TelemetryConfig},
+    metrics::{Metrics, MetricsServer},
+    solana::{handshake_report::EXIT_ALL_SUCCEEDED, HandshakeReport},
+    solana::{identity::load_identity, AccessControl, GossipNode, HandshakeClient, HandshakeMode},
+    solana::{DiscoveryReport, PeerDiscovery, TopologyReport},
+    telemetry::{log_operation_failure, log_operation_success, setup_telemetry},
+    telemetry::{OtlpConfig, TelemetryConfig},
 };
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::time::Instant;
 use tracing::{error, info, warn};
@@ -22,8 +26,17 @@ async fn main() -> anyhow::Result<()> {
 
     // Setup telemetry based on configuration
     let telemetry_config = TelemetryConfig::from_env();
-    setup_telemetry(args.log_level.clone(), args.log_format.clone())
-        .map_err(|e| anyhow::anyhow!("Failed to setup telemetry: {}", e))?;
+    let otlp = args
+        .otlp_endpoint
+        .as_ref()
+        .map(|endpoint| OtlpConfig::new(endpoint, &telemetry_config.service_name));
+    let telemetry = setup_telemetry(
+        args.log_level.clone(),
+        args.log_format.clone(),
+        args.log_target(),
+        otlp.as_ref(),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to setup telemetry: {}", e))?;
 
     info!(
         service_name = %telemetry_config.service_name,
@@ -37,25 +50,59 @@ async fn main() -> anyhow::Result<()> {
 
     let start_time = Instant::now();
 
-    // Generate a unique node public key for this session
-    let node_pubkey = Pubkey::new_unique();
-    info!(node_pubkey = %node_pubkey, "Generated node public key");
+    // Load the persistent node identity, or fall back to one for this session only
+    let identity = match &args.identity {
+        Some(path) => load_identity(path, args.generate_identity)
+            .map_err(|e| anyhow::anyhow!("Failed to load node identity: {}", e))?,
+        None => {
+            let identity = Keypair::new();
+            warn!(
+                node_pubkey = %identity.pubkey(),
+                "No --identity given, using an ephemeral node identity"
+            );
+            identity
+        }
+    };
+
+    let access = AccessControl::new(
+        args.access_policy()
+            .map_err(|e| anyhow::anyhow!("Failed to load access policy: {}", e))?,
+    );
+    spawn_access_policy_reload(&args, &access);
+
+    let metrics = Metrics::new();
+    spawn_metrics_server(&args, &metrics).await?;
 
+    // Client mode reports failed handshakes through the exit code, other modes exit 0
     let result = match args.mode {
-        ApplicationMode::Client => run_client_mode(&args, node_pubkey).await,
-        ApplicationMode::Server => run_server_mode(&args, node_pubkey).await,
+        ApplicationMode::Client => run_client_mode(&args, identity, access, &metrics).await,
+        ApplicationMode::Server => run_server_mode(&args, identity, access, &metrics)
+            .await
+            .map(|_| EXIT_ALL_SUCCEEDED),
+        ApplicationMode::Discover => discover_peers(&args, identity, access, &metrics)
+            .await
+            .map(|_| EXIT_ALL_SUCCEEDED),
+        ApplicationMode::Crawl => run_crawl_mode(&args, identity, access, &metrics)
+            .await
+            .map(|_| EXIT_ALL_SUCCEEDED),
     };
 
     let duration = start_time.elapsed();
 
     match result {
-        Ok(_) => {
+        Ok(exit_code) => {
             log_operation_success("application", duration);
-            info!("Application completed successfully");
+            info!(exit_code = exit_code, "Application completed successfully");
+            if exit_code != EXIT_ALL_SUCCEEDED {
+                drop(telemetry);
+                std::process::exit(exit_code);
+            }
         }
         Err(e) => {
             log_operation_failure("application", e.as_ref(), duration);
             error!(error = %e, "Application failed");
+            // Exiting skips destructors, so flush exported spans first
+            drop(telemetry);
             std::process::exit(1);
         }
     }
@@ -64,73 +111,205 @@ async fn main() -> anyhow::Result<()> {
 }
 
 /// Run the application in client mode - perform handshakes with peers
-async fn run_client_mode(args: &Args, node_pubkey: Pubkey) -> anyhow::Result<()> {
+///
+/// Returns the exit code reflecting how many handshakes of a single batch failed.
+async fn run_client_mode(
+    args: &Args,
+    identity: Keypair,
+    access: AccessControl,
+    metrics: &Metrics,
+) -> anyhow::Result<i32> {
     info!(
         peer_count = args.peer_addresses.len(),
         continuous = args.continuous,
+        concurrency = args.concurrency,
+        handshake_mode = %args.handshake_mode,
         "Running in client mode"
     );
 
-    // Create a handshake client
-    let handshake_client = HandshakeClient::with_config(
-        &args.bind_address,
-        node_pubkey,
-        args.network_id.to_string(),
-        args.network_timeout(),
-        args.max_retries,
-    )
-    .await
-    .map_err(|e| anyhow::anyhow!("Failed to create handshake client: {}", e))?;
+    let handshake_client = create_handshake_client(args, identity, access, metrics).await?;
 
     if args.continuous {
-        run_continuous_client_mode(&handshake_client, args).await
+        run_continuous_client_mode(&handshake_client, args)
+            .await
+            .map(|_| EXIT_ALL_SUCCEEDED)
     } else {
         run_single_client_mode(&handshake_client, args).await
     }
 }
 
+/// Create a handshake client configured from the command-line arguments
+async fn create_handshake_client(
+    args: &Args,
+    identity: Keypair,
+    access: AccessControl,
+    metrics: &Metrics,
+) -> anyhow::Result<HandshakeClient> {
+    let builder = HandshakeClient::builder()
+        .bind_addr(&args.bind_address)
+        .identity(identity)
+        .network_id(args.network_id.to_string())
+        .operation_timeout(args.network_timeout())
+        .retry_policy(args.retry_policy())
+        .handshake_mode(args.handshake_mode)
+        .shred_version(args.shred_version)
+        .concurrency(args.concurrency)
+        .required_capabilities(args.required_capabilities.unwrap_or_default())
+        .access_control(access)
+        .metrics(metrics.clone());
+    let builder = match args.advertise_addr {
+        Some(addr) => builder.advertise_addr(addr),
+        None => builder,
+    };
+    builder
+        .build()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to create handshake client: {}", e))
+}
+
+/// Run the application in discover mode - find peers outwards from the entrypoints
+async fn discover_peers(
+    args: &Args,
+    identity: Keypair,
+    access: AccessControl,
+    metrics: &Metrics,
+) -> anyhow::Result<DiscoveryReport> {
+    info!(
+        entrypoint_count = args.peer_addresses.len(),
+        max_depth = args.max_depth,
+        max_peers = args.max_peers,
+        handshake_mode = %args.handshake_mode,
+        "Discovering peers from entrypoints"
+    );
+
+    if args.handshake_mode == HandshakeMode::Legacy {
+        warn!("Legacy handshakes do not share contact info, only the entrypoints will be reached; use --handshake-mode gossip to discover validators");
+    }
+
+    let handshake_client = create_handshake_client(args, identity, access, metrics).await?;
+    let entrypoints = resolve_peer_addresses(&args.peer_addresses).await?;
+
+    let discovery = PeerDiscovery::new(args.discovery_config());
+    metrics.observe_peer_registry(discovery.peer_registry());
+    let report = discovery.run(&handshake_client, entrypoints).await;
+
+    for peer_info in report.peers() {
+        info!(
+            peer_addr = %peer_info.gossip_addr,
+            peer_pubkey = %peer_info.peer_pubkey,
+            protocol_version = %peer_info.protocol_version,
+            "Discovered peer"
+        );
+    }
+    for (peer_addr, e) in report.failures() {
+        warn!(peer_addr = %peer_addr, error = %e, "Handshake with discovered peer failed");
+    }
+
+    info!(
+        attempted = report.attempted(),
+        discovered_peers = report.peers().count(),
+        depth_reached = report.depth_reached,
+        unvisited = report.unvisited.len(),
+        "Discovery completed"
+    );
+
+    if let Some(path) = &args.peer_store {
+        let saved = discovery
+            .peer_registry()
+            .save(path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to save discovered peers: {}", e))?;
+        info!(path = %path.display(), saved_peers = saved, "Discovered peers saved");
+    }
+
+    Ok(report)
+}
+
+/// Run the application in crawl mode - discover peers and write a topology report
+async fn run_crawl_mode(
+    args: &Args,
+    identity: Keypair,
+    access: AccessControl,
+    metrics: &Metrics,
+) -> anyhow::Result<()> {
+    let discovery = discover_peers(args, identity, access, metrics).await?;
+    let report = TopologyReport::from_discovery(args.network_id.to_string(), &discovery);
+
+    report
+        .write(&args.report_dir)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to write topology report: {}", e))?;
+
+    info!(
+        report_dir = %args.report_dir.display(),
+        nodes = report.nodes.len(),
+        reachable_nodes = report.reachable_count(),
+        edges = report.edges.len(),
+        "Topology report written"
+    );
+
+    Ok(())
+}
+
 /// Run client in single-shot mode
-async fn run_single_client_mode(client: &HandshakeClient, args: &Args) -> anyhow::Result<()> {
+async fn run_single_client_mode(client: &HandshakeClient, args: &Args) -> anyhow::Result<i32> {
     info!("Performing single batch of handshakes");
 
     // Resolve hostnames to SocketAddrs
     let peer_addresses = resolve_peer_addresses(&args.peer_addresses).await?;
 
     let start_time = Instant::now();
-    let results = client.perform_batch_handshakes(peer_addresses).await;
+    let outcomes = client
+        .perform_batch_handshake_outcomes(peer_addresses)
+        .await;
     let duration = start_time.elapsed();
 
-    let successful_count = results.iter().filter(|(_, result)| result.is_ok()).count();
+    let successful_count = outcomes
+        .iter()
+        .filter(|outcome| outcome.result.is_ok())
+        .count();
 
     info!(
-        total_peers = results.len(),
+        total_peers = outcomes.len(),
         successful_handshakes = successful_count,
         duration_ms = duration.as_millis(),
         "Batch handshakes completed"
     );
 
     // Log individual results
-    for (peer_addr, result) in results {
-        match result {
-            Ok(peer_info) => {
+    for outcome in &outcomes {
+        match &outcome.result {
+            Ok(details) => {
+                let peer_info = &details.peer_info;
                 info!(
-                    peer_addr = %peer_addr,
+                    peer_addr = %outcome.peer_addr,
                     peer_pubkey = %peer_info.peer_pubkey,
                     protocol_version = %peer_info.protocol_version,
+                    negotiated_version = ?peer_info.negotiated_version,
+                    attempts = outcome.attempts,
                     "Handshake successful"
                 );
             }
             Err(e) => {
                 warn!(
-                    peer_addr = %peer_addr,
+                    peer_addr = %outcome.peer_addr,
                     error = %e,
+                    attempts = outcome.attempts,
                     "Handshake failed"
                 );
             }
         }
     }
 
-    Ok(())
+    let report = HandshakeReport::from_outcomes(&outcomes);
+    if let Some(format) = args.output {
+        report
+            .write(format, args.output_file.as_deref())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to write handshake results: {}", e))?;
+    }
+
+    Ok(report.exit_code())
 }
 
 /// Run client in continuous mode
@@ -146,15 +325,28 @@ async fn run_continuous_client_mode(client: &HandshakeClient, args: &Args) -> an
     let mut interval = tokio::time::interval(args.continuous_interval());
     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
     loop {
-        interval.tick().await;
+        tokio::select! {
+            _ = &mut shutdown => {
+                info!("Shutdown requested, stopping continuous client");
+                return Ok(());
+            }
+            _ = interval.tick() => {}
+        }
 
         info!("Starting continuous handshake cycle");
         let start_time = Instant::now();
 
-        let results = client
-            .perform_batch_handshakes(peer_addresses.clone())
-            .await;
+        let results = tokio::select! {
+            _ = &mut shutdown => {
+                info!("Shutdown requested, abandoning in-progress handshake cycle");
+                return Ok(());
+            }
+            results = client.perform_batch_handshakes(peer_addresses.clone()) => results,
+        };
         let duration = start_time.elapsed();
 
         let successful_count = results.iter().filter(|(_, result)| result.is_ok()).count();
@@ -169,14 +361,37 @@ async fn run_continuous_client_mode(client: &HandshakeClient, args: &Args) -> an
 }
 
 /// Run the application in server mode - listen for incoming handshakes
-async fn run_server_mode(args: &Args, node_pubkey: Pubkey) -> anyhow::Result<()> {
+async fn run_server_mode(
+    args: &Args,
+    identity: Keypair,
+    access: AccessControl,
+    metrics: &Metrics,
+) -> anyhow::Result<()> {
     info!("Running in server mode - listening for handshakes");
 
     // Create a gossip node for listening
-    let mut gossip_node =
-        GossipNode::new(&args.bind_address, node_pubkey, args.network_id.to_string())
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to create gossip node: {}", e))?;
+    let builder = GossipNode::builder()
+        .bind_addr(&args.bind_address)
+        .identity(identity)
+        .network_id(args.network_id.to_string())
+        .replay_window(args.replay_window())
+        .liveness(args.liveness_config())
+        .rate_limits(args.rate_limit_config())
+        .reject_policy(args.reject_policy)
+        .access_control(access)
+        .workers(args.workers)
+        .queue_depth(args.queue_depth)
+        .snapshot_interval(args.peer_store_interval())
+        .metrics(metrics.clone());
+    let builder = match &args.peer_store {
+        Some(path) => builder.peer_store(path),
+        None => builder,
+    };
+    let mut gossip_node = builder
+        .build()
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to create gossip node: {}", e))?;
+    let node_pubkey = gossip_node.node_pubkey();
 
     let local_addr = gossip_node
         .local_addr()
@@ -188,15 +403,108 @@ async fn run_server_mode(args: &Args, node_pubkey: Pubkey) -> anyhow::Result<()>
         "Gossip node listening for handshakes"
     );
 
-    // Run the gossip node event loop
-    gossip_node
-        .run()
+    // Run the gossip node event loop until SIGINT or SIGTERM
+    let summary = gossip_node
+        .run_until(shutdown_signal())
         .await
         .map_err(|e| anyhow::anyhow!("Gossip node error: {}", e))?;
 
+    info!(
+        uptime_secs = summary.uptime.as_secs(),
+        packets_received = summary.packets_received,
+        packets_failed = summary.packets_failed,
+        registered_peers = summary.peers.len(),
+        "Gossip node shut down cleanly"
+    );
+
+    Ok(())
+}
+
+/// Serve `metrics` on `--metrics-addr`, if given, for the rest of the run
+async fn spawn_metrics_server(args: &Args, metrics: &Metrics) -> anyhow::Result<()> {
+    let Some(addr) = args.metrics_addr else {
+        return Ok(());
+    };
+
+    let server = MetricsServer::bind(addr, metrics.clone())
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to start metrics endpoint: {}", e))?;
+    tokio::spawn(async move {
+        if let Err(e) = server.run().await {
+            error!(error = %e, "Metrics endpoint stopped");
+        }
+    });
+
     Ok(())
 }
 
+/// Reload the access policy file whenever the process receives SIGHUP
+///
+/// A policy that fails to load is logged and the current one kept.
+fn spawn_access_policy_reload(args: &Args, access: &AccessControl) {
+    #[cfg(unix)]
+    if let Some(path) = args.access_policy_file.clone() {
+        let args = args.clone();
+        let access = access.clone();
+        tokio::spawn(async move {
+            let mut hangup =
+                match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+                    Ok(hangup) => hangup,
+                    Err(e) => {
+                        error!(error = %e, "Failed to listen for SIGHUP");
+                        return;
+                    }
+                };
+
+            while hangup.recv().await.is_some() {
+                match args.access_policy() {
+                    Ok(policy) => {
+                        access.replace(policy);
+                        info!(path = %path.display(), "Access policy reloaded");
+                    }
+                    Err(e) => {
+                        warn!(error = %e, "Failed to reload access policy, keeping the current one");
+                    }
+                }
+            }
+        });
+    }
+
+    #[cfg(not(unix))]
+    let _ = (args, access);
+}
+
+/// Complete when the process receives SIGINT (Ctrl+C) or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!(error = %e, "Failed to listen for Ctrl+C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received SIGINT"),
+        _ = terminate => info!("Received SIGTERM"),
+    }
+}
+
 /// Resolve hostname:port strings to SocketAddr
 async fn resolve_peer_addresses(peer_addresses: &[String]) -> anyhow::Result<Vec<SocketAddr>> {
     let mut resolved_addrs = Vec::new();
@@ -0,0 +1,530 @@
+//! Prometheus metrics for handshakes, gossip traffic and registered peers
+//!
+//! `Metrics` is a cheap, cloneable handle shared by the handshake client and
+//! the gossip node. Handshake outcomes and packet sizes are recorded as they
+//! happen; packet counters and peer gauges are read from the node's
+//! `NodeStats` and `PeerRegistry` when scraped, so they always agree with
+//! what the node reports itself. `MetricsServer` serves everything in the
+//! Prometheus text exposition format.
+
+use crate::solana::domain::{ConnectionStatus, SolanaNodeError};
+use crate::solana::peer_registry::PeerRegistry;
+use crate::solana::stats::NodeStats;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+/// Path the metrics are served on
+pub const METRICS_PATH: &str = "/metrics";
+
+/// Content type of the Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Longest request head read from a scraper
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// How long a scraper may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Pause after a failed accept, e.g. when out of file descriptors
+const ACCEPT_ERROR_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Upper bounds of the handshake latency buckets, in seconds
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Upper bounds of the retries-per-handshake buckets
+const RETRY_BUCKETS: &[f64] = &[0.0, 1.0, 2.0, 3.0, 5.0, 10.0];
+
+/// Upper bounds of the packet size buckets, in bytes
+const PACKET_SIZE_BUCKETS: &[f64] = &[64.0, 128.0, 256.0, 512.0, 768.0, 1024.0, 1232.0];
+
+/// Metrics shared between the handshake client, the gossip node and the exporter
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    handshakes_attempted: AtomicU64,
+    handshakes_succeeded: AtomicU64,
+    handshakes_failed: Mutex<BTreeMap<&'static str, u64>>,
+    handshake_latency: Histogram,
+    handshake_retries: Histogram,
+    packet_size: Histogram,
+    node_stats: Mutex<Option<NodeStats>>,
+    peer_registry: Mutex<Option<PeerRegistry>>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                handshakes_attempted: AtomicU64::new(0),
+                handshakes_succeeded: AtomicU64::new(0),
+                handshakes_failed: Mutex::new(BTreeMap::new()),
+                handshake_latency: Histogram::new(LATENCY_BUCKETS),
+                handshake_retries: Histogram::new(RETRY_BUCKETS),
+                packet_size: Histogram::new(PACKET_SIZE_BUCKETS),
+                node_stats: Mutex::new(None),
+                peer_registry: Mutex::new(None),
+            }),
+        }
+    }
+}
+
+impl Metrics {
+    /// Create an empty set of metrics
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Report the packet counters of `stats` when scraped
+    pub fn observe_node_stats(&self, stats: NodeStats) {
+        *lock(&self.inner.node_stats) = Some(stats);
+    }
+
+    /// Report the peers in `registry` by connection status when scraped
+    pub fn observe_peer_registry(&self, registry: PeerRegistry) {
+        *lock(&self.inner.peer_registry) = Some(registry);
+    }
+
+    /// Count a handshake started by the client, retries not included
+    pub(crate) fn handshake_started(&self) {
+        self.inner
+            .handshakes_attempted
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a handshake that completed after `retries` failed attempts
+    pub(crate) fn handshake_succeeded(&self, latency: Duration, retries: u8) {
+        self.inner
+            .handshakes_succeeded
+            .fetch_add(1, Ordering::Relaxed);
+        self.inner.handshake_latency.observe(latency.as_secs_f64());
+        self.inner.handshake_retries.observe(retries.into());
+    }
+
+    /// Record a handshake that gave up with `error` after `retries` retries
+    pub(crate) fn handshake_failed(&self, error: &SolanaNodeError, retries: u8) {
+        *lock(&self.inner.handshakes_failed)
+            .entry(error.kind().as_str())
+            .or_default() += 1;
+        self.inner.handshake_retries.observe(retries.into());
+    }
+
+    /// Record the size of a packet read from the socket
+    pub(crate) fn packet_received(&self, size: usize) {
+        self.inner.packet_size.observe(size as f64);
+    }
+
+    /// Render every metric in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let inner = &self.inner;
+
+        header(
+            &mut out,
+            "p2p_handshakes_attempted_total",
+            "counter",
+            "Handshakes started by the client, retries not included",
+        );
+        sample(
+            &mut out,
+            "p2p_handshakes_attempted_total",
+            "",
+            inner.handshakes_attempted.load(Ordering::Relaxed),
+        );
+
+        header(
+            &mut out,
+            "p2p_handshakes_succeeded_total",
+            "counter",
+            "Handshakes completed by the client",
+        );
+        sample(
+            &mut out,
+            "p2p_handshakes_succeeded_total",
+            "",
+            inner.handshakes_succeeded.load(Ordering::Relaxed),
+        );
+
+        header(
+            &mut out,
+            "p2p_handshakes_failed_total",
+            "counter",
+            "Handshakes the client gave up on, by error kind",
+        );
+        for (kind, count) in lock(&inner.handshakes_failed).iter() {
+            sample(
+                &mut out,
+                "p2p_handshakes_failed_total",
+                &format!("kind=\"{}\"", kind),
+                *count,
+            );
+        }
+
+        inner.handshake_latency.render(
+            &mut out,
+            "p2p_handshake_duration_seconds",
+            "Time taken by successful handshakes, retries included",
+        );
+        inner.handshake_retries.render(
+            &mut out,
+            "p2p_handshake_retries",
+            "Retries made per finished handshake",
+        );
+
+        let stats = lock(&inner.node_stats)
+            .as_ref()
+            .map(NodeStats::snapshot)
+            .unwrap_or_default();
+        for (name, help, value) in [
+            (
+                "p2p_packets_received_total",
+                "Packets read from the gossip socket, including rate-limited ones",
+                stats.packets_received,
+            ),
+            (
+                "p2p_packets_failed_total",
+                "Packets whose handling failed",
+                stats.packets_failed,
+            ),
+            (
+                "p2p_packets_unknown_total",
+                "Packets that matched no known protocol",
+                stats.packets_unknown,
+            ),
+        ] {
+            header(&mut out, name, "counter", help);
+            sample(&mut out, name, "", value);
+        }
+
+        header(
+            &mut out,
+            "p2p_packets_dropped_total",
+            "counter",
+            "Packets dropped by a rate limit, by limit",
+        );
+        for (reason, value) in [
+            ("global_limit", stats.dropped_global_limit),
+            ("ip_limit", stats.dropped_ip_limit),
+            ("pubkey_limit", stats.dropped_pubkey_limit),
+        ] {
+            sample(
+                &mut out,
+                "p2p_packets_dropped_total",
+                &format!("reason=\"{}\"", reason),
+                value,
+            );
+        }
+
+        inner.packet_size.render(
+            &mut out,
+            "p2p_packet_size_bytes",
+            "Size of packets read from the gossip socket",
+        );
+
+        let peers = lock(&inner.peer_registry)
+            .as_ref()
+            .map(PeerRegistry::snapshot)
+            .unwrap_or_default();
+        header(
+            &mut out,
+            "p2p_registered_peers",
+            "gauge",
+            "Peers in the registry, by connection status",
+        );
+        for (status, label) in [
+            (ConnectionStatus::Connected, "connected"),
+            (ConnectionStatus::Connecting, "connecting"),
+            (ConnectionStatus::Disconnected, "disconnected"),
+            (ConnectionStatus::VerificationFailed, "verification_failed"),
+        ] {
+            let count = peers
+                .iter()
+                .filter(|peer| peer.connection_status == status)
+                .count();
+            sample(
+                &mut out,
+                "p2p_registered_peers",
+                &format!("status=\"{}\"", label),
+                count as u64,
+            );
+        }
+
+        out
+    }
+}
+
+/// Lock a metric even if a panic poisoned it; a count is at worst off by one
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn sample(out: &mut String, name: &str, labels: &str, value: impl std::fmt::Display) {
+    if labels.is_empty() {
+        let _ = writeln!(out, "{} {}", name, value);
+    } else {
+        let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+    }
+}
+
+/// Histogram with fixed bucket bounds
+#[derive(Debug)]
+struct Histogram {
+    bounds: &'static [f64],
+    state: Mutex<HistogramState>,
+}
+
+#[derive(Debug)]
+struct HistogramState {
+    /// Observations per bucket, the last one being `+Inf`
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            state: Mutex::new(HistogramState {
+                buckets: vec![0; bounds.len() + 1],
+                sum: 0.0,
+                count: 0,
+            }),
+        }
+    }
+
+    fn observe(&self, value: f64) {
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.bounds.len());
+        let mut state = lock(&self.state);
+        state.buckets[bucket] += 1;
+        state.sum += value;
+        state.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let state = lock(&self.state);
+        header(out, name, "histogram", help);
+
+        let bucket_name = format!("{}_bucket", name);
+        let mut cumulative = 0;
+        for (bound, count) in self.bounds.iter().zip(&state.buckets) {
+            cumulative += count;
+            sample(out, &bucket_name, &format!("le=\"{}\"", bound), cumulative);
+        }
+        sample(out, &bucket_name, "le=\"+Inf\"", state.count);
+        sample(out, &format!("{}_sum", name), "", state.sum);
+        sample(out, &format!("{}_count", name), "", state.count);
+    }
+}
+
+/// Minimal HTTP endpoint serving `Metrics` to Prometheus scrapers
+#[derive(Debug)]
+pub struct MetricsServer {
+    listener: TcpListener,
+    metrics: Metrics,
+}
+
+impl MetricsServer {
+    /// Listen for scrapes of `metrics` on `addr`
+    pub async fn bind(addr: SocketAddr, metrics: Metrics) -> Result<Self, SolanaNodeError> {
+        let listener = TcpListener::bind(addr).await.map_err(|e| {
+            warn!(error = %e, metrics_addr = %addr, "Failed to bind metrics endpoint");
+            SolanaNodeError::NetworkError(e)
+        })?;
+        Ok(Self { listener, metrics })
+    }
+
+    /// Get the local address the endpoint is bound to
+    pub fn local_addr(&self) -> Result<SocketAddr, SolanaNodeError> {
+        self.listener
+            .local_addr()
+            .map_err(SolanaNodeError::NetworkError)
+    }
+
+    /// Serve scrapes until the task is cancelled
+    ///
+    /// Each connection is answered once and closed. `GET /metrics` returns
+    /// the rendered metrics; any other path is answered with 404. Failing to
+    /// accept a connection is logged and retried after a short pause.
+    pub async fn run(self) -> Result<(), SolanaNodeError> {
+        info!(metrics_addr = %self.local_addr()?, path = METRICS_PATH, "Serving metrics");
+
+        loop {
+            let (stream, scraper) = match self.listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!(error = %e, "Failed to accept metrics connection");
+                    tokio::time::sleep(ACCEPT_ERROR_BACKOFF).await;
+                    continue;
+                }
+            };
+            let metrics = self.metrics.clone();
+            tokio::spawn(async move {
+                if let Err(e) = answer_scrape(stream, &metrics).await {
+                    debug!(scraper = %scraper, error = %e, "Metrics scrape failed");
+                }
+            });
+        }
+    }
+}
+
+/// Read one request from `stream` and answer it
+async fn answer_scrape(mut stream: TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    let head_complete = tokio::time::timeout(REQUEST_TIMEOUT, async {
+        while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+            let read = stream.read(&mut buffer).await?;
+            if read == 0 || request.len() + read > MAX_REQUEST_BYTES {
+                return Ok::<_, std::io::Error>(false);
+            }
+            request.extend_from_slice(&buffer[..read]);
+        }
+        Ok(true)
+    })
+    .await
+    .unwrap_or(Ok(false))?;
+
+    let request_line = String::from_utf8_lossy(&request);
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next();
+    let path = parts
+        .next()
+        .map(|target| target.split('?').next().unwrap_or(target));
+
+    let (status, content_type, body) = match (head_complete, method, path) {
+        (false, _, _) => ("400 Bad Request", "text/plain", "Bad request\n".to_string()),
+        (true, Some("GET"), Some(METRICS_PATH)) => ("200 OK", CONTENT_TYPE, metrics.render()),
+        (true, _, Some(METRICS_PATH)) => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed\n".to_string(),
+        ),
+        _ => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::domain::GossipNodeInfo;
+    use crate::solana::rate_limit::RateLimitExceeded;
+
+    fn peer(port: u16, status: ConnectionStatus) -> GossipNodeInfo {
+        GossipNodeInfo::test_peer(SocketAddr::from(([127, 0, 0, 1], port)), status)
+    }
+
+    #[test]
+    fn test_handshake_metrics_rendered() {
+        let metrics = Metrics::new();
+        metrics.handshake_started();
+        metrics.handshake_started();
+        metrics.handshake_succeeded(Duration::from_millis(20), 1);
+        let peer_addr = "127.0.0.1:8001".parse().unwrap();
+        metrics.handshake_failed(&SolanaNodeError::ReceiveTimeout { peer_addr }, 2);
+
+        let rendered = metrics.render();
+
+        assert!(rendered.contains("# TYPE p2p_handshakes_attempted_total counter\n"));
+        assert!(rendered.contains("p2p_handshakes_attempted_total 2\n"));
+        assert!(rendered.contains("p2p_handshakes_succeeded_total 1\n"));
+        assert!(rendered.contains("p2p_handshakes_failed_total{kind=\"receive_timeout\"} 1\n"));
+        assert!(rendered.contains("p2p_handshake_duration_seconds_bucket{le=\"0.01\"} 0\n"));
+        assert!(rendered.contains("p2p_handshake_duration_seconds_bucket{le=\"0.025\"} 1\n"));
+        assert!(rendered.contains("p2p_handshake_duration_seconds_count 1\n"));
+        assert!(rendered.contains("p2p_handshake_retries_bucket{le=\"1\"} 1\n"));
+        assert!(rendered.contains("p2p_handshake_retries_bucket{le=\"+Inf\"} 2\n"));
+        assert!(rendered.contains("p2p_handshake_retries_sum 3\n"));
+    }
+
+    #[test]
+    fn test_node_metrics_read_at_scrape_time() {
+        let metrics = Metrics::new();
+        let stats = NodeStats::new();
+        let registry = PeerRegistry::new();
+        metrics.observe_node_stats(stats.clone());
+        metrics.observe_peer_registry(registry.clone());
+
+        stats.packet_received();
+        stats.packet_unknown();
+        stats.rate_limited(RateLimitExceeded::SourceIp);
+        metrics.packet_received(2000);
+        registry.upsert(peer(8001, ConnectionStatus::Connected));
+        registry.upsert(peer(8002, ConnectionStatus::Connected));
+        registry.upsert(peer(8003, ConnectionStatus::Disconnected));
+
+        let rendered = metrics.render();
+
+        assert!(rendered.contains("p2p_packets_received_total 1\n"));
+        assert!(rendered.contains("p2p_packets_unknown_total 1\n"));
+        assert!(rendered.contains("p2p_packets_dropped_total{reason=\"ip_limit\"} 1\n"));
+        assert!(rendered.contains("p2p_packets_dropped_total{reason=\"global_limit\"} 0\n"));
+        assert!(rendered.contains("p2p_packet_size_bytes_bucket{le=\"1232\"} 0\n"));
+        assert!(rendered.contains("p2p_packet_size_bytes_bucket{le=\"+Inf\"} 1\n"));
+        assert!(rendered.contains("p2p_registered_peers{status=\"connected\"} 2\n"));
+        assert!(rendered.contains("p2p_registered_peers{status=\"disconnected\"} 1\n"));
+        assert!(rendered.contains("p2p_registered_peers{status=\"connecting\"} 0\n"));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_server_answers_scrapes() {
+        let metrics = Metrics::new();
+        metrics.handshake_started();
+        let server = MetricsServer::bind("127.0.0.1:0".parse().unwrap(), metrics)
+            .await
+            .unwrap();
+        let addr = server.local_addr().unwrap();
+        let serving = tokio::spawn(server.run());
+
+        let get = |path: &'static str| async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes())
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+
+        let response = get("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains(CONTENT_TYPE));
+        assert!(response.contains("p2p_handshakes_attempted_total 1\n"));
+
+        assert!(get("/").await.starts_with("HTTP/1.1 404 Not Found\r\n"));
+
+        serving.abort();
+    }
+}
@@ -0,0 +1,395 @@
+//! Access control for peers by pubkey and IP range
+//!
+//! An `AccessPolicy` combines pubkey and CIDR allow- and denylists. Denylists
+//! always win; a non-empty allowlist admits only the peers it lists. Policies
+//! are loaded from a JSON file and/or the command line and shared through an
+//! `AccessControl` handle, which can swap in a new policy while the node or
+//! client is running.
+
+use crate::solana::domain::SolanaNodeError;
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+/// A range of IP addresses such as `10.0.0.0/8` or `2001:db8::/32`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// Range of addresses sharing the first `prefix_len` bits of `network`
+    pub fn new(network: IpAddr, prefix_len: u8) -> Result<Self, String> {
+        let max_len = match network {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        if prefix_len > max_len {
+            return Err(format!(
+                "Invalid prefix length /{} for {}",
+                prefix_len, network
+            ));
+        }
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    /// Whether `addr` falls inside this range
+    ///
+    /// IPv4-mapped IPv6 addresses are matched against IPv4 ranges.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        let addr = match addr {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(addr, IpAddr::V4),
+            v4 => v4,
+        };
+
+        match (self.network, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = String;
+
+    /// Parse `addr/len`, or a bare address as a single-host range
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid CIDR range: '{}'", s);
+        match s.split_once('/') {
+            Some((network, prefix_len)) => Self::new(
+                network.trim().parse().map_err(|_| invalid())?,
+                prefix_len.trim().parse().map_err(|_| invalid())?,
+            ),
+            None => {
+                let addr: IpAddr = s.trim().parse().map_err(|_| invalid())?;
+                Self::new(addr, if addr.is_ipv4() { 32 } else { 128 })
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+/// Why a peer was refused by an `AccessPolicy`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessDenied {
+    /// The pubkey is on the denylist
+    PubkeyDenied(Pubkey),
+    /// A pubkey allowlist is set and does not list the pubkey
+    PubkeyNotAllowed(Pubkey),
+    /// The address falls inside a denied range
+    AddressDenied(IpAddr),
+    /// An address allowlist is set and no range contains the address
+    AddressNotAllowed(IpAddr),
+}
+
+impl std::fmt::Display for AccessDenied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccessDenied::PubkeyDenied(pubkey) => write!(f, "pubkey {} is denied", pubkey),
+            AccessDenied::PubkeyNotAllowed(pubkey) => {
+                write!(f, "pubkey {} is not on the allowlist", pubkey)
+            }
+            AccessDenied::AddressDenied(addr) => write!(f, "address {} is denied", addr),
+            AccessDenied::AddressNotAllowed(addr) => {
+                write!(f, "address {} is not on the allowlist", addr)
+            }
+        }
+    }
+}
+
+/// On-disk form of an `AccessPolicy`, with pubkeys in base58
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AccessPolicyFile {
+    allow_pubkeys: Vec<String>,
+    deny_pubkeys: Vec<String>,
+    allow_cidrs: Vec<String>,
+    deny_cidrs: Vec<String>,
+}
+
+/// Pubkey and IP range allow- and denylists
+///
+/// The default policy admits every peer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessPolicy {
+    /// If non-empty, only these pubkeys are admitted
+    pub allow_pubkeys: HashSet<Pubkey>,
+
+    /// Pubkeys that are never admitted
+    pub deny_pubkeys: HashSet<Pubkey>,
+
+    /// If non-empty, only addresses in these ranges are admitted
+    pub allow_cidrs: Vec<IpCidr>,
+
+    /// Address ranges that are never admitted
+    pub deny_cidrs: Vec<IpCidr>,
+}
+
+impl AccessPolicy {
+    /// Load a policy from a JSON file
+    ///
+    /// The file may contain any of `allow_pubkeys`, `deny_pubkeys`,
+    /// `allow_cidrs` and `deny_cidrs`, each a list of strings.
+    pub fn load(path: &Path) -> Result<Self, SolanaNodeError> {
+        let policy_error = |reason: String| SolanaNodeError::AccessPolicyError {
+            path: path.display().to_string(),
+            reason,
+        };
+
+        let contents = std::fs::read_to_string(path).map_err(|e| policy_error(e.to_string()))?;
+        let file: AccessPolicyFile =
+            serde_json::from_str(&contents).map_err(|e| policy_error(e.to_string()))?;
+
+        let pubkeys = |entries: Vec<String>| {
+            entries
+                .iter()
+                .map(|entry| {
+                    Pubkey::from_str(entry.trim())
+                        .map_err(|_| policy_error(format!("Invalid pubkey: '{}'", entry)))
+                })
+                .collect::<Result<HashSet<_>, _>>()
+        };
+        let cidrs = |entries: Vec<String>| {
+            entries
+                .iter()
+                .map(|entry| entry.parse().map_err(policy_error))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        Ok(Self {
+            allow_pubkeys: pubkeys(file.allow_pubkeys)?,
+            deny_pubkeys: pubkeys(file.deny_pubkeys)?,
+            allow_cidrs: cidrs(file.allow_cidrs)?,
+            deny_cidrs: cidrs(file.deny_cidrs)?,
+        })
+    }
+
+    /// Combine two policies by taking the union of each list
+    pub fn merge(mut self, other: AccessPolicy) -> Self {
+        self.allow_pubkeys.extend(other.allow_pubkeys);
+        self.deny_pubkeys.extend(other.deny_pubkeys);
+        self.allow_cidrs.extend(other.allow_cidrs);
+        self.deny_cidrs.extend(other.deny_cidrs);
+        self
+    }
+
+    /// Whether the policy admits every peer
+    pub fn is_open(&self) -> bool {
+        self.allow_pubkeys.is_empty()
+            && self.deny_pubkeys.is_empty()
+            && self.allow_cidrs.is_empty()
+            && self.deny_cidrs.is_empty()
+    }
+
+    /// Check a peer's address
+    pub fn check_addr(&self, addr: IpAddr) -> Result<(), AccessDenied> {
+        if self.deny_cidrs.iter().any(|cidr| cidr.contains(addr)) {
+            return Err(AccessDenied::AddressDenied(addr));
+        }
+        if !self.allow_cidrs.is_empty() && !self.allow_cidrs.iter().any(|cidr| cidr.contains(addr))
+        {
+            return Err(AccessDenied::AddressNotAllowed(addr));
+        }
+        Ok(())
+    }
+
+    /// Check a peer's pubkey
+    pub fn check_pubkey(&self, pubkey: &Pubkey) -> Result<(), AccessDenied> {
+        if self.deny_pubkeys.contains(pubkey) {
+            return Err(AccessDenied::PubkeyDenied(*pubkey));
+        }
+        if !self.allow_pubkeys.is_empty() && !self.allow_pubkeys.contains(pubkey) {
+            return Err(AccessDenied::PubkeyNotAllowed(*pubkey));
+        }
+        Ok(())
+    }
+
+    /// Check both a peer's address and its pubkey
+    pub fn check(&self, addr: IpAddr, pubkey: &Pubkey) -> Result<(), AccessDenied> {
+        self.check_addr(addr)?;
+        self.check_pubkey(pubkey)
+    }
+}
+
+/// Cloneable handle to an `AccessPolicy` that can be replaced at runtime
+///
+/// Clones share the same policy, so a node and a reload task can each hold one.
+#[derive(Debug, Clone, Default)]
+pub struct AccessControl {
+    policy: Arc<RwLock<AccessPolicy>>,
+}
+
+impl AccessControl {
+    pub fn new(policy: AccessPolicy) -> Self {
+        Self {
+            policy: Arc::new(RwLock::new(policy)),
+        }
+    }
+
+    /// Swap in a new policy for every holder of this handle
+    pub fn replace(&self, policy: AccessPolicy) {
+        *self.policy.write().unwrap_or_else(|e| e.into_inner()) = policy;
+    }
+
+    /// Copy of the current policy
+    pub fn policy(&self) -> AccessPolicy {
+        self.read().clone()
+    }
+
+    /// Check a peer's address against the current policy
+    pub fn check_addr(&self, addr: IpAddr) -> Result<(), AccessDenied> {
+        self.read().check_addr(addr)
+    }
+
+    /// Check a peer's pubkey against the current policy
+    pub fn check_pubkey(&self, pubkey: &Pubkey) -> Result<(), AccessDenied> {
+        self.read().check_pubkey(pubkey)
+    }
+
+    /// Check a peer's address and pubkey against the current policy
+    pub fn check(&self, addr: IpAddr, pubkey: &Pubkey) -> Result<(), AccessDenied> {
+        self.read().check(addr, pubkey)
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, AccessPolicy> {
+        self.policy.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_cidr_contains() {
+        let cidr: IpCidr = "10.1.0.0/16".parse().unwrap();
+        assert!(cidr.contains(ip("10.1.2.3")));
+        assert!(!cidr.contains(ip("10.2.0.1")));
+        assert!(cidr.contains(ip("::ffff:10.1.0.9")));
+
+        let host: IpCidr = "192.168.1.7".parse().unwrap();
+        assert_eq!(host.to_string(), "192.168.1.7/32");
+        assert!(host.contains(ip("192.168.1.7")));
+        assert!(!host.contains(ip("192.168.1.8")));
+
+        let everything: IpCidr = "0.0.0.0/0".parse().unwrap();
+        assert!(everything.contains(ip("8.8.8.8")));
+
+        let v6: IpCidr = "2001:db8::/32".parse().unwrap();
+        assert!(v6.contains(ip("2001:db8:1::1")));
+        assert!(!v6.contains(ip("10.1.2.3")));
+
+        assert!("10.0.0.0/33".parse::<IpCidr>().is_err());
+        assert!("not-an-ip/8".parse::<IpCidr>().is_err());
+    }
+
+    #[test]
+    fn test_denylists_win_over_allowlists() {
+        let pubkey = Pubkey::new_unique();
+        let policy = AccessPolicy {
+            allow_pubkeys: HashSet::from([pubkey]),
+            deny_pubkeys: HashSet::from([pubkey]),
+            allow_cidrs: vec!["10.0.0.0/8".parse().unwrap()],
+            deny_cidrs: vec!["10.9.0.0/16".parse().unwrap()],
+        };
+
+        assert_eq!(
+            policy.check_pubkey(&pubkey),
+            Err(AccessDenied::PubkeyDenied(pubkey))
+        );
+        assert_eq!(
+            policy.check_addr(ip("10.9.1.1")),
+            Err(AccessDenied::AddressDenied(ip("10.9.1.1")))
+        );
+        assert!(policy.check_addr(ip("10.1.1.1")).is_ok());
+        assert_eq!(
+            policy.check_addr(ip("11.0.0.1")),
+            Err(AccessDenied::AddressNotAllowed(ip("11.0.0.1")))
+        );
+    }
+
+    #[test]
+    fn test_allowlist_admits_only_listed_pubkeys() {
+        let allowed = Pubkey::new_unique();
+        let policy = AccessPolicy {
+            allow_pubkeys: HashSet::from([allowed]),
+            ..Default::default()
+        };
+
+        assert!(policy.check(ip("127.0.0.1"), &allowed).is_ok());
+        let stranger = Pubkey::new_unique();
+        assert_eq!(
+            policy.check(ip("127.0.0.1"), &stranger),
+            Err(AccessDenied::PubkeyNotAllowed(stranger))
+        );
+        assert!(AccessPolicy::default().is_open());
+    }
+
+    #[test]
+    fn test_load_from_file() {
+        let denied = Pubkey::new_unique();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"{{"deny_pubkeys": ["{}"], "allow_cidrs": ["127.0.0.0/8"]}}"#,
+            denied
+        )
+        .unwrap();
+
+        let policy = AccessPolicy::load(file.path()).unwrap();
+        assert!(policy.deny_pubkeys.contains(&denied));
+        assert_eq!(policy.allow_cidrs.len(), 1);
+
+        std::fs::write(file.path(), r#"{"deny_pubkeys": ["nope"]}"#).unwrap();
+        assert!(matches!(
+            AccessPolicy::load(file.path()),
+            Err(SolanaNodeError::AccessPolicyError { .. })
+        ));
+
+        std::fs::write(file.path(), r#"{"allow_ips": []}"#).unwrap();
+        assert!(AccessPolicy::load(file.path()).is_err());
+    }
+
+    #[test]
+    fn test_access_control_replace_is_shared() {
+        let control = AccessControl::default();
+        let observer = control.clone();
+        let pubkey = Pubkey::new_unique();
+        assert!(observer.check_pubkey(&pubkey).is_ok());
+
+        control.replace(AccessPolicy {
+            deny_pubkeys: HashSet::from([pubkey]),
+            ..Default::default()
+        });
+        assert!(observer.check_pubkey(&pubkey).is_err());
+    }
+}
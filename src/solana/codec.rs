@@ -0,0 +1,1501 @@
+//! Codec for the Solana gossip wire protocol
+//!
+//! This module encodes and decodes the agave gossip `Protocol` messages using
+//! the same bincode-compatible byte layouts as a real validator (v1.18), so the
+//! packets produced here can be exchanged with a validator's gossip socket.
+//!
+//! Layout rules follow bincode's defaults: little-endian fixed-width integers,
+//! `u32` enum discriminants, `u64` length prefixes for vectors, plus the
+//! `short_vec` (compact-u16) and `serde_varint` (LEB128) encodings used by
+//! `ContactInfo`.
+
+use rand::Rng;
+use solana_sdk::hash::{hashv, Hash};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use thiserror::Error;
+
+/// Maximum payload size of a gossip packet accepted by validators
+pub const PACKET_DATA_SIZE: usize = 1232;
+
+/// Domain separator hashed together with a ping token to produce a pong
+pub const PING_PONG_HASH_PREFIX: &[u8] = b"SOLANA_PING_PONG";
+
+/// `ContactInfo` socket tag for the gossip socket
+pub const SOCKET_TAG_GOSSIP: u8 = 0;
+
+/// `ContactInfo` socket tag for the JSON RPC socket
+pub const SOCKET_TAG_RPC: u8 = 2;
+
+/// `ContactInfo` socket tag for the TPU socket
+pub const SOCKET_TAG_TPU: u8 = 5;
+
+/// `ContactInfo` socket tag for the TVU socket
+pub const SOCKET_TAG_TVU: u8 = 10;
+
+/// Number of bits in the bloom filter sent with our pull requests
+const PULL_REQUEST_BLOOM_BITS: u64 = 512;
+
+/// Number of hash keys in the bloom filter sent with our pull requests
+const PULL_REQUEST_BLOOM_KEYS: usize = 8;
+
+/// Errors produced while encoding or decoding gossip packets
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CodecError {
+    #[error("Unexpected end of packet at offset {offset} (needed {needed} more bytes)")]
+    UnexpectedEof { offset: usize, needed: usize },
+
+    #[error("Invalid {type_name} discriminant {tag} at offset {offset}")]
+    InvalidTag {
+        type_name: &'static str,
+        tag: u32,
+        offset: usize,
+    },
+
+    #[error("Invalid {field} at offset {offset}")]
+    InvalidValue { field: &'static str, offset: usize },
+
+    #[error("{trailing} trailing bytes after message at offset {offset}")]
+    TrailingBytes { trailing: usize, offset: usize },
+
+    #[error("Encoded packet too large: {size} bytes (max {max})")]
+    PacketTooLarge { size: usize, max: usize },
+}
+
+impl CodecError {
+    /// Byte offset into the packet at which decoding failed, if any
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            CodecError::UnexpectedEof { offset, .. }
+            | CodecError::InvalidTag { offset, .. }
+            | CodecError::InvalidValue { offset, .. }
+            | CodecError::TrailingBytes { offset, .. } => Some(*offset),
+            CodecError::PacketTooLarge { .. } => None,
+        }
+    }
+}
+
+/// Top-level gossip message exchanged between validators
+#[allow(clippy::large_enum_variant)] // mirrors the upstream layout; values are short-lived
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Protocol {
+    /// Request for CRDS values not present in the sender's bloom filter
+    PullRequest(CrdsFilter, CrdsValue),
+    /// Values sent in answer to a pull request
+    PullResponse(Pubkey, Vec<CrdsValue>),
+    /// Values pushed to peers without being requested
+    PushMessage(Pubkey, Vec<CrdsValue>),
+    /// Request to stop pushing values originating from the listed nodes
+    PruneMessage(Pubkey, PruneData),
+    /// Liveness challenge proving ownership of the sender address
+    PingMessage(Ping),
+    /// Answer to a liveness challenge
+    PongMessage(Pong),
+}
+
+impl Protocol {
+    /// Human-readable message name for logging
+    pub fn name(&self) -> &'static str {
+        match self {
+            Protocol::PullRequest(..) => "pull_request",
+            Protocol::PullResponse(..) => "pull_response",
+            Protocol::PushMessage(..) => "push_message",
+            Protocol::PruneMessage(..) => "prune_message",
+            Protocol::PingMessage(_) => "ping",
+            Protocol::PongMessage(_) => "pong",
+        }
+    }
+
+    /// Serialize the message into a gossip packet
+    pub fn encode(&self) -> Result<Vec<u8>, CodecError> {
+        let mut encoder = Encoder::default();
+        match self {
+            Protocol::PullRequest(filter, value) => {
+                encoder.put_u32(0);
+                filter.encode(&mut encoder);
+                value.encode(&mut encoder);
+            }
+            Protocol::PullResponse(from, values) => {
+                encoder.put_u32(1);
+                encoder.put_pubkey(from);
+                encoder.put_len(values.len());
+                values.iter().for_each(|value| value.encode(&mut encoder));
+            }
+            Protocol::PushMessage(from, values) => {
+                encoder.put_u32(2);
+                encoder.put_pubkey(from);
+                encoder.put_len(values.len());
+                values.iter().for_each(|value| value.encode(&mut encoder));
+            }
+            Protocol::PruneMessage(from, data) => {
+                encoder.put_u32(3);
+                encoder.put_pubkey(from);
+                data.encode(&mut encoder);
+            }
+            Protocol::PingMessage(ping) => {
+                encoder.put_u32(4);
+                ping.encode(&mut encoder);
+            }
+            Protocol::PongMessage(pong) => {
+                encoder.put_u32(5);
+                pong.encode(&mut encoder);
+            }
+        }
+
+        let bytes = encoder.into_inner();
+        if bytes.len() > PACKET_DATA_SIZE {
+            return Err(CodecError::PacketTooLarge {
+                size: bytes.len(),
+                max: PACKET_DATA_SIZE,
+            });
+        }
+        Ok(bytes)
+    }
+
+    /// Deserialize a gossip packet, rejecting trailing bytes
+    pub fn decode(bytes: &[u8]) -> Result<Self, CodecError> {
+        let mut decoder = Decoder::new(bytes);
+        let tag_offset = decoder.offset();
+        let message = match decoder.get_u32()? {
+            0 => Protocol::PullRequest(
+                CrdsFilter::decode(&mut decoder)?,
+                CrdsValue::decode(&mut decoder)?,
+            ),
+            1 => Protocol::PullResponse(decoder.get_pubkey()?, decode_values(&mut decoder)?),
+            2 => Protocol::PushMessage(decoder.get_pubkey()?, decode_values(&mut decoder)?),
+            3 => Protocol::PruneMessage(decoder.get_pubkey()?, PruneData::decode(&mut decoder)?),
+            4 => Protocol::PingMessage(Ping::decode(&mut decoder)?),
+            5 => Protocol::PongMessage(Pong::decode(&mut decoder)?),
+            tag => {
+                return Err(CodecError::InvalidTag {
+                    type_name: "Protocol",
+                    tag,
+                    offset: tag_offset,
+                })
+            }
+        };
+        decoder.finish()?;
+        Ok(message)
+    }
+}
+
+fn decode_values(decoder: &mut Decoder) -> Result<Vec<CrdsValue>, CodecError> {
+    let len = decoder.get_len(CrdsValue::MIN_SIZE)?;
+    (0..len).map(|_| CrdsValue::decode(decoder)).collect()
+}
+
+/// Bloom filter over CRDS value hashes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bloom {
+    /// Seeds for the bloom hash functions
+    pub keys: Vec<u64>,
+    /// Filter bits packed into 64-bit blocks
+    pub bits: Vec<u64>,
+    /// Number of valid bits in `bits`
+    pub num_bits: u64,
+    /// Number of bits currently set
+    pub num_bits_set: u64,
+}
+
+impl Bloom {
+    /// Create an empty filter with random keys
+    pub fn empty(num_bits: u64, num_keys: usize) -> Self {
+        let mut rng = rand::thread_rng();
+        Self {
+            keys: (0..num_keys).map(|_| rng.gen()).collect(),
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+            num_bits,
+            num_bits_set: 0,
+        }
+    }
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_len(self.keys.len());
+        self.keys.iter().for_each(|key| encoder.put_u64(*key));
+        // `bv::BitVec<u64>` serializes as `Option<Box<[u64]>>` followed by its bit length
+        if self.bits.is_empty() {
+            encoder.put_u8(0);
+        } else {
+            encoder.put_u8(1);
+            encoder.put_len(self.bits.len());
+            self.bits.iter().for_each(|block| encoder.put_u64(*block));
+        }
+        encoder.put_u64(self.num_bits);
+        encoder.put_u64(self.num_bits_set);
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        let key_count = decoder.get_len(8)?;
+        let keys = (0..key_count)
+            .map(|_| decoder.get_u64())
+            .collect::<Result<_, _>>()?;
+        let bits = match decoder.get_option_tag()? {
+            false => Vec::new(),
+            true => {
+                let block_count = decoder.get_len(8)?;
+                (0..block_count)
+                    .map(|_| decoder.get_u64())
+                    .collect::<Result<_, _>>()?
+            }
+        };
+        let num_bits_offset = decoder.offset();
+        let num_bits = decoder.get_u64()?;
+        if num_bits > bits.len() as u64 * 64 {
+            return Err(CodecError::InvalidValue {
+                field: "bloom bit length",
+                offset: num_bits_offset,
+            });
+        }
+        Ok(Self {
+            keys,
+            bits,
+            num_bits,
+            num_bits_set: decoder.get_u64()?,
+        })
+    }
+}
+
+/// Filter describing which CRDS values a pull request already has
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrdsFilter {
+    /// Bloom filter of known value hashes
+    pub filter: Bloom,
+    /// Hash prefix selecting the partition of the table covered by this filter
+    pub mask: u64,
+    /// Number of significant bits in `mask`
+    pub mask_bits: u32,
+}
+
+impl CrdsFilter {
+    /// Filter covering the whole table and matching nothing, i.e. "send me everything"
+    pub fn new_empty() -> Self {
+        Self {
+            filter: Bloom::empty(PULL_REQUEST_BLOOM_BITS, PULL_REQUEST_BLOOM_KEYS),
+            mask: u64::MAX,
+            mask_bits: 0,
+        }
+    }
+
+    fn encode(&self, encoder: &mut Encoder) {
+        self.filter.encode(encoder);
+        encoder.put_u64(self.mask);
+        encoder.put_u32(self.mask_bits);
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        Ok(Self {
+            filter: Bloom::decode(decoder)?,
+            mask: decoder.get_u64()?,
+            mask_bits: decoder.get_u32()?,
+        })
+    }
+}
+
+/// Signed CRDS value as gossiped between nodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrdsValue {
+    /// Signature by the originating node over the serialized `data`
+    pub signature: Signature,
+    /// The replicated data itself
+    pub data: CrdsData,
+}
+
+impl CrdsValue {
+    /// Smallest possible encoded value, used to bound untrusted length prefixes
+    const MIN_SIZE: usize = 64 + 4 + 32;
+
+    /// Create a value signed by `keypair`
+    pub fn new_signed(data: CrdsData, keypair: &Keypair) -> Self {
+        let signature = keypair.sign_message(&data.to_bytes());
+        Self { signature, data }
+    }
+
+    /// Public key of the node this value originates from
+    pub fn pubkey(&self) -> Pubkey {
+        self.data.pubkey()
+    }
+
+    /// Check the signature against the originating node's public key
+    pub fn verify(&self) -> bool {
+        self.signature
+            .verify(self.pubkey().as_ref(), &self.data.to_bytes())
+    }
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_signature(&self.signature);
+        self.data.encode(encoder);
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        Ok(Self {
+            signature: decoder.get_signature()?,
+            data: CrdsData::decode(decoder)?,
+        })
+    }
+}
+
+/// Replicated data carried by a `CrdsValue`
+///
+/// Only the variants needed for peer discovery are modelled field by field;
+/// every other variant is validated structurally and kept as raw bytes so it
+/// can still be re-encoded and have its signature verified.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CrdsData {
+    /// Fixed-layout contact info (discriminant 0)
+    LegacyContactInfo(LegacyContactInfo),
+    /// Software version without feature set (discriminant 6)
+    LegacyVersion(LegacyVersion),
+    /// Software version with feature set (discriminant 7)
+    Version(Version),
+    /// Identity of a running validator instance (discriminant 8)
+    NodeInstance(NodeInstance),
+    /// Compact contact info (discriminant 11)
+    ContactInfo(ContactInfo),
+    /// Any other variant, stored as its encoded payload (without discriminant)
+    Other {
+        kind: u32,
+        from: Pubkey,
+        payload: Vec<u8>,
+    },
+}
+
+impl CrdsData {
+    /// Public key of the node this data describes
+    pub fn pubkey(&self) -> Pubkey {
+        match self {
+            CrdsData::LegacyContactInfo(info) => info.id,
+            CrdsData::LegacyVersion(version) => version.from,
+            CrdsData::Version(version) => version.from,
+            CrdsData::NodeInstance(instance) => instance.from,
+            CrdsData::ContactInfo(info) => info.pubkey,
+            CrdsData::Other { from, .. } => *from,
+        }
+    }
+
+    /// Variant discriminant as used on the wire
+    pub fn kind(&self) -> u32 {
+        match self {
+            CrdsData::LegacyContactInfo(_) => 0,
+            CrdsData::LegacyVersion(_) => 6,
+            CrdsData::Version(_) => 7,
+            CrdsData::NodeInstance(_) => 8,
+            CrdsData::ContactInfo(_) => 11,
+            CrdsData::Other { kind, .. } => *kind,
+        }
+    }
+
+    /// Serialized form, which is also the message signed by the originating node
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        self.encode(&mut encoder);
+        encoder.into_inner()
+    }
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_u32(self.kind());
+        match self {
+            CrdsData::LegacyContactInfo(info) => info.encode(encoder),
+            CrdsData::LegacyVersion(version) => version.encode(encoder),
+            CrdsData::Version(version) => version.encode(encoder),
+            CrdsData::NodeInstance(instance) => instance.encode(encoder),
+            CrdsData::ContactInfo(info) => info.encode(encoder),
+            CrdsData::Other { payload, .. } => encoder.put_bytes(payload),
+        }
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        let tag_offset = decoder.offset();
+        let kind = decoder.get_u32()?;
+        let data = match kind {
+            0 => CrdsData::LegacyContactInfo(LegacyContactInfo::decode(decoder)?),
+            6 => CrdsData::LegacyVersion(LegacyVersion::decode(decoder)?),
+            7 => CrdsData::Version(Version::decode(decoder)?),
+            8 => CrdsData::NodeInstance(NodeInstance::decode(decoder)?),
+            11 => CrdsData::ContactInfo(ContactInfo::decode(decoder)?),
+            1..=5 | 9 | 10 | 12 | 13 => {
+                let start = decoder.offset();
+                let from = skip_crds_data(kind, decoder)?;
+                CrdsData::Other {
+                    kind,
+                    from,
+                    payload: decoder.bytes[start..decoder.offset()].to_vec(),
+                }
+            }
+            tag => {
+                return Err(CodecError::InvalidTag {
+                    type_name: "CrdsData",
+                    tag,
+                    offset: tag_offset,
+                })
+            }
+        };
+        Ok(data)
+    }
+}
+
+/// Walk over the payload of a CRDS variant we do not model, returning its origin
+fn skip_crds_data(kind: u32, decoder: &mut Decoder) -> Result<Pubkey, CodecError> {
+    match kind {
+        // Vote(VoteIndex, Vote { from, transaction, wallclock })
+        1 => {
+            decoder.skip(1)?;
+            let from = decoder.get_pubkey()?;
+            let signature_count = decoder.get_short_vec_len()?;
+            decoder.skip(signature_count * 64)?;
+            decoder.skip(3)?; // message header
+            let account_count = decoder.get_short_vec_len()?;
+            decoder.skip(account_count * 32 + 32)?; // account keys + recent blockhash
+            for _ in 0..decoder.get_short_vec_len()? {
+                decoder.skip(1)?; // program id index
+                let accounts = decoder.get_short_vec_len()?;
+                decoder.skip(accounts)?;
+                let data = decoder.get_short_vec_len()?;
+                decoder.skip(data)?;
+            }
+            decoder.skip(8)?;
+            Ok(from)
+        }
+        // LowestSlot(u8, LowestSlot { from, root, lowest, slots, stash, wallclock })
+        2 => {
+            decoder.skip(1)?;
+            let from = decoder.get_pubkey()?;
+            decoder.skip(16)?;
+            let slots = decoder.get_len(8)?;
+            decoder.skip(slots * 8)?;
+            for _ in 0..decoder.get_len(20)? {
+                decoder.skip(12)?; // first slot + compression type
+                let compressed = decoder.get_len(1)?;
+                decoder.skip(compressed)?;
+            }
+            decoder.skip(8)?;
+            Ok(from)
+        }
+        // LegacySnapshotHashes / AccountsHashes { from, hashes: Vec<(Slot, Hash)>, wallclock }
+        3 | 4 => {
+            let from = decoder.get_pubkey()?;
+            let hashes = decoder.get_len(40)?;
+            decoder.skip(hashes * 40 + 8)?;
+            Ok(from)
+        }
+        // EpochSlots(u8, EpochSlots { from, slots: Vec<CompressedSlots>, wallclock })
+        5 => {
+            decoder.skip(1)?;
+            let from = decoder.get_pubkey()?;
+            for _ in 0..decoder.get_len(20)? {
+                let tag_offset = decoder.offset();
+                match decoder.get_u32()? {
+                    // Flate2 { first_slot, num, compressed: Vec<u8> }
+                    0 => {
+                        decoder.skip(16)?;
+                        let compressed = decoder.get_len(1)?;
+                        decoder.skip(compressed)?;
+                    }
+                    // Uncompressed { first_slot, num, slots: BitVec<u8> }
+                    1 => {
+                        decoder.skip(16)?;
+                        skip_byte_bitvec(decoder)?;
+                    }
+                    tag => {
+                        return Err(CodecError::InvalidTag {
+                            type_name: "CompressedSlots",
+                            tag,
+                            offset: tag_offset,
+                        })
+                    }
+                }
+            }
+            decoder.skip(8)?;
+            Ok(from)
+        }
+        // DuplicateShred(u16, DuplicateShred { from, wallclock, slot, _, _, num_chunks, chunk_index, chunk })
+        9 => {
+            decoder.skip(2)?;
+            let from = decoder.get_pubkey()?;
+            decoder.skip(8 + 8 + 4 + 1 + 1 + 1)?;
+            let chunk = decoder.get_len(1)?;
+            decoder.skip(chunk)?;
+            Ok(from)
+        }
+        // SnapshotHashes { from, full: (Slot, Hash), incremental: Vec<(Slot, Hash)>, wallclock }
+        10 => {
+            let from = decoder.get_pubkey()?;
+            decoder.skip(40)?;
+            let incremental = decoder.get_len(40)?;
+            decoder.skip(incremental * 40 + 8)?;
+            Ok(from)
+        }
+        // RestartLastVotedForkSlots { from, wallclock, offsets, last_voted_slot, last_voted_hash, shred_version }
+        12 => {
+            let from = decoder.get_pubkey()?;
+            decoder.skip(8)?;
+            let tag_offset = decoder.offset();
+            match decoder.get_u32()? {
+                0 => {
+                    let run_lengths = decoder.get_len(2)?;
+                    decoder.skip(run_lengths * 2)?;
+                }
+                1 => skip_byte_bitvec(decoder)?,
+                tag => {
+                    return Err(CodecError::InvalidTag {
+                        type_name: "SlotsOffsets",
+                        tag,
+                        offset: tag_offset,
+                    })
+                }
+            }
+            decoder.skip(8 + 32 + 2)?;
+            Ok(from)
+        }
+        // RestartHeaviestFork { from, wallclock, last_slot, last_slot_hash, observed_stake, shred_version }
+        13 => {
+            let from = decoder.get_pubkey()?;
+            decoder.skip(8 + 8 + 32 + 8 + 2)?;
+            Ok(from)
+        }
+        _ => unreachable!("skip_crds_data called for modelled variant {kind}"),
+    }
+}
+
+/// Skip a serialized `bv::BitVec<u8>`
+fn skip_byte_bitvec(decoder: &mut Decoder) -> Result<(), CodecError> {
+    if decoder.get_option_tag()? {
+        let blocks = decoder.get_len(1)?;
+        decoder.skip(blocks)?;
+    }
+    decoder.skip(8)
+}
+
+/// Fixed-layout contact info advertised by pre-`ContactInfo` validators
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyContactInfo {
+    pub id: Pubkey,
+    pub gossip: SocketAddr,
+    pub tvu: SocketAddr,
+    pub tvu_quic: SocketAddr,
+    pub serve_repair_quic: SocketAddr,
+    pub tpu: SocketAddr,
+    pub tpu_forwards: SocketAddr,
+    pub tpu_vote: SocketAddr,
+    pub rpc: SocketAddr,
+    pub rpc_pubsub: SocketAddr,
+    pub serve_repair: SocketAddr,
+    pub wallclock: u64,
+    pub shred_version: u16,
+}
+
+impl LegacyContactInfo {
+    fn sockets(&self) -> [&SocketAddr; 10] {
+        [
+            &self.gossip,
+            &self.tvu,
+            &self.tvu_quic,
+            &self.serve_repair_quic,
+            &self.tpu,
+            &self.tpu_forwards,
+            &self.tpu_vote,
+            &self.rpc,
+            &self.rpc_pubsub,
+            &self.serve_repair,
+        ]
+    }
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_pubkey(&self.id);
+        self.sockets()
+            .into_iter()
+            .for_each(|addr| encoder.put_socket_addr(addr));
+        encoder.put_u64(self.wallclock);
+        encoder.put_u16(self.shred_version);
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        Ok(Self {
+            id: decoder.get_pubkey()?,
+            gossip: decoder.get_socket_addr()?,
+            tvu: decoder.get_socket_addr()?,
+            tvu_quic: decoder.get_socket_addr()?,
+            serve_repair_quic: decoder.get_socket_addr()?,
+            tpu: decoder.get_socket_addr()?,
+            tpu_forwards: decoder.get_socket_addr()?,
+            tpu_vote: decoder.get_socket_addr()?,
+            rpc: decoder.get_socket_addr()?,
+            rpc_pubsub: decoder.get_socket_addr()?,
+            serve_repair: decoder.get_socket_addr()?,
+            wallclock: decoder.get_u64()?,
+            shred_version: decoder.get_u16()?,
+        })
+    }
+}
+
+/// Software version advertised inside `ContactInfo`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+    /// First four bytes of the source commit hash
+    pub commit: Option<u32>,
+    /// First four bytes of the feature set identifier
+    pub feature_set: u32,
+    /// Client implementation identifier (0 = Solana Labs, 1 = Jito, 3 = Agave)
+    pub client: u16,
+}
+
+impl ClientVersion {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_varint(self.major.into());
+        encoder.put_varint(self.minor.into());
+        encoder.put_varint(self.patch.into());
+        encoder.put_option_u32(self.commit);
+        encoder.put_u32(self.feature_set);
+        encoder.put_varint(self.client.into());
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        Ok(Self {
+            major: decoder.get_varint_u16()?,
+            minor: decoder.get_varint_u16()?,
+            patch: decoder.get_varint_u16()?,
+            commit: decoder.get_option_u32()?,
+            feature_set: decoder.get_u32()?,
+            client: decoder.get_varint_u16()?,
+        })
+    }
+}
+
+impl std::fmt::Display for ClientVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// One socket of a `ContactInfo`, stored as a port delta from the previous entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketEntry {
+    /// Socket tag, e.g. `SOCKET_TAG_GOSSIP`
+    pub key: u8,
+    /// Index into `ContactInfo::addrs`
+    pub index: u8,
+    /// Port offset relative to the previous entry
+    pub offset: u16,
+}
+
+/// Compact contact info advertised by current validators
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContactInfo {
+    pub pubkey: Pubkey,
+    pub wallclock: u64,
+    /// Timestamp (in microseconds) at which the node instance started
+    pub outset: u64,
+    pub shred_version: u16,
+    pub version: ClientVersion,
+    pub addrs: Vec<IpAddr>,
+    pub sockets: Vec<SocketEntry>,
+}
+
+impl ContactInfo {
+    /// Contact info advertising only a gossip socket
+    pub fn new_gossip_only(
+        pubkey: Pubkey,
+        gossip_addr: SocketAddr,
+        shred_version: u16,
+        version: ClientVersion,
+    ) -> Self {
+        let wallclock = solana_sdk::timing::timestamp();
+        Self {
+            pubkey,
+            wallclock,
+            outset: wallclock.saturating_mul(1000),
+            shred_version,
+            version,
+            addrs: vec![gossip_addr.ip()],
+            sockets: vec![SocketEntry {
+                key: SOCKET_TAG_GOSSIP,
+                index: 0,
+                offset: gossip_addr.port(),
+            }],
+        }
+    }
+
+    /// Resolve the socket address registered under `key`
+    pub fn socket(&self, key: u8) -> Option<SocketAddr> {
+        let mut port = 0u16;
+        for entry in &self.sockets {
+            port = port.checked_add(entry.offset)?;
+            if entry.key == key {
+                let ip = self.addrs.get(usize::from(entry.index))?;
+                return Some(SocketAddr::new(*ip, port));
+            }
+        }
+        None
+    }
+
+    /// The node's gossip socket
+    pub fn gossip(&self) -> Option<SocketAddr> {
+        self.socket(SOCKET_TAG_GOSSIP)
+    }
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_pubkey(&self.pubkey);
+        encoder.put_varint(self.wallclock);
+        encoder.put_u64(self.outset);
+        encoder.put_u16(self.shred_version);
+        self.version.encode(encoder);
+        encoder.put_short_vec_len(self.addrs.len());
+        self.addrs.iter().for_each(|ip| encoder.put_ip_addr(ip));
+        encoder.put_short_vec_len(self.sockets.len());
+        for entry in &self.sockets {
+            encoder.put_u8(entry.key);
+            encoder.put_u8(entry.index);
+            encoder.put_varint(entry.offset.into());
+        }
+        // Extensions are not used by any released client
+        encoder.put_short_vec_len(0);
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        let pubkey = decoder.get_pubkey()?;
+        let wallclock = decoder.get_varint_u64()?;
+        let outset = decoder.get_u64()?;
+        let shred_version = decoder.get_u16()?;
+        let version = ClientVersion::decode(decoder)?;
+        let addrs = (0..decoder.get_short_vec_len()?)
+            .map(|_| decoder.get_ip_addr())
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut sockets = Vec::new();
+        for _ in 0..decoder.get_short_vec_len()? {
+            let key = decoder.get_u8()?;
+            let index_offset = decoder.offset();
+            let index = decoder.get_u8()?;
+            if usize::from(index) >= addrs.len() {
+                return Err(CodecError::InvalidValue {
+                    field: "socket address index",
+                    offset: index_offset,
+                });
+            }
+            let offset = decoder.get_varint_u16()?;
+            sockets.push(SocketEntry { key, index, offset });
+        }
+        let extensions_offset = decoder.offset();
+        if decoder.get_short_vec_len()? != 0 {
+            return Err(CodecError::InvalidValue {
+                field: "contact info extensions",
+                offset: extensions_offset,
+            });
+        }
+        Ok(Self {
+            pubkey,
+            wallclock,
+            outset,
+            shred_version,
+            version,
+            addrs,
+            sockets,
+        })
+    }
+}
+
+/// Legacy software version record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyVersion {
+    pub from: Pubkey,
+    pub wallclock: u64,
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+    pub commit: Option<u32>,
+}
+
+impl LegacyVersion {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_pubkey(&self.from);
+        encoder.put_u64(self.wallclock);
+        encoder.put_u16(self.major);
+        encoder.put_u16(self.minor);
+        encoder.put_u16(self.patch);
+        encoder.put_option_u32(self.commit);
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        Ok(Self {
+            from: decoder.get_pubkey()?,
+            wallclock: decoder.get_u64()?,
+            major: decoder.get_u16()?,
+            minor: decoder.get_u16()?,
+            patch: decoder.get_u16()?,
+            commit: decoder.get_option_u32()?,
+        })
+    }
+}
+
+/// Software version record including the feature set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    pub from: Pubkey,
+    pub wallclock: u64,
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+    pub commit: Option<u32>,
+    pub feature_set: u32,
+}
+
+impl Version {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_pubkey(&self.from);
+        encoder.put_u64(self.wallclock);
+        encoder.put_u16(self.major);
+        encoder.put_u16(self.minor);
+        encoder.put_u16(self.patch);
+        encoder.put_option_u32(self.commit);
+        encoder.put_u32(self.feature_set);
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        Ok(Self {
+            from: decoder.get_pubkey()?,
+            wallclock: decoder.get_u64()?,
+            major: decoder.get_u16()?,
+            minor: decoder.get_u16()?,
+            patch: decoder.get_u16()?,
+            commit: decoder.get_option_u32()?,
+            feature_set: decoder.get_u32()?,
+        })
+    }
+}
+
+/// Record identifying one running instance of a validator
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeInstance {
+    pub from: Pubkey,
+    pub wallclock: u64,
+    pub timestamp: u64,
+    pub token: u64,
+}
+
+impl NodeInstance {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_pubkey(&self.from);
+        encoder.put_u64(self.wallclock);
+        encoder.put_u64(self.timestamp);
+        encoder.put_u64(self.token);
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        Ok(Self {
+            from: decoder.get_pubkey()?,
+            wallclock: decoder.get_u64()?,
+            timestamp: decoder.get_u64()?,
+            token: decoder.get_u64()?,
+        })
+    }
+}
+
+/// Payload of a prune message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PruneData {
+    pub pubkey: Pubkey,
+    pub prunes: Vec<Pubkey>,
+    pub signature: Signature,
+    pub destination: Pubkey,
+    pub wallclock: u64,
+}
+
+impl PruneData {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_pubkey(&self.pubkey);
+        encoder.put_len(self.prunes.len());
+        self.prunes
+            .iter()
+            .for_each(|prune| encoder.put_pubkey(prune));
+        encoder.put_signature(&self.signature);
+        encoder.put_pubkey(&self.destination);
+        encoder.put_u64(self.wallclock);
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        let pubkey = decoder.get_pubkey()?;
+        let prune_count = decoder.get_len(32)?;
+        let prunes = (0..prune_count)
+            .map(|_| decoder.get_pubkey())
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            pubkey,
+            prunes,
+            signature: decoder.get_signature()?,
+            destination: decoder.get_pubkey()?,
+            wallclock: decoder.get_u64()?,
+        })
+    }
+}
+
+/// Liveness challenge carrying a random token signed by the sender
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ping {
+    pub from: Pubkey,
+    pub token: [u8; 32],
+    pub signature: Signature,
+}
+
+impl Ping {
+    /// Create a ping for `token` signed by `keypair`
+    pub fn new(token: [u8; 32], keypair: &Keypair) -> Self {
+        Self {
+            from: keypair.pubkey(),
+            token,
+            signature: keypair.sign_message(&token),
+        }
+    }
+
+    /// Create a ping with a fresh random token
+    pub fn new_rand(keypair: &Keypair) -> Self {
+        Self::new(rand::random(), keypair)
+    }
+
+    /// Check the sender's signature over the token
+    pub fn verify(&self) -> bool {
+        self.signature.verify(self.from.as_ref(), &self.token)
+    }
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_pubkey(&self.from);
+        encoder.put_bytes(&self.token);
+        encoder.put_signature(&self.signature);
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        Ok(Self {
+            from: decoder.get_pubkey()?,
+            token: decoder.get_array()?,
+            signature: decoder.get_signature()?,
+        })
+    }
+}
+
+/// Answer to a `Ping`, proving the sender received the token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pong {
+    pub from: Pubkey,
+    /// `sha256(PING_PONG_HASH_PREFIX || token)`
+    pub hash: Hash,
+    pub signature: Signature,
+}
+
+impl Pong {
+    /// Create the pong answering `ping`, signed by `keypair`
+    pub fn new(ping: &Ping, keypair: &Keypair) -> Self {
+        let hash = Self::hash_token(&ping.token);
+        Self {
+            from: keypair.pubkey(),
+            hash,
+            signature: keypair.sign_message(hash.as_ref()),
+        }
+    }
+
+    /// Hash a pong must carry to answer a ping with `token`
+    pub fn hash_token(token: &[u8; 32]) -> Hash {
+        hashv(&[PING_PONG_HASH_PREFIX, token])
+    }
+
+    /// Check the sender's signature over the hash
+    pub fn verify(&self) -> bool {
+        self.signature
+            .verify(self.from.as_ref(), self.hash.as_ref())
+    }
+
+    /// Whether this pong is a correctly signed answer to `ping`
+    pub fn answers(&self, ping: &Ping) -> bool {
+        self.hash == Self::hash_token(&ping.token) && self.verify()
+    }
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.put_pubkey(&self.from);
+        encoder.put_bytes(self.hash.as_ref());
+        encoder.put_signature(&self.signature);
+    }
+
+    fn decode(decoder: &mut Decoder) -> Result<Self, CodecError> {
+        Ok(Self {
+            from: decoder.get_pubkey()?,
+            hash: Hash::new_from_array(decoder.get_array()?),
+            signature: decoder.get_signature()?,
+        })
+    }
+}
+
+/// Append-only writer for bincode-compatible layouts
+#[derive(Default)]
+struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    fn into_inner(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    fn put_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn put_u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    fn put_u16(&mut self, value: u16) {
+        self.put_bytes(&value.to_le_bytes());
+    }
+
+    fn put_u32(&mut self, value: u32) {
+        self.put_bytes(&value.to_le_bytes());
+    }
+
+    fn put_u64(&mut self, value: u64) {
+        self.put_bytes(&value.to_le_bytes());
+    }
+
+    fn put_len(&mut self, len: usize) {
+        self.put_u64(len as u64);
+    }
+
+    fn put_option_u32(&mut self, value: Option<u32>) {
+        match value {
+            Some(value) => {
+                self.put_u8(1);
+                self.put_u32(value);
+            }
+            None => self.put_u8(0),
+        }
+    }
+
+    fn put_varint(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.put_u8(byte);
+                return;
+            }
+            self.put_u8(byte | 0x80);
+        }
+    }
+
+    fn put_short_vec_len(&mut self, len: usize) {
+        // compact-u16 shares the LEB128 layout, capped at three bytes
+        self.put_varint(len as u64);
+    }
+
+    fn put_pubkey(&mut self, pubkey: &Pubkey) {
+        self.put_bytes(pubkey.as_ref());
+    }
+
+    fn put_signature(&mut self, signature: &Signature) {
+        self.put_bytes(signature.as_ref());
+    }
+
+    fn put_ip_addr(&mut self, ip: &IpAddr) {
+        match ip {
+            IpAddr::V4(ip) => {
+                self.put_u32(0);
+                self.put_bytes(&ip.octets());
+            }
+            IpAddr::V6(ip) => {
+                self.put_u32(1);
+                self.put_bytes(&ip.octets());
+            }
+        }
+    }
+
+    fn put_socket_addr(&mut self, addr: &SocketAddr) {
+        self.put_ip_addr(&addr.ip());
+        self.put_u16(addr.port());
+    }
+}
+
+/// Bounds-checked reader tracking the current offset for error reporting
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.offset
+    }
+
+    fn finish(&self) -> Result<(), CodecError> {
+        match self.remaining() {
+            0 => Ok(()),
+            trailing => Err(CodecError::TrailingBytes {
+                trailing,
+                offset: self.offset,
+            }),
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CodecError> {
+        if len > self.remaining() {
+            return Err(CodecError::UnexpectedEof {
+                offset: self.offset,
+                needed: len - self.remaining(),
+            });
+        }
+        let slice = &self.bytes[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(slice)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), CodecError> {
+        self.take(len).map(|_| ())
+    }
+
+    fn get_array<const N: usize>(&mut self) -> Result<[u8; N], CodecError> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn get_u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn get_u16(&mut self) -> Result<u16, CodecError> {
+        Ok(u16::from_le_bytes(self.get_array()?))
+    }
+
+    fn get_u32(&mut self) -> Result<u32, CodecError> {
+        Ok(u32::from_le_bytes(self.get_array()?))
+    }
+
+    fn get_u64(&mut self) -> Result<u64, CodecError> {
+        Ok(u64::from_le_bytes(self.get_array()?))
+    }
+
+    /// Read a `u64` length prefix, rejecting lengths the remaining bytes cannot hold
+    fn get_len(&mut self, min_element_size: usize) -> Result<usize, CodecError> {
+        let offset = self.offset;
+        let len = self.get_u64()?;
+        match usize::try_from(len) {
+            Ok(len) if len.saturating_mul(min_element_size.max(1)) <= self.remaining() => Ok(len),
+            _ => Err(CodecError::InvalidValue {
+                field: "length prefix",
+                offset,
+            }),
+        }
+    }
+
+    fn get_option_tag(&mut self) -> Result<bool, CodecError> {
+        let offset = self.offset;
+        match self.get_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            tag => Err(CodecError::InvalidTag {
+                type_name: "Option",
+                tag: tag.into(),
+                offset,
+            }),
+        }
+    }
+
+    fn get_option_u32(&mut self) -> Result<Option<u32>, CodecError> {
+        match self.get_option_tag()? {
+            true => Ok(Some(self.get_u32()?)),
+            false => Ok(None),
+        }
+    }
+
+    fn get_varint_u64(&mut self) -> Result<u64, CodecError> {
+        let offset = self.offset;
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.get_u8()?;
+            let bits = u64::from(byte & 0x7f);
+            if shift == 63 && bits > 1 {
+                break;
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                // Reject non-canonical encodings with a redundant trailing zero byte
+                if byte == 0 && shift > 0 {
+                    break;
+                }
+                return Ok(value);
+            }
+        }
+        Err(CodecError::InvalidValue {
+            field: "varint",
+            offset,
+        })
+    }
+
+    fn get_varint_u16(&mut self) -> Result<u16, CodecError> {
+        let offset = self.offset;
+        u16::try_from(self.get_varint_u64()?).map_err(|_| CodecError::InvalidValue {
+            field: "varint u16",
+            offset,
+        })
+    }
+
+    fn get_short_vec_len(&mut self) -> Result<usize, CodecError> {
+        let offset = self.offset;
+        let len = usize::from(self.get_varint_u16()?);
+        if self.offset - offset > 3 || len > self.remaining() {
+            return Err(CodecError::InvalidValue {
+                field: "short_vec length",
+                offset,
+            });
+        }
+        Ok(len)
+    }
+
+    fn get_pubkey(&mut self) -> Result<Pubkey, CodecError> {
+        Ok(Pubkey::new_from_array(self.get_array()?))
+    }
+
+    fn get_signature(&mut self) -> Result<Signature, CodecError> {
+        Ok(Signature::from(self.get_array::<64>()?))
+    }
+
+    fn get_ip_addr(&mut self) -> Result<IpAddr, CodecError> {
+        let offset = self.offset;
+        match self.get_u32()? {
+            0 => Ok(IpAddr::V4(Ipv4Addr::from(self.get_array::<4>()?))),
+            1 => Ok(IpAddr::V6(Ipv6Addr::from(self.get_array::<16>()?))),
+            tag => Err(CodecError::InvalidTag {
+                type_name: "IpAddr",
+                tag,
+                offset,
+            }),
+        }
+    }
+
+    fn get_socket_addr(&mut self) -> Result<SocketAddr, CodecError> {
+        let ip = self.get_ip_addr()?;
+        Ok(SocketAddr::new(ip, self.get_u16()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_version() -> ClientVersion {
+        ClientVersion {
+            major: 1,
+            minor: 18,
+            patch: 26,
+            commit: Some(0xdead_beef),
+            feature_set: 4_215_500_110,
+            client: 0,
+        }
+    }
+
+    #[test]
+    fn test_ping_layout_and_roundtrip() {
+        let keypair = Keypair::new();
+        let ping = Ping::new([7u8; 32], &keypair);
+        assert!(ping.verify());
+
+        let bytes = Protocol::PingMessage(ping.clone()).encode().unwrap();
+        assert_eq!(bytes.len(), 4 + 32 + 32 + 64);
+        assert_eq!(&bytes[..4], &[4, 0, 0, 0]);
+        assert_eq!(&bytes[4..36], keypair.pubkey().as_ref());
+        assert_eq!(&bytes[36..68], &[7u8; 32]);
+
+        assert_eq!(
+            Protocol::decode(&bytes).unwrap(),
+            Protocol::PingMessage(ping)
+        );
+    }
+
+    #[test]
+    fn test_pong_answers_ping() {
+        let pinger = Keypair::new();
+        let ponger = Keypair::new();
+        let ping = Ping::new_rand(&pinger);
+        let pong = Pong::new(&ping, &ponger);
+
+        assert_eq!(pong.from, ponger.pubkey());
+        assert!(pong.answers(&ping));
+        assert!(!pong.answers(&Ping::new_rand(&pinger)));
+
+        let bytes = Protocol::PongMessage(pong.clone()).encode().unwrap();
+        assert_eq!(&bytes[..4], &[5, 0, 0, 0]);
+        assert_eq!(
+            Protocol::decode(&bytes).unwrap(),
+            Protocol::PongMessage(pong)
+        );
+    }
+
+    #[test]
+    fn test_pong_hash_uses_sha256_with_prefix() {
+        let token = [42u8; 32];
+        let expected = solana_sdk::hash::hashv(&[b"SOLANA_PING_PONG", &token]);
+        assert_eq!(Pong::hash_token(&token), expected);
+    }
+
+    #[test]
+    fn test_tampered_pong_fails_verification() {
+        let keypair = Keypair::new();
+        let ping = Ping::new_rand(&keypair);
+        let mut pong = Pong::new(&ping, &keypair);
+        pong.from = Pubkey::new_unique();
+        assert!(!pong.verify());
+        assert!(!pong.answers(&ping));
+    }
+
+    #[test]
+    fn test_pull_request_roundtrip_fits_packet() {
+        let keypair = Keypair::new();
+        let contact_info = ContactInfo::new_gossip_only(
+            keypair.pubkey(),
+            "10.0.0.1:8001".parse().unwrap(),
+            4711,
+            test_version(),
+        );
+        let value = CrdsValue::new_signed(CrdsData::ContactInfo(contact_info), &keypair);
+        assert!(value.verify());
+
+        let request = Protocol::PullRequest(CrdsFilter::new_empty(), value);
+        let bytes = request.encode().unwrap();
+        assert!(bytes.len() <= PACKET_DATA_SIZE);
+        assert_eq!(Protocol::decode(&bytes).unwrap(), request);
+    }
+
+    #[test]
+    fn test_contact_info_socket_resolution() {
+        let contact_info = ContactInfo {
+            pubkey: Pubkey::new_unique(),
+            wallclock: 1,
+            outset: 2,
+            shred_version: 3,
+            version: test_version(),
+            addrs: vec!["10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap()],
+            sockets: vec![
+                SocketEntry {
+                    key: SOCKET_TAG_GOSSIP,
+                    index: 0,
+                    offset: 8001,
+                },
+                SocketEntry {
+                    key: SOCKET_TAG_RPC,
+                    index: 1,
+                    offset: 898,
+                },
+            ],
+        };
+
+        assert_eq!(
+            contact_info.gossip(),
+            Some("10.0.0.1:8001".parse().unwrap())
+        );
+        assert_eq!(
+            contact_info.socket(SOCKET_TAG_RPC),
+            Some("10.0.0.2:8899".parse().unwrap())
+        );
+        assert_eq!(contact_info.socket(SOCKET_TAG_TPU), None);
+    }
+
+    #[test]
+    fn test_pull_response_with_mixed_values() {
+        let keypair = Keypair::new();
+        let legacy = LegacyContactInfo {
+            id: keypair.pubkey(),
+            gossip: "127.0.0.1:8001".parse().unwrap(),
+            tvu: "127.0.0.1:8002".parse().unwrap(),
+            tvu_quic: "127.0.0.1:8003".parse().unwrap(),
+            serve_repair_quic: "127.0.0.1:8004".parse().unwrap(),
+            tpu: "127.0.0.1:8005".parse().unwrap(),
+            tpu_forwards: "127.0.0.1:8006".parse().unwrap(),
+            tpu_vote: "127.0.0.1:8007".parse().unwrap(),
+            rpc: "[::1]:8899".parse().unwrap(),
+            rpc_pubsub: "127.0.0.1:8900".parse().unwrap(),
+            serve_repair: "127.0.0.1:8008".parse().unwrap(),
+            wallclock: 123,
+            shred_version: 9,
+        };
+        let version = Version {
+            from: keypair.pubkey(),
+            wallclock: 123,
+            major: 1,
+            minor: 18,
+            patch: 26,
+            commit: None,
+            feature_set: 1,
+        };
+        // RestartHeaviestFork is only kept as raw bytes
+        let mut restart = keypair.pubkey().to_bytes().to_vec();
+        restart.extend_from_slice(&[0u8; 8 + 8 + 32 + 8 + 2]);
+        let other = CrdsData::Other {
+            kind: 13,
+            from: keypair.pubkey(),
+            payload: restart,
+        };
+
+        let values = vec![
+            CrdsValue::new_signed(CrdsData::LegacyContactInfo(legacy), &keypair),
+            CrdsValue::new_signed(CrdsData::Version(version), &keypair),
+            CrdsValue::new_signed(other, &keypair),
+        ];
+        let response = Protocol::PullResponse(keypair.pubkey(), values);
+        let bytes = response.encode().unwrap();
+        let decoded = Protocol::decode(&bytes).unwrap();
+        assert_eq!(decoded, response);
+
+        let Protocol::PullResponse(_, values) = decoded else {
+            panic!("expected pull response");
+        };
+        assert!(values.iter().all(CrdsValue::verify));
+        assert_eq!(values[2].data.kind(), 13);
+    }
+
+    #[test]
+    fn test_decode_errors_report_offsets() {
+        let keypair = Keypair::new();
+        let bytes = Protocol::PingMessage(Ping::new_rand(&keypair))
+            .encode()
+            .unwrap();
+
+        let truncated = Protocol::decode(&bytes[..100]).unwrap_err();
+        assert!(matches!(
+            truncated,
+            CodecError::UnexpectedEof { offset: 68, .. }
+        ));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            Protocol::decode(&trailing).unwrap_err(),
+            CodecError::TrailingBytes {
+                trailing: 1,
+                offset: bytes.len()
+            }
+        );
+
+        let mut bad_tag = bytes;
+        bad_tag[0] = 9;
+        assert_eq!(Protocol::decode(&bad_tag).unwrap_err().offset(), Some(0));
+    }
+
+    #[test]
+    fn test_varint_roundtrip() {
+        for value in [0u64, 1, 127, 128, 300, 16_383, 16_384, u64::MAX] {
+            let mut encoder = Encoder::default();
+            encoder.put_varint(value);
+            let bytes = encoder.into_inner();
+            let mut decoder = Decoder::new(&bytes);
+            assert_eq!(decoder.get_varint_u64().unwrap(), value);
+            assert!(decoder.finish().is_ok());
+        }
+    }
+}
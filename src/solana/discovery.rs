@@ -0,0 +1,226 @@
+//! Peer discovery starting from cluster entrypoints
+//!
+//! `PeerDiscovery` handshakes with a set of entrypoints, learns the gossip
+//! addresses of other nodes from the contact info they return, and handshakes
+//! with those in turn, one hop at a time. Every peer that completes a
+//! handshake is recorded in a `PeerRegistry`, the same registry type a
+//! `GossipNode` keeps.
+
+use crate::solana::domain::{AdvertisedPeer, GossipNodeInfo, PeerDetails, SolanaNodeError};
+use crate::solana::handshake_client::HandshakeClient;
+use crate::solana::peer_registry::PeerRegistry;
+use futures::stream::{self, StreamExt};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use tracing::{debug, info};
+
+/// Default number of hops followed from the entrypoints
+pub const DEFAULT_MAX_DEPTH: u32 = 2;
+
+/// Default maximum number of nodes handshaked with during one discovery run
+pub const DEFAULT_MAX_PEERS: usize = 256;
+
+/// Limits on how far discovery spreads from the entrypoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiscoveryConfig {
+    /// Hops followed from the entrypoints; 0 handshakes with the entrypoints only
+    pub max_depth: u32,
+
+    /// Maximum number of nodes handshaked with, entrypoints included
+    pub max_peers: usize,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_peers: DEFAULT_MAX_PEERS,
+        }
+    }
+}
+
+/// A node discovery attempted a handshake with
+#[derive(Debug)]
+pub struct DiscoveredNode {
+    /// Address the handshake was sent to
+    pub addr: SocketAddr,
+
+    /// Hops from the entrypoints, 0 for an entrypoint
+    pub depth: u32,
+
+    /// How the node was advertised to us, `None` for an entrypoint
+    pub advertised: Option<AdvertisedPeer>,
+
+    /// What the handshake learned, or why it failed
+    pub result: Result<PeerDetails, SolanaNodeError>,
+}
+
+/// Outcome of a discovery run
+#[derive(Debug, Default)]
+pub struct DiscoveryReport {
+    /// Every node a handshake was attempted with, in the order they were reached
+    pub nodes: Vec<DiscoveredNode>,
+
+    /// Who told us about whom, as the advertising peer's pubkey and the node it advertised
+    ///
+    /// Includes nodes that were already known or left unvisited.
+    pub edges: Vec<(Pubkey, AdvertisedPeer)>,
+
+    /// Advertised nodes left unvisited because the depth or peer limit was reached
+    pub unvisited: Vec<AdvertisedPeer>,
+
+    /// Deepest hop at which handshakes were attempted
+    pub depth_reached: u32,
+}
+
+impl DiscoveryReport {
+    /// Number of nodes a handshake was attempted with
+    pub fn attempted(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Peers that completed a handshake, in the order they were reached
+    pub fn peers(&self) -> impl Iterator<Item = &GossipNodeInfo> {
+        self.nodes
+            .iter()
+            .filter_map(|node| node.result.as_ref().ok())
+            .map(|details| &details.peer_info)
+    }
+
+    /// Nodes that were handshaked with unsuccessfully, and why
+    pub fn failures(&self) -> impl Iterator<Item = (SocketAddr, &SolanaNodeError)> {
+        self.nodes
+            .iter()
+            .filter_map(|node| node.result.as_ref().err().map(|e| (node.addr, e)))
+    }
+}
+
+/// Breadth-first crawler that follows the contact info peers advertise
+#[derive(Debug, Clone, Default)]
+pub struct PeerDiscovery {
+    /// Limits on depth and number of peers
+    config: DiscoveryConfig,
+
+    /// Registry discovered peers are recorded in
+    registry: PeerRegistry,
+}
+
+impl PeerDiscovery {
+    /// Create a discovery run with its own empty registry
+    pub fn new(config: DiscoveryConfig) -> Self {
+        Self {
+            config,
+            registry: PeerRegistry::new(),
+        }
+    }
+
+    /// Record discovered peers in `registry` instead of a private one
+    pub fn with_peer_registry(mut self, registry: PeerRegistry) -> Self {
+        self.registry = registry;
+        self
+    }
+
+    /// Handle to the registry discovered peers are recorded in
+    pub fn peer_registry(&self) -> PeerRegistry {
+        self.registry.clone()
+    }
+
+    /// Discover peers reachable from `entrypoints`
+    ///
+    /// Each hop is handshaked with concurrently, up to the client's batch
+    /// concurrency. Nodes are identified by pubkey as well as address, so a
+    /// node advertised by several peers, or reached through an entrypoint's
+    /// DNS name, is only handshaked with once.
+    pub async fn run(
+        &self,
+        client: &HandshakeClient,
+        entrypoints: Vec<SocketAddr>,
+    ) -> DiscoveryReport {
+        let mut report = DiscoveryReport::default();
+        let mut seen_addrs = HashSet::new();
+        let mut seen_pubkeys: HashSet<Pubkey> = HashSet::new();
+
+        let mut frontier: Vec<(SocketAddr, Option<AdvertisedPeer>)> = entrypoints
+            .into_iter()
+            .filter(|addr| seen_addrs.insert(*addr))
+            .take(self.config.max_peers)
+            .map(|addr| (addr, None))
+            .collect();
+        let mut visited = frontier.len();
+        let mut depth = 0;
+
+        while !frontier.is_empty() {
+            report.depth_reached = depth;
+            info!(
+                depth,
+                peer_count = frontier.len(),
+                "Handshaking with discovered peers"
+            );
+
+            let results: Vec<_> = stream::iter(frontier)
+                .map(|(addr, advertised)| async move {
+                    DiscoveredNode {
+                        addr,
+                        depth,
+                        advertised,
+                        result: client.perform_discovery_handshake(addr).await,
+                    }
+                })
+                .buffer_unordered(client.concurrency())
+                .collect()
+                .await;
+
+            // Register the whole hop first, so nodes advertised by one peer and
+            // reached directly as another are not visited twice
+            let first_new = report.nodes.len();
+            for node in results {
+                match &node.result {
+                    Ok(details) => {
+                        seen_pubkeys.insert(details.peer_info.peer_pubkey);
+                        self.registry.upsert(details.peer_info.clone());
+                    }
+                    Err(e) => {
+                        debug!(peer_addr = %node.addr, error = %e, "Discovery handshake failed");
+                    }
+                }
+                report.nodes.push(node);
+            }
+
+            let mut next = Vec::new();
+            for details in report.nodes[first_new..]
+                .iter()
+                .filter_map(|node| node.result.as_ref().ok())
+            {
+                for peer in &details.advertised {
+                    report
+                        .edges
+                        .push((details.peer_info.peer_pubkey, peer.clone()));
+
+                    if !seen_pubkeys.insert(peer.pubkey) || !seen_addrs.insert(peer.gossip_addr) {
+                        continue;
+                    }
+                    if depth < self.config.max_depth && visited < self.config.max_peers {
+                        next.push((peer.gossip_addr, Some(peer.clone())));
+                        visited += 1;
+                    } else {
+                        report.unvisited.push(peer.clone());
+                    }
+                }
+            }
+
+            frontier = next;
+            depth += 1;
+        }
+
+        info!(
+            discovered_peers = report.peers().count(),
+            failed_handshakes = report.failures().count(),
+            depth_reached = report.depth_reached,
+            unvisited = report.unvisited.len(),
+            "Peer discovery completed"
+        );
+
+        report
+    }
+}
@@ -3,10 +3,14 @@
 //! This module defines the core data types, error handling, and serialization
 //! formats used throughout the Solana P2P networking implementation.
 
+use crate::solana::codec::CodecError;
+use crate::telemetry::generate_correlation_id;
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
 use std::net::SocketAddr;
+use std::str::FromStr;
 use thiserror::Error;
 
 /// Solana network identifiers as constants
@@ -17,11 +21,21 @@ pub mod network {
     pub const MAINNET_BETA: &str = "mainnet-beta";
 }
 
-/// Protocol version constant
-pub const DEFAULT_PROTOCOL_VERSION: &str = "1.18.0";
+/// Highest handshake protocol version this implementation speaks
+pub const DEFAULT_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::new(1, 18, 0);
+
+/// Oldest handshake protocol version this implementation still accepts
+pub const MIN_SUPPORTED_PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion::new(1, 17, 0);
+
+/// Largest gossip packet handled in full by default (1280 bytes)
+pub const DEFAULT_MAX_PACKET_SIZE: usize = 1280;
 
 /// Default capabilities for Solana nodes
-pub const DEFAULT_CAPABILITIES: &[&str] = &["gossip", "handshake"];
+pub const DEFAULT_CAPABILITIES: CapabilitySet =
+    CapabilitySet::from_capabilities(&[Capability::Gossip, Capability::Handshake]);
+
+/// Oldest handshake response, in seconds, that is still accepted
+pub const MAX_RESPONSE_AGE_SECS: u64 = 300;
 
 /// Comprehensive error types for Solana node operations
 #[derive(Error, Debug)]
@@ -29,36 +43,676 @@ pub enum SolanaNodeError {
     #[error("Network connection failed: {0}")]
     NetworkError(#[from] std::io::Error),
 
-    #[error("Handshake protocol error: {message}")]
-    HandshakeError { message: String },
+    #[error("A handshake with {peer_addr} is already in progress")]
+    HandshakeInProgress { peer_addr: SocketAddr },
 
-    #[error("Invalid peer data received: {reason}")]
-    InvalidPeerData { reason: String },
+    #[error("Handshake client stopped receiving: {reason}")]
+    ReceiverStopped { reason: String },
+
+    #[error("Cannot advertise {addr} to peers: {reason}")]
+    InvalidAdvertiseAddr { addr: SocketAddr, reason: String },
 
     #[error("Serialization failed: {0}")]
     SerializationFailed(String),
 
+    #[error("Failed to decode packet at offset {offset}: {reason}")]
+    DecodeError { offset: usize, reason: String },
+
+    #[error("Invalid signature on message from {peer_addr}")]
+    SignatureInvalid { peer_addr: SocketAddr },
+
     #[error("Peer verification failed: {peer_addr}")]
     PeerVerificationFailed { peer_addr: SocketAddr },
 
     #[error("Protocol version mismatch. Expected: {expected}, Got: {received}")]
     ProtocolVersionMismatch { expected: String, received: String },
 
-    #[error("Timeout occurred during operation")]
-    TimeoutError,
+    #[error("Network mismatch. Expected: {expected}, Got: {received}")]
+    NetworkMismatch { expected: String, received: String },
+
+    #[error("Peer is missing required capabilities: {missing}")]
+    MissingCapabilities { missing: CapabilitySet },
+
+    #[error("Peer rejected the handshake ({reason}): {message}")]
+    RemoteRejected {
+        reason: RejectReason,
+        message: String,
+    },
+
+    #[error("Message timestamp is {age_secs}s old, the limit is {max_age_secs}s")]
+    StaleTimestamp { age_secs: u64, max_age_secs: u64 },
+
+    #[error("Timed out sending to {peer_addr}")]
+    SendTimeout { peer_addr: SocketAddr },
+
+    #[error("Timed out waiting for a reply from {peer_addr}")]
+    ReceiveTimeout { peer_addr: SocketAddr },
+
+    #[error("Gossip protocol codec error: {0}")]
+    CodecError(#[from] CodecError),
+
+    #[error("Rejected handshake request from {peer_pubkey}: {reason}")]
+    ReplayedRequest { peer_pubkey: Pubkey, reason: String },
+
+    #[error("Node identity error ({path}): {reason}")]
+    IdentityError { path: String, reason: String },
+
+    #[error("Access policy error ({path}): {reason}")]
+    AccessPolicyError { path: String, reason: String },
+
+    #[error("Peer store error ({path}): {reason}")]
+    PeerStoreError { path: String, reason: String },
+
+    #[error("Topology report error ({path}): {reason}")]
+    ReportError { path: String, reason: String },
 
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
 }
 
+impl SolanaNodeError {
+    /// Kind of failure, used as a metrics label and in handshake results
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            SolanaNodeError::NetworkError(_) => ErrorKind::Network,
+            SolanaNodeError::HandshakeInProgress { .. } => ErrorKind::InProgress,
+            SolanaNodeError::ReceiverStopped { .. } => ErrorKind::ReceiverStopped,
+            SolanaNodeError::InvalidAdvertiseAddr { .. } => ErrorKind::InvalidAdvertiseAddr,
+            SolanaNodeError::SerializationFailed(_) => ErrorKind::Serialization,
+            SolanaNodeError::DecodeError { .. } => ErrorKind::Decode,
+            SolanaNodeError::SignatureInvalid { .. } => ErrorKind::SignatureInvalid,
+            SolanaNodeError::PeerVerificationFailed { .. } => ErrorKind::PeerVerification,
+            SolanaNodeError::ProtocolVersionMismatch { .. } => ErrorKind::VersionMismatch,
+            SolanaNodeError::NetworkMismatch { .. } => ErrorKind::NetworkMismatch,
+            SolanaNodeError::MissingCapabilities { .. } => ErrorKind::MissingCapabilities,
+            SolanaNodeError::RemoteRejected { .. } => ErrorKind::RemoteRejected,
+            SolanaNodeError::StaleTimestamp { .. } => ErrorKind::StaleTimestamp,
+            SolanaNodeError::SendTimeout { .. } => ErrorKind::SendTimeout,
+            SolanaNodeError::ReceiveTimeout { .. } => ErrorKind::ReceiveTimeout,
+            // Only decoding reports an offset; encoding fails on oversized packets
+            SolanaNodeError::CodecError(e) if e.offset().is_some() => ErrorKind::Decode,
+            SolanaNodeError::CodecError(_) => ErrorKind::Serialization,
+            SolanaNodeError::ReplayedRequest { .. } => ErrorKind::ReplayedRequest,
+            SolanaNodeError::IdentityError { .. } => ErrorKind::Identity,
+            SolanaNodeError::AccessPolicyError { .. } => ErrorKind::AccessPolicy,
+            SolanaNodeError::PeerStoreError { .. } => ErrorKind::PeerStore,
+            SolanaNodeError::ReportError { .. } => ErrorKind::Report,
+            SolanaNodeError::UnexpectedError(_) => ErrorKind::Unexpected,
+        }
+    }
+
+    /// Whether another attempt at the same handshake could succeed
+    ///
+    /// Incompatibilities between the peers, failed verification and local
+    /// misconfiguration are permanent; lost or garbled packets and rejections
+    /// the peer gave no lasting reason for are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SolanaNodeError::RemoteRejected { reason, .. } => reason.is_retryable(),
+            other => other.kind().is_retryable(),
+        }
+    }
+}
+
+/// Kind of a `SolanaNodeError`, without the details of the failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ErrorKind {
+    Network,
+    InProgress,
+    ReceiverStopped,
+    InvalidAdvertiseAddr,
+    Serialization,
+    Decode,
+    SignatureInvalid,
+    PeerVerification,
+    VersionMismatch,
+    NetworkMismatch,
+    MissingCapabilities,
+    RemoteRejected,
+    StaleTimestamp,
+    SendTimeout,
+    ReceiveTimeout,
+    ReplayedRequest,
+    Identity,
+    AccessPolicy,
+    PeerStore,
+    Report,
+    Unexpected,
+}
+
+impl ErrorKind {
+    /// Every kind, in declaration order
+    pub const ALL: [ErrorKind; 21] = [
+        ErrorKind::Network,
+        ErrorKind::InProgress,
+        ErrorKind::ReceiverStopped,
+        ErrorKind::InvalidAdvertiseAddr,
+        ErrorKind::Serialization,
+        ErrorKind::Decode,
+        ErrorKind::SignatureInvalid,
+        ErrorKind::PeerVerification,
+        ErrorKind::VersionMismatch,
+        ErrorKind::NetworkMismatch,
+        ErrorKind::MissingCapabilities,
+        ErrorKind::RemoteRejected,
+        ErrorKind::StaleTimestamp,
+        ErrorKind::SendTimeout,
+        ErrorKind::ReceiveTimeout,
+        ErrorKind::ReplayedRequest,
+        ErrorKind::Identity,
+        ErrorKind::AccessPolicy,
+        ErrorKind::PeerStore,
+        ErrorKind::Report,
+        ErrorKind::Unexpected,
+    ];
+
+    /// Short snake_case name of the kind
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Network => "network",
+            ErrorKind::InProgress => "in_progress",
+            ErrorKind::ReceiverStopped => "receiver_stopped",
+            ErrorKind::InvalidAdvertiseAddr => "invalid_advertise_addr",
+            ErrorKind::Serialization => "serialization",
+            ErrorKind::Decode => "decode",
+            ErrorKind::SignatureInvalid => "signature_invalid",
+            ErrorKind::PeerVerification => "peer_verification",
+            ErrorKind::VersionMismatch => "version_mismatch",
+            ErrorKind::NetworkMismatch => "network_mismatch",
+            ErrorKind::MissingCapabilities => "missing_capabilities",
+            ErrorKind::RemoteRejected => "remote_rejected",
+            ErrorKind::StaleTimestamp => "stale_timestamp",
+            ErrorKind::SendTimeout => "send_timeout",
+            ErrorKind::ReceiveTimeout => "receive_timeout",
+            ErrorKind::ReplayedRequest => "replayed_request",
+            ErrorKind::Identity => "identity",
+            ErrorKind::AccessPolicy => "access_policy",
+            ErrorKind::PeerStore => "peer_store",
+            ErrorKind::Report => "report",
+            ErrorKind::Unexpected => "unexpected",
+        }
+    }
+
+    /// Whether failures of this kind are worth retrying
+    ///
+    /// Remote rejections count as retryable here; `SolanaNodeError::is_retryable`
+    /// also looks at the reason the peer gave.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ErrorKind::Network
+                | ErrorKind::Decode
+                | ErrorKind::RemoteRejected
+                | ErrorKind::SendTimeout
+                | ErrorKind::ReceiveTimeout
+                | ErrorKind::Unexpected
+        )
+    }
+}
+
+impl FromStr for ErrorKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase().replace('-', "_");
+        ErrorKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == name)
+            .ok_or_else(|| {
+                let valid: Vec<_> = ErrorKind::ALL.iter().map(ErrorKind::as_str).collect();
+                format!(
+                    "Invalid error kind: '{}'. Valid options: {}",
+                    s,
+                    valid.join(", ")
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Why a node refused a handshake request, carried in the failure response
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+pub enum RejectReason {
+    /// The node gave no reason
+    Unspecified,
+    /// The request was for a different network
+    NetworkMismatch,
+    /// No protocol version is supported by both sides
+    UnsupportedVersion,
+    /// The request did not offer capabilities the node requires
+    MissingCapabilities,
+    /// The node's access policy denies the requester
+    AccessDenied,
+    /// The request timestamp is outside the node's acceptance window
+    StaleRequest,
+    /// The node has already accepted a request with the same nonce
+    ReplayedRequest,
+}
+
+impl RejectReason {
+    /// Whether a fresh request could be accepted
+    ///
+    /// A replayed request is answered by sending a new one, which carries a
+    /// new nonce. Every other reason holds until one of the nodes changes its
+    /// configuration or clock.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            RejectReason::Unspecified | RejectReason::ReplayedRequest
+        )
+    }
+}
+
+impl std::fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RejectReason::Unspecified => write!(f, "unspecified"),
+            RejectReason::NetworkMismatch => write!(f, "network_mismatch"),
+            RejectReason::UnsupportedVersion => write!(f, "unsupported_version"),
+            RejectReason::MissingCapabilities => write!(f, "missing_capabilities"),
+            RejectReason::AccessDenied => write!(f, "access_denied"),
+            RejectReason::StaleRequest => write!(f, "stale_request"),
+            RejectReason::ReplayedRequest => write!(f, "replayed_request"),
+        }
+    }
+}
+
+/// Decode a Borsh message that must take up all of `bytes`
+///
+/// Failures report the offset at which decoding stopped.
+fn decode_borsh<T: BorshDeserialize>(bytes: &[u8]) -> Result<T, SolanaNodeError> {
+    let mut remaining = bytes;
+    let decoded = T::deserialize(&mut remaining);
+    let offset = bytes.len() - remaining.len();
+
+    match decoded {
+        Ok(_) if !remaining.is_empty() => Err(SolanaNodeError::DecodeError {
+            offset,
+            reason: format!("{} trailing bytes", remaining.len()),
+        }),
+        Ok(value) => Ok(value),
+        Err(e) => Err(SolanaNodeError::DecodeError {
+            offset,
+            reason: e.to_string(),
+        }),
+    }
+}
+
+/// Wire protocol used to perform a handshake with a peer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HandshakeMode {
+    /// Borsh `HandshakeRequest`/`HandshakeResponse` exchange understood by `GossipNode`
+    #[default]
+    Legacy,
+    /// Solana gossip ping/pong and pull exchange understood by real validators
+    Gossip,
+}
+
+impl FromStr for HandshakeMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "legacy" | "borsh" => Ok(HandshakeMode::Legacy),
+            "gossip" | "solana" => Ok(HandshakeMode::Gossip),
+            _ => Err(format!(
+                "Invalid handshake mode: '{}'. Valid options: legacy, gossip",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for HandshakeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HandshakeMode::Legacy => write!(f, "legacy"),
+            HandshakeMode::Gossip => write!(f, "gossip"),
+        }
+    }
+}
+
+/// Version of the handshake protocol, compared as `major.minor.patch`
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, BorshSerialize, BorshDeserialize,
+)]
+pub struct ProtocolVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl ProtocolVersion {
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl std::fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for ProtocolVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid protocol version: '{}'. Expected major.minor.patch",
+                s
+            )
+        };
+
+        let mut parts = s.trim().splitn(3, '.');
+        let mut next = || {
+            parts
+                .next()
+                .and_then(|part| part.parse::<u16>().ok())
+                .ok_or_else(invalid)
+        };
+        Ok(Self::new(next()?, next()?, next()?))
+    }
+}
+
+// Serialized as its "major.minor.patch" string so JSON output stays readable
+impl Serialize for ProtocolVersion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProtocolVersion {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <String as Deserialize>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Inclusive range of protocol versions a node is willing to speak
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+pub struct VersionRange {
+    /// Oldest supported version
+    pub min: ProtocolVersion,
+
+    /// Newest supported version
+    pub max: ProtocolVersion,
+}
+
+impl Default for VersionRange {
+    fn default() -> Self {
+        Self::new(MIN_SUPPORTED_PROTOCOL_VERSION, DEFAULT_PROTOCOL_VERSION)
+    }
+}
+
+impl VersionRange {
+    pub const fn new(min: ProtocolVersion, max: ProtocolVersion) -> Self {
+        Self { min, max }
+    }
+
+    /// Whether `version` falls within the range
+    pub fn contains(&self, version: ProtocolVersion) -> bool {
+        self.min <= version && version <= self.max
+    }
+
+    /// Highest version supported by both ranges, if they overlap
+    pub fn negotiate(&self, other: &VersionRange) -> Option<ProtocolVersion> {
+        let highest = self.max.min(other.max);
+        let lowest = self.min.max(other.min);
+        (lowest <= highest).then_some(highest)
+    }
+}
+
+impl std::fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, ">={}, <={}", self.min, self.max)
+    }
+}
+
+/// A feature a node can offer during the handshake
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Takes part in gossip (ping/pong, pull requests)
+    Gossip,
+    /// Answers signed handshake requests
+    Handshake,
+}
+
+impl Capability {
+    /// Every capability known to this version
+    pub const ALL: [Capability; 2] = [Capability::Gossip, Capability::Handshake];
+
+    /// Wire bit assigned to the capability; never reuse a retired bit
+    pub const fn bit(self) -> u64 {
+        match self {
+            Capability::Gossip => 1 << 0,
+            Capability::Handshake => 1 << 1,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::Gossip => "gossip",
+            Capability::Handshake => "handshake",
+        }
+    }
+}
+
+impl FromStr for Capability {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Capability::ALL
+            .into_iter()
+            .find(|capability| capability.name() == s.to_lowercase())
+            .ok_or_else(|| {
+                format!(
+                    "Invalid capability: '{}'. Valid options: gossip, handshake",
+                    s
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Set of capabilities, encoded on the wire as a bitmask
+///
+/// Bits not assigned to a known `Capability` are kept rather than dropped, so
+/// a node relays and intersects capabilities introduced by newer peers
+/// correctly. In JSON the set is a list of names, with unknown bits written
+/// as `"unknown-<bit>"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, BorshSerialize, BorshDeserialize)]
+pub struct CapabilitySet(u64);
+
+impl CapabilitySet {
+    /// The empty set
+    pub const EMPTY: CapabilitySet = CapabilitySet(0);
+
+    pub const fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    pub const fn from_capabilities(capabilities: &[Capability]) -> Self {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < capabilities.len() {
+            bits |= capabilities[i].bit();
+            i += 1;
+        }
+        Self(bits)
+    }
+
+    /// Raw bitmask, including unknown bits
+    pub const fn bits(self) -> u64 {
+        self.0
+    }
+
+    pub fn contains(self, capability: Capability) -> bool {
+        self.0 & capability.bit() != 0
+    }
+
+    /// Whether every capability in `other` is also in this set
+    pub fn contains_all(self, other: CapabilitySet) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, capability: Capability) {
+        self.0 |= capability.bit();
+    }
+
+    pub fn union(self, other: CapabilitySet) -> CapabilitySet {
+        Self(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: CapabilitySet) -> CapabilitySet {
+        Self(self.0 & other.0)
+    }
+
+    /// Capabilities in this set that are not in `other`
+    pub fn difference(self, other: CapabilitySet) -> CapabilitySet {
+        Self(self.0 & !other.0)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Known capabilities in the set
+    pub fn iter(self) -> impl Iterator<Item = Capability> {
+        Capability::ALL
+            .into_iter()
+            .filter(move |capability| self.contains(*capability))
+    }
+
+    /// Bits set that do not correspond to a known capability
+    pub fn unknown_bits(self) -> u64 {
+        let known = Self::from_capabilities(&Capability::ALL).0;
+        self.0 & !known
+    }
+
+    /// Names of every capability in the set, including unknown ones
+    fn names(self) -> Vec<String> {
+        let unknown = self.unknown_bits();
+        self.iter()
+            .map(|capability| capability.name().to_string())
+            .chain(
+                (0..u64::BITS)
+                    .filter(|bit| unknown & (1 << bit) != 0)
+                    .map(|bit| format!("unknown-{}", bit)),
+            )
+            .collect()
+    }
+}
+
+impl From<Capability> for CapabilitySet {
+    fn from(capability: Capability) -> Self {
+        Self(capability.bit())
+    }
+}
+
+impl FromIterator<Capability> for CapabilitySet {
+    fn from_iter<I: IntoIterator<Item = Capability>>(iter: I) -> Self {
+        let mut set = Self::EMPTY;
+        for capability in iter {
+            set.insert(capability);
+        }
+        set
+    }
+}
+
+impl std::fmt::Display for CapabilitySet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "none");
+        }
+        write!(f, "{}", self.names().join(","))
+    }
+}
+
+impl FromStr for CapabilitySet {
+    type Err = String;
+
+    /// Parse a comma-separated list of capability names
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut set = Self::EMPTY;
+        for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            match name.strip_prefix("unknown-") {
+                Some(bit) => {
+                    let bit: u32 = bit
+                        .parse()
+                        .ok()
+                        .filter(|bit| *bit < u64::BITS)
+                        .ok_or_else(|| format!("Invalid capability: '{}'", name))?;
+                    set.0 |= 1 << bit;
+                }
+                None => set.insert(name.parse()?),
+            }
+        }
+        Ok(set)
+    }
+}
+
+impl Serialize for CapabilitySet {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.names())
+    }
+}
+
+impl<'de> Deserialize<'de> for CapabilitySet {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let names = <Vec<String> as Deserialize>::deserialize(deserializer)?;
+        names.join(",").parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// Ed25519 signature carried by handshake messages
+///
+/// Wraps `solana_sdk::signature::Signature`, which has no Borsh encoding of
+/// its own; on the wire it is the raw 64 signature bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MessageSignature(pub Signature);
+
+impl BorshSerialize for MessageSignature {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(self.0.as_ref())
+    }
+}
+
+impl BorshDeserialize for MessageSignature {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let bytes: [u8; 64] = BorshDeserialize::deserialize(buf)?;
+        Ok(Self(Signature::from(bytes)))
+    }
+}
+
 /// Outgoing handshake request data structure
 #[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct HandshakeRequest {
     /// Public key of the requesting node
     pub node_pubkey: Pubkey,
 
-    /// Protocol version supported by this node
-    pub protocol_version: String,
+    /// Protocol versions supported by this node
+    pub supported_versions: VersionRange,
 
     /// Timestamp of the request
     pub timestamp: u64,
@@ -66,8 +720,18 @@ pub struct HandshakeRequest {
     /// Network ID (mainnet-beta, testnet, devnet, localnet)
     pub network_id: String,
 
-    /// Additional node capabilities and features
-    pub capabilities: Vec<String>,
+    /// Capabilities offered by the requesting node
+    pub capabilities: CapabilitySet,
+
+    /// Random request id echoed back in the matching response
+    pub nonce: u64,
+
+    /// Id shared by every attempt of this handshake, recorded on the spans
+    /// of both the client and the server so their traces can be joined
+    pub correlation_id: String,
+
+    /// Signature by `node_pubkey` over the rest of the request
+    pub signature: MessageSignature,
 }
 
 impl HandshakeRequest {
@@ -75,13 +739,16 @@ impl HandshakeRequest {
     pub fn new_local(node_pubkey: Pubkey) -> Self {
         Self {
             node_pubkey,
-            protocol_version: DEFAULT_PROTOCOL_VERSION.to_string(),
+            supported_versions: VersionRange::default(),
             timestamp: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_default()
                 .as_secs(),
             network_id: network::LOCALNET.to_string(),
-            capabilities: DEFAULT_CAPABILITIES.iter().map(|s| s.to_string()).collect(),
+            capabilities: DEFAULT_CAPABILITIES,
+            nonce: rand::random(),
+            correlation_id: generate_correlation_id(),
+            signature: MessageSignature::default(),
         }
     }
 
@@ -98,6 +765,30 @@ impl HandshakeRequest {
         request.network_id = network::MAINNET_BETA.to_string();
         request
     }
+
+    /// Decode a request from a packet
+    pub fn decode(bytes: &[u8]) -> Result<Self, SolanaNodeError> {
+        decode_borsh(bytes)
+    }
+
+    /// Canonical bytes covered by the signature: the Borsh encoding with a zeroed signature
+    pub fn signable_bytes(&self) -> Vec<u8> {
+        let mut unsigned = self.clone();
+        unsigned.signature = MessageSignature::default();
+        borsh::to_vec(&unsigned).expect("Borsh serialization into a Vec cannot fail")
+    }
+
+    /// Sign the request with the node's keypair
+    pub fn sign(&mut self, keypair: &Keypair) {
+        self.signature = MessageSignature(keypair.sign_message(&self.signable_bytes()));
+    }
+
+    /// Check that the request was signed by `node_pubkey`
+    pub fn verify_signature(&self) -> bool {
+        self.signature
+            .0
+            .verify(self.node_pubkey.as_ref(), &self.signable_bytes())
+    }
 }
 
 /// Incoming handshake response data structure
@@ -106,8 +797,11 @@ pub struct HandshakeResponse {
     /// Public key of the responding peer
     pub peer_pubkey: Pubkey,
 
-    /// Protocol version of the peer
-    pub protocol_version: String,
+    /// Negotiated protocol version (the peer's newest version on failure)
+    pub protocol_version: ProtocolVersion,
+
+    /// Protocol versions supported by the peer
+    pub supported_versions: VersionRange,
 
     /// Network ID of the peer
     pub network_id: String,
@@ -115,8 +809,8 @@ pub struct HandshakeResponse {
     /// Peer's advertised socket address for gossip
     pub gossip_addr: SocketAddr,
 
-    /// Peer capabilities and supported features
-    pub capabilities: Vec<String>,
+    /// Capabilities agreed for the session: those offered that the peer also supports
+    pub capabilities: CapabilitySet,
 
     /// Response timestamp
     pub timestamp: u64,
@@ -126,13 +820,78 @@ pub struct HandshakeResponse {
 
     /// Optional error message if handshake failed
     pub error_message: Option<String>,
+
+    /// Why the handshake was rejected, if it failed
+    pub reject_reason: Option<RejectReason>,
+
+    /// Nonce of the request this response answers
+    pub nonce: u64,
+
+    /// Signature by `peer_pubkey` over the rest of the response
+    pub signature: MessageSignature,
 }
 
 impl HandshakeResponse {
+    /// Decode a response from a packet
+    pub fn decode(bytes: &[u8]) -> Result<Self, SolanaNodeError> {
+        decode_borsh(bytes)
+    }
+
+    /// Canonical bytes covered by the signature: the Borsh encoding with a zeroed signature
+    pub fn signable_bytes(&self) -> Vec<u8> {
+        let mut unsigned = self.clone();
+        unsigned.signature = MessageSignature::default();
+        borsh::to_vec(&unsigned).expect("Borsh serialization into a Vec cannot fail")
+    }
+
+    /// Sign the response with the responding node's keypair
+    pub fn sign(&mut self, keypair: &Keypair) {
+        self.signature = MessageSignature(keypair.sign_message(&self.signable_bytes()));
+    }
+
+    /// Check that the response was signed by `peer_pubkey`
+    pub fn verify_signature(&self) -> bool {
+        self.signature
+            .0
+            .verify(self.peer_pubkey.as_ref(), &self.signable_bytes())
+    }
+
     /// Validate the handshake response against expected criteria
-    pub fn verify(&self, expected_network: &str) -> Result<(), SolanaNodeError> {
+    ///
+    /// Network and version incompatibilities are reported as such even when
+    /// the peer signals failure, so callers can tell them apart from other
+    /// rejections.
+    pub fn verify(
+        &self,
+        expected_network: &str,
+        supported_versions: &VersionRange,
+    ) -> Result<(), SolanaNodeError> {
+        if self.network_id != expected_network {
+            return Err(SolanaNodeError::NetworkMismatch {
+                expected: expected_network.to_string(),
+                received: self.network_id.clone(),
+            });
+        }
+
+        match supported_versions.negotiate(&self.supported_versions) {
+            None => {
+                return Err(SolanaNodeError::ProtocolVersionMismatch {
+                    expected: supported_versions.to_string(),
+                    received: self.supported_versions.to_string(),
+                });
+            }
+            Some(negotiated) if self.success && negotiated != self.protocol_version => {
+                return Err(SolanaNodeError::ProtocolVersionMismatch {
+                    expected: negotiated.to_string(),
+                    received: self.protocol_version.to_string(),
+                });
+            }
+            Some(_) => {}
+        }
+
         if !self.success {
-            return Err(SolanaNodeError::HandshakeError {
+            return Err(SolanaNodeError::RemoteRejected {
+                reason: self.reject_reason.unwrap_or(RejectReason::Unspecified),
                 message: self
                     .error_message
                     .clone()
@@ -140,22 +899,17 @@ impl HandshakeResponse {
             });
         }
 
-        if self.network_id != expected_network {
-            return Err(SolanaNodeError::ProtocolVersionMismatch {
-                expected: expected_network.to_string(),
-                received: self.network_id.clone(),
-            });
-        }
-
-        // Verify the timestamp is recent (within 5 minutes)
+        // Verify the timestamp is recent
         let current_time = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap_or_default()
             .as_secs();
 
-        if current_time.saturating_sub(self.timestamp) > 300 {
-            return Err(SolanaNodeError::InvalidPeerData {
-                reason: "Response timestamp too old".to_string(),
+        let age_secs = current_time.saturating_sub(self.timestamp);
+        if age_secs > MAX_RESPONSE_AGE_SECS {
+            return Err(SolanaNodeError::StaleTimestamp {
+                age_secs,
+                max_age_secs: MAX_RESPONSE_AGE_SECS,
             });
         }
 
@@ -182,11 +936,15 @@ pub struct GossipNodeInfo {
     /// Protocol version supported by the peer
     pub protocol_version: String,
 
+    /// Handshake protocol version agreed with the peer, if one was negotiated
+    #[serde(default)]
+    pub negotiated_version: Option<ProtocolVersion>,
+
     /// Network identifier (mainnet-beta, testnet, etc.)
     pub network_id: String,
 
-    /// Peer capabilities and features
-    pub capabilities: Vec<String>,
+    /// Capabilities agreed with the peer
+    pub capabilities: CapabilitySet,
 
     /// When this peer was last seen/verified
     pub last_seen: u64,
@@ -201,17 +959,78 @@ impl GossipNodeInfo {
         Self {
             peer_pubkey: response.peer_pubkey,
             gossip_addr: response.gossip_addr,
-            protocol_version: response.protocol_version,
+            protocol_version: response.supported_versions.max.to_string(),
+            negotiated_version: Some(response.protocol_version),
             network_id: response.network_id,
             capabilities: response.capabilities,
             last_seen: response.timestamp,
             connection_status: ConnectionStatus::Connected,
         }
     }
+
+    /// Localnet peer at `gossip_addr` with a fresh pubkey, for tests
+    #[cfg(test)]
+    pub(crate) fn test_peer(gossip_addr: SocketAddr, connection_status: ConnectionStatus) -> Self {
+        Self {
+            peer_pubkey: Pubkey::new_unique(),
+            gossip_addr,
+            protocol_version: "1.18.0".to_string(),
+            negotiated_version: None,
+            network_id: network::LOCALNET.to_string(),
+            capabilities: DEFAULT_CAPABILITIES,
+            last_seen: 0,
+            connection_status,
+        }
+    }
+}
+
+/// Another node a peer told us about during a handshake
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AdvertisedPeer {
+    /// Public key the node's contact info was signed with
+    pub pubkey: Pubkey,
+
+    /// Gossip address the node advertises
+    pub gossip_addr: SocketAddr,
+
+    /// Shred version the node advertises
+    pub shred_version: u16,
+
+    /// Software version the node advertises, if its contact info carries one
+    pub version: Option<String>,
+}
+
+/// Everything learned from a peer during a discovery handshake
+#[derive(Debug, Clone)]
+pub struct PeerDetails {
+    /// The peer itself, as it would be registered
+    pub peer_info: GossipNodeInfo,
+
+    /// Shred version from the peer's own contact info, if it sent any
+    pub shred_version: Option<u16>,
+
+    /// Time between sending our challenge or request and the peer answering it
+    pub rtt: std::time::Duration,
+
+    /// Other nodes the peer told us about
+    pub advertised: Vec<AdvertisedPeer>,
+}
+
+/// Result of a handshake with one peer, retries included
+#[derive(Debug)]
+pub struct HandshakeOutcome {
+    /// Address the handshake was sent to
+    pub peer_addr: SocketAddr,
+
+    /// Attempts made, 1 when the first attempt succeeded
+    pub attempts: u8,
+
+    /// What the handshake learned, or why its last attempt failed
+    pub result: Result<PeerDetails, SolanaNodeError>,
 }
 
 /// Status of connection with a peer
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ConnectionStatus {
     /// Successfully connected and verified
     Connected,
@@ -241,10 +1060,10 @@ mod tests {
         let request = HandshakeRequest::new_local(pubkey);
 
         assert_eq!(request.node_pubkey, pubkey);
-        assert_eq!(request.protocol_version, "1.18.0");
+        assert_eq!(request.supported_versions, VersionRange::default());
         assert_eq!(request.network_id, "localnet");
-        assert!(request.capabilities.contains(&"gossip".to_string()));
-        assert!(request.capabilities.contains(&"handshake".to_string()));
+        assert!(request.capabilities.contains(Capability::Gossip));
+        assert!(request.capabilities.contains(Capability::Handshake));
 
         // Verify a timestamp is recent (within last minute)
         let current_time = SystemTime::now()
@@ -252,6 +1071,12 @@ mod tests {
             .unwrap()
             .as_secs();
         assert!(current_time.saturating_sub(request.timestamp) < 60);
+
+        // Every request gets its own nonce and correlation id
+        let other = HandshakeRequest::new_local(pubkey);
+        assert_ne!(request.nonce, other.nonce);
+        assert!(!request.correlation_id.is_empty());
+        assert_ne!(request.correlation_id, other.correlation_id);
     }
 
     #[test]
@@ -261,7 +1086,7 @@ mod tests {
 
         assert_eq!(request.node_pubkey, pubkey);
         assert_eq!(request.network_id, "testnet");
-        assert_eq!(request.protocol_version, "1.18.0");
+        assert_eq!(request.supported_versions, VersionRange::default());
     }
 
     #[test]
@@ -271,7 +1096,7 @@ mod tests {
 
         assert_eq!(request.node_pubkey, pubkey);
         assert_eq!(request.network_id, "mainnet-beta");
-        assert_eq!(request.protocol_version, "1.18.0");
+        assert_eq!(request.supported_versions, VersionRange::default());
     }
 
     #[test]
@@ -284,10 +1109,11 @@ mod tests {
         let deserialized: HandshakeRequest = HandshakeRequest::try_from_slice(&serialized).unwrap();
 
         assert_eq!(request.node_pubkey, deserialized.node_pubkey);
-        assert_eq!(request.protocol_version, deserialized.protocol_version);
+        assert_eq!(request.supported_versions, deserialized.supported_versions);
         assert_eq!(request.network_id, deserialized.network_id);
         assert_eq!(request.capabilities, deserialized.capabilities);
         assert_eq!(request.timestamp, deserialized.timestamp);
+        assert_eq!(request.nonce, deserialized.nonce);
 
         // Test Serde serialization
         let json = serde_json::to_string(&request).unwrap();
@@ -299,66 +1125,101 @@ mod tests {
     fn test_handshake_response_verify_success() {
         let response = HandshakeResponse {
             peer_pubkey: create_test_pubkey(),
-            protocol_version: "1.18.0".to_string(),
+            protocol_version: DEFAULT_PROTOCOL_VERSION,
+            supported_versions: VersionRange::default(),
             network_id: "localnet".to_string(),
             gossip_addr: "127.0.0.1:8001".parse().unwrap(),
-            capabilities: vec!["gossip".to_string()],
+            capabilities: Capability::Gossip.into(),
             timestamp: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
             success: true,
             error_message: None,
+            reject_reason: None,
+            nonce: 0,
+            signature: MessageSignature::default(),
         };
 
-        assert!(response.verify("localnet").is_ok());
+        assert!(response
+            .verify("localnet", &VersionRange::default())
+            .is_ok());
     }
 
     #[test]
     fn test_handshake_response_verify_failure() {
         let response = HandshakeResponse {
             peer_pubkey: create_test_pubkey(),
-            protocol_version: "1.18.0".to_string(),
+            protocol_version: DEFAULT_PROTOCOL_VERSION,
+            supported_versions: VersionRange::default(),
             network_id: "localnet".to_string(),
             gossip_addr: "127.0.0.1:8001".parse().unwrap(),
-            capabilities: vec!["gossip".to_string()],
+            capabilities: Capability::Gossip.into(),
             timestamp: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
             success: false,
             error_message: Some("Test error".to_string()),
+            reject_reason: Some(RejectReason::AccessDenied),
+            nonce: 0,
+            signature: MessageSignature::default(),
         };
 
-        let result = response.verify("localnet");
+        let result = response.verify("localnet", &VersionRange::default());
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(SolanaNodeError::HandshakeError { .. })
+            Err(SolanaNodeError::RemoteRejected {
+                reason: RejectReason::AccessDenied,
+                ..
+            })
         ));
+        assert!(!result.unwrap_err().is_retryable());
+
+        // Responses from nodes that give no reason are rejected as unspecified
+        let response = HandshakeResponse {
+            reject_reason: None,
+            ..response
+        };
+        let error = response
+            .verify("localnet", &VersionRange::default())
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            SolanaNodeError::RemoteRejected {
+                reason: RejectReason::Unspecified,
+                ..
+            }
+        ));
+        assert!(error.is_retryable());
     }
 
     #[test]
     fn test_handshake_response_verify_network_mismatch() {
         let response = HandshakeResponse {
             peer_pubkey: create_test_pubkey(),
-            protocol_version: "1.18.0".to_string(),
+            protocol_version: DEFAULT_PROTOCOL_VERSION,
+            supported_versions: VersionRange::default(),
             network_id: "testnet".to_string(),
             gossip_addr: "127.0.0.1:8001".parse().unwrap(),
-            capabilities: vec!["gossip".to_string()],
+            capabilities: Capability::Gossip.into(),
             timestamp: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
             success: true,
             error_message: None,
+            reject_reason: None,
+            nonce: 0,
+            signature: MessageSignature::default(),
         };
 
-        let result = response.verify("localnet");
+        let result = response.verify("localnet", &VersionRange::default());
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(SolanaNodeError::ProtocolVersionMismatch { .. })
+            Err(SolanaNodeError::NetworkMismatch { .. })
         ));
     }
 
@@ -372,20 +1233,27 @@ mod tests {
 
         let response = HandshakeResponse {
             peer_pubkey: create_test_pubkey(),
-            protocol_version: "1.18.0".to_string(),
+            protocol_version: DEFAULT_PROTOCOL_VERSION,
+            supported_versions: VersionRange::default(),
             network_id: "localnet".to_string(),
             gossip_addr: "127.0.0.1:8001".parse().unwrap(),
-            capabilities: vec!["gossip".to_string()],
+            capabilities: Capability::Gossip.into(),
             timestamp: old_timestamp,
             success: true,
             error_message: None,
+            reject_reason: None,
+            nonce: 0,
+            signature: MessageSignature::default(),
         };
 
-        let result = response.verify("localnet");
+        let result = response.verify("localnet", &VersionRange::default());
         assert!(result.is_err());
         assert!(matches!(
             result,
-            Err(SolanaNodeError::InvalidPeerData { .. })
+            Err(SolanaNodeError::StaleTimestamp {
+                age_secs: 400..,
+                max_age_secs: MAX_RESPONSE_AGE_SECS,
+            })
         ));
     }
 
@@ -393,23 +1261,31 @@ mod tests {
     fn test_gossip_node_info_from_handshake_response() {
         let response = HandshakeResponse {
             peer_pubkey: create_test_pubkey(),
-            protocol_version: "1.18.0".to_string(),
+            protocol_version: DEFAULT_PROTOCOL_VERSION,
+            supported_versions: VersionRange::default(),
             network_id: "localnet".to_string(),
             gossip_addr: "127.0.0.1:8001".parse().unwrap(),
-            capabilities: vec!["gossip".to_string(), "handshake".to_string()],
+            capabilities: DEFAULT_CAPABILITIES,
             timestamp: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
             success: true,
             error_message: None,
+            reject_reason: None,
+            nonce: 0,
+            signature: MessageSignature::default(),
         };
 
         let peer_info = GossipNodeInfo::from_handshake_response(response.clone());
 
         assert_eq!(peer_info.peer_pubkey, response.peer_pubkey);
         assert_eq!(peer_info.gossip_addr, response.gossip_addr);
-        assert_eq!(peer_info.protocol_version, response.protocol_version);
+        assert_eq!(peer_info.protocol_version, "1.18.0");
+        assert_eq!(
+            peer_info.negotiated_version,
+            Some(response.protocol_version)
+        );
         assert_eq!(peer_info.network_id, response.network_id);
         assert_eq!(peer_info.capabilities, response.capabilities);
         assert_eq!(peer_info.last_seen, response.timestamp);
@@ -440,6 +1316,304 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_handshake_request_signature() {
+        let keypair = Keypair::new();
+        let mut request = HandshakeRequest::new_local(keypair.pubkey());
+        assert!(!request.verify_signature());
+
+        request.sign(&keypair);
+        assert!(request.verify_signature());
+
+        // Signature survives a Borsh roundtrip
+        let serialized = borsh::to_vec(&request).unwrap();
+        let deserialized = HandshakeRequest::try_from_slice(&serialized).unwrap();
+        assert!(deserialized.verify_signature());
+
+        // Any change to the signed body invalidates the signature
+        let mut tampered = request.clone();
+        tampered.network_id = network::TESTNET.to_string();
+        assert!(!tampered.verify_signature());
+
+        // Claiming another identity invalidates the signature
+        let mut spoofed = request;
+        spoofed.node_pubkey = Pubkey::new_unique();
+        assert!(!spoofed.verify_signature());
+    }
+
+    #[test]
+    fn test_handshake_response_signature() {
+        let keypair = Keypair::new();
+        let mut response = HandshakeResponse {
+            peer_pubkey: keypair.pubkey(),
+            protocol_version: DEFAULT_PROTOCOL_VERSION,
+            supported_versions: VersionRange::default(),
+            network_id: "localnet".to_string(),
+            gossip_addr: "127.0.0.1:8001".parse().unwrap(),
+            capabilities: Capability::Gossip.into(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            success: true,
+            error_message: None,
+            reject_reason: None,
+            nonce: 0,
+            signature: MessageSignature::default(),
+        };
+        response.sign(&keypair);
+        assert!(response.verify_signature());
+
+        // Signed by someone other than the claimed peer
+        response.sign(&Keypair::new());
+        assert!(!response.verify_signature());
+
+        // JSON keeps the signature intact
+        response.sign(&keypair);
+        let json = serde_json::to_string(&response).unwrap();
+        let from_json: HandshakeResponse = serde_json::from_str(&json).unwrap();
+        assert!(from_json.verify_signature());
+    }
+
+    #[test]
+    fn test_protocol_version_parsing_and_ordering() {
+        let version: ProtocolVersion = "1.18.2".parse().unwrap();
+        assert_eq!(version, ProtocolVersion::new(1, 18, 2));
+        assert_eq!(version.to_string(), "1.18.2");
+
+        assert!("1.18".parse::<ProtocolVersion>().is_err());
+        assert!("1.x.0".parse::<ProtocolVersion>().is_err());
+
+        assert!(ProtocolVersion::new(1, 18, 0) > ProtocolVersion::new(1, 17, 9));
+        assert!(ProtocolVersion::new(2, 0, 0) > ProtocolVersion::new(1, 99, 99));
+
+        // JSON uses the dotted string form
+        let json = serde_json::to_string(&version).unwrap();
+        assert_eq!(json, "\"1.18.2\"");
+        assert_eq!(
+            serde_json::from_str::<ProtocolVersion>(&json).unwrap(),
+            version
+        );
+    }
+
+    #[test]
+    fn test_version_negotiation() {
+        let v = ProtocolVersion::new;
+        let ours = VersionRange::new(v(1, 17, 0), v(1, 18, 0));
+
+        // Picks the highest version both sides support
+        let newer_peer = VersionRange::new(v(1, 16, 0), v(1, 19, 0));
+        assert_eq!(ours.negotiate(&newer_peer), Some(v(1, 18, 0)));
+        let older_peer = VersionRange::new(v(1, 15, 0), v(1, 17, 3));
+        assert_eq!(ours.negotiate(&older_peer), Some(v(1, 17, 3)));
+        assert_eq!(older_peer.negotiate(&ours), Some(v(1, 17, 3)));
+
+        // Disjoint ranges have no common version
+        let ancient_peer = VersionRange::new(v(1, 14, 0), v(1, 16, 9));
+        assert_eq!(ours.negotiate(&ancient_peer), None);
+
+        assert!(ours.contains(v(1, 17, 5)));
+        assert!(!ours.contains(v(1, 18, 1)));
+    }
+
+    #[test]
+    fn test_handshake_response_verify_version_mismatch() {
+        let v = ProtocolVersion::new;
+        let mut response = HandshakeResponse {
+            peer_pubkey: create_test_pubkey(),
+            protocol_version: v(2, 0, 0),
+            supported_versions: VersionRange::new(v(2, 0, 0), v(2, 1, 0)),
+            network_id: "localnet".to_string(),
+            gossip_addr: "127.0.0.1:8001".parse().unwrap(),
+            capabilities: Capability::Gossip.into(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            success: false,
+            error_message: Some("Unsupported protocol versions".to_string()),
+            reject_reason: None,
+            nonce: 0,
+            signature: MessageSignature::default(),
+        };
+
+        assert!(matches!(
+            response.verify("localnet", &VersionRange::default()),
+            Err(SolanaNodeError::ProtocolVersionMismatch { .. })
+        ));
+
+        // A peer must pick the highest common version
+        response.success = true;
+        response.error_message = None;
+        response.supported_versions = VersionRange::new(v(1, 16, 0), v(1, 19, 0));
+        response.protocol_version = v(1, 17, 0);
+        assert!(matches!(
+            response.verify("localnet", &VersionRange::default()),
+            Err(SolanaNodeError::ProtocolVersionMismatch { .. })
+        ));
+
+        response.protocol_version = v(1, 18, 0);
+        assert!(response
+            .verify("localnet", &VersionRange::default())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_capability_set_operations() {
+        let offered = DEFAULT_CAPABILITIES;
+        let supported = CapabilitySet::from(Capability::Gossip);
+
+        let agreed = offered.intersection(supported);
+        assert!(agreed.contains(Capability::Gossip));
+        assert!(!agreed.contains(Capability::Handshake));
+        assert_eq!(
+            offered.difference(agreed),
+            CapabilitySet::from(Capability::Handshake)
+        );
+        assert!(offered.contains_all(agreed));
+        assert!(!agreed.contains_all(offered));
+
+        assert_eq!(offered.to_string(), "gossip,handshake");
+        assert_eq!(CapabilitySet::EMPTY.to_string(), "none");
+        assert_eq!(
+            "handshake, gossip".parse::<CapabilitySet>().unwrap(),
+            offered
+        );
+        assert!("teleport".parse::<CapabilitySet>().is_err());
+    }
+
+    #[test]
+    fn test_capability_set_keeps_unknown_bits() {
+        // A newer peer offering a capability we do not know about yet
+        let from_newer_peer = CapabilitySet::from_bits(DEFAULT_CAPABILITIES.bits() | 1 << 40);
+        assert_eq!(from_newer_peer.unknown_bits(), 1 << 40);
+        assert_eq!(from_newer_peer.iter().count(), 2);
+
+        // Unknown bits survive Borsh and JSON roundtrips
+        let borsh_bytes = borsh::to_vec(&from_newer_peer).unwrap();
+        assert_eq!(
+            CapabilitySet::try_from_slice(&borsh_bytes).unwrap(),
+            from_newer_peer
+        );
+
+        let json = serde_json::to_string(&from_newer_peer).unwrap();
+        assert_eq!(json, r#"["gossip","handshake","unknown-40"]"#);
+        assert_eq!(
+            serde_json::from_str::<CapabilitySet>(&json).unwrap(),
+            from_newer_peer
+        );
+
+        // But are never agreed by a node that does not support them
+        assert_eq!(
+            from_newer_peer.intersection(DEFAULT_CAPABILITIES),
+            DEFAULT_CAPABILITIES
+        );
+    }
+
+    #[test]
+    fn test_handshake_mode_parsing() {
+        assert_eq!(
+            "legacy".parse::<HandshakeMode>().unwrap(),
+            HandshakeMode::Legacy
+        );
+        assert_eq!(
+            "GOSSIP".parse::<HandshakeMode>().unwrap(),
+            HandshakeMode::Gossip
+        );
+        assert!("invalid".parse::<HandshakeMode>().is_err());
+        assert_eq!(HandshakeMode::default(), HandshakeMode::Legacy);
+        assert_eq!(format!("{}", HandshakeMode::Gossip), "gossip");
+    }
+
+    #[test]
+    fn test_error_kinds_and_retryability() {
+        let peer_addr: SocketAddr = "127.0.0.1:8001".parse().unwrap();
+
+        let send_timeout = SolanaNodeError::SendTimeout { peer_addr };
+        let receive_timeout = SolanaNodeError::ReceiveTimeout { peer_addr };
+        assert_eq!(send_timeout.kind(), ErrorKind::SendTimeout);
+        assert_eq!(receive_timeout.kind(), ErrorKind::ReceiveTimeout);
+        assert!(send_timeout.is_retryable());
+        assert!(receive_timeout.is_retryable());
+
+        let signature_invalid = SolanaNodeError::SignatureInvalid { peer_addr };
+        assert_eq!(signature_invalid.kind().as_str(), "signature_invalid");
+        assert!(!signature_invalid.is_retryable());
+
+        let mismatch = SolanaNodeError::NetworkMismatch {
+            expected: "localnet".to_string(),
+            received: "testnet".to_string(),
+        };
+        assert!(!mismatch.is_retryable());
+
+        let replayed = SolanaNodeError::RemoteRejected {
+            reason: RejectReason::ReplayedRequest,
+            message: "duplicate nonce".to_string(),
+        };
+        assert_eq!(replayed.kind(), ErrorKind::RemoteRejected);
+        assert!(replayed.is_retryable());
+
+        // Codec errors with an offset come from decoding
+        let truncated = SolanaNodeError::from(CodecError::UnexpectedEof {
+            offset: 4,
+            needed: 8,
+        });
+        assert_eq!(truncated.kind(), ErrorKind::Decode);
+        let oversized = SolanaNodeError::from(CodecError::PacketTooLarge {
+            size: 2000,
+            max: 1232,
+        });
+        assert_eq!(oversized.kind(), ErrorKind::Serialization);
+    }
+
+    #[test]
+    fn test_error_kind_parsing() {
+        for kind in ErrorKind::ALL {
+            assert_eq!(kind.as_str().parse::<ErrorKind>(), Ok(kind));
+        }
+        assert_eq!(
+            "Receive-Timeout".parse::<ErrorKind>(),
+            Ok(ErrorKind::ReceiveTimeout)
+        );
+        assert!("timeout".parse::<ErrorKind>().is_err());
+    }
+
+    #[test]
+    fn test_handshake_response_decode_reports_offset() {
+        let response = HandshakeResponse {
+            peer_pubkey: create_test_pubkey(),
+            protocol_version: DEFAULT_PROTOCOL_VERSION,
+            supported_versions: VersionRange::default(),
+            network_id: "localnet".to_string(),
+            gossip_addr: "127.0.0.1:8001".parse().unwrap(),
+            capabilities: DEFAULT_CAPABILITIES,
+            timestamp: 0,
+            success: true,
+            error_message: None,
+            reject_reason: None,
+            nonce: 7,
+            signature: MessageSignature::default(),
+        };
+        let bytes = borsh::to_vec(&response).unwrap();
+
+        let decoded = HandshakeResponse::decode(&bytes).unwrap();
+        assert_eq!(decoded.nonce, 7);
+
+        // The pubkey decodes, the version that follows it is cut short
+        let error = HandshakeResponse::decode(&bytes[..34]).unwrap_err();
+        assert!(matches!(
+            error,
+            SolanaNodeError::DecodeError { offset, .. } if offset > 32
+        ));
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(
+            HandshakeResponse::decode(&trailing),
+            Err(SolanaNodeError::DecodeError { offset, .. }) if offset == bytes.len()
+        ));
+    }
+
     #[test]
     fn test_error_display_formatting() {
         let network_error = SolanaNodeError::NetworkError(std::io::Error::new(
@@ -449,10 +1623,10 @@ mod tests {
         let error_str = format!("{}", network_error);
         assert!(error_str.contains("Network connection failed"));
 
-        let handshake_error = SolanaNodeError::HandshakeError {
-            message: "Invalid signature".to_string(),
+        let signature_error = SolanaNodeError::SignatureInvalid {
+            peer_addr: "127.0.0.1:8001".parse().unwrap(),
         };
-        let error_str = format!("{}", handshake_error);
+        let error_str = format!("{}", signature_error);
         assert!(error_str.contains("Invalid signature"));
 
         let protocol_mismatch = SolanaNodeError::ProtocolVersionMismatch {
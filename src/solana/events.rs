@@ -0,0 +1,73 @@
+//! Peer lifecycle events published by `GossipNode`
+//!
+//! Subscribers get a `tokio::sync::broadcast` receiver, so any number of
+//! services can react to peer changes without polling the registry. A
+//! subscriber that falls behind by more than the channel capacity misses the
+//! oldest events and is told how many with `RecvError::Lagged`.
+
+use crate::solana::domain::{ConnectionStatus, GossipNodeInfo};
+use solana_sdk::pubkey::Pubkey;
+use std::net::SocketAddr;
+
+/// Number of events buffered for each subscriber
+pub const DEFAULT_EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Something that happened to a peer of a `GossipNode`
+#[derive(Debug, Clone)]
+pub enum PeerEvent {
+    /// A correctly signed handshake request arrived
+    HandshakeReceived {
+        peer_addr: SocketAddr,
+        peer_pubkey: Pubkey,
+    },
+
+    /// A peer was added to the registry
+    PeerRegistered { peer_info: GossipNodeInfo },
+
+    /// A registered peer was re-registered or changed connection status
+    PeerUpdated {
+        peer_info: GossipNodeInfo,
+        previous_status: ConnectionStatus,
+    },
+
+    /// A packet from `peer_addr` failed signature or ping verification
+    PeerVerificationFailed {
+        peer_addr: SocketAddr,
+        reason: String,
+    },
+
+    /// A peer stopped answering probes and was removed from the registry
+    PeerExpired { peer_info: GossipNodeInfo },
+
+    /// A packet that is not part of any protocol this node speaks
+    UnknownPacket {
+        sender_addr: SocketAddr,
+        packet_size: usize,
+    },
+}
+
+impl PeerEvent {
+    /// Short name of the event, for logging
+    pub fn name(&self) -> &'static str {
+        match self {
+            PeerEvent::HandshakeReceived { .. } => "HandshakeReceived",
+            PeerEvent::PeerRegistered { .. } => "PeerRegistered",
+            PeerEvent::PeerUpdated { .. } => "PeerUpdated",
+            PeerEvent::PeerVerificationFailed { .. } => "PeerVerificationFailed",
+            PeerEvent::PeerExpired { .. } => "PeerExpired",
+            PeerEvent::UnknownPacket { .. } => "UnknownPacket",
+        }
+    }
+
+    /// Address of the peer the event is about
+    pub fn peer_addr(&self) -> SocketAddr {
+        match self {
+            PeerEvent::HandshakeReceived { peer_addr, .. }
+            | PeerEvent::PeerVerificationFailed { peer_addr, .. } => *peer_addr,
+            PeerEvent::PeerRegistered { peer_info }
+            | PeerEvent::PeerUpdated { peer_info, .. }
+            | PeerEvent::PeerExpired { peer_info } => peer_info.gossip_addr,
+            PeerEvent::UnknownPacket { sender_addr, .. } => *sender_addr,
+        }
+    }
+}
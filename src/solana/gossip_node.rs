@@ -3,73 +3,582 @@
 //! This module provides the core gossip node functionality for participating
 //! in the Solana network's peer-to-peer communication layer.
 
+use crate::metrics::Metrics;
+use crate::solana::access_policy::AccessControl;
+use crate::solana::codec::{CodecError, Pong, Protocol};
+use crate::solana::domain::network;
 use crate::solana::domain::{
-    ConnectionStatus, GossipNodeInfo, HandshakeRequest, HandshakeResponse, SolanaNodeError,
+    CapabilitySet, ConnectionStatus, GossipNodeInfo, HandshakeRequest, HandshakeResponse,
+    MessageSignature, RejectReason, SolanaNodeError, VersionRange, DEFAULT_CAPABILITIES,
+    DEFAULT_MAX_PACKET_SIZE,
 };
-use borsh::BorshDeserialize;
+use crate::solana::events::{PeerEvent, DEFAULT_EVENT_CHANNEL_CAPACITY};
+use crate::solana::liveness::{LivenessConfig, LivenessTracker};
+use crate::solana::peer_registry::PeerRegistry;
+use crate::solana::ping_cache::{PingCache, DEFAULT_PING_CACHE_CAPACITY};
+use crate::solana::rate_limit::{RateLimitConfig, RateLimiter, RejectPolicy};
+use crate::solana::replay_cache::{
+    ReplayCache, ReplayRejection, DEFAULT_REPLAY_CACHE_CAPACITY, DEFAULT_REPLAY_WINDOW,
+};
+use crate::solana::stats::NodeStats;
+use futures::future::{Fuse, FusedFuture, FutureExt};
+use futures::stream::{FuturesUnordered, StreamExt};
 use solana_sdk::pubkey::Pubkey;
-use std::collections::HashMap;
+use solana_sdk::signature::{Keypair, Signer};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
 use tokio::net::UdpSocket;
+use tokio::sync::broadcast;
 use tokio::time::{timeout, Duration};
-use tracing::{debug, error, info, warn};
-
-/// Maximum UDP packet size for Solana gossip protocol (1280 bytes)
-const MAX_GOSSIP_PACKET_SIZE: usize = 1280;
+use tracing::{debug, error, info, trace, warn};
 
 /// Default timeout for network operations (30 seconds)
-const DEFAULT_NETWORK_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_NETWORK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Address a node binds to unless given another one or a bound socket
+const DEFAULT_BIND_ADDR: &str = "0.0.0.0:0";
+
+/// Default number of packets handled concurrently
+pub const DEFAULT_WORKERS: usize = 64;
+
+/// Default number of received packets waiting for a free worker
+pub const DEFAULT_QUEUE_DEPTH: usize = 1024;
+
+/// Default interval between snapshots of the peer registry to the peer store
+pub const DEFAULT_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Maximum number of already-queued packets handled after shutdown is requested
+const MAX_DRAIN_PACKETS: usize = 1024;
+
+/// Current time in seconds since the Unix epoch
+fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// A handshake that is waiting for the peer to answer our ping challenge
+#[derive(Debug)]
+struct PendingPeer {
+    /// Peer information to register once the peer is verified
+    peer_info: GossipNodeInfo,
+
+    /// Response to send back once the peer is verified
+    response: Option<HandshakeResponse>,
+    /// When the handshake started waiting
+    queued_at: Instant,
+}
+
+/// Handshakes waiting for a ping answer, evicted oldest first
+///
+/// `order` lists queued addresses by age. Entries of peers that were removed
+/// since are skipped when they reach the front, so every operation is O(1)
+/// amortized.
+#[derive(Debug, Default)]
+struct PendingPeers {
+    peers: HashMap<SocketAddr, PendingPeer>,
+    order: VecDeque<(SocketAddr, Instant)>,
+}
+
+impl PendingPeers {
+    fn len(&self) -> usize {
+        self.peers.len()
+    }
+
+    fn get(&self, addr: &SocketAddr) -> Option<&PendingPeer> {
+        self.peers.get(addr)
+    }
+
+    fn remove(&mut self, addr: &SocketAddr) -> Option<PendingPeer> {
+        self.peers.remove(addr)
+    }
+
+    fn clear(&mut self) {
+        self.peers.clear();
+        self.order.clear();
+    }
+
+    /// Queue a handshake from `addr`, evicting the oldest one once `capacity` are waiting
+    ///
+    /// A peer that asks again keeps its place in the queue. Returns the address
+    /// of the evicted handshake, if any.
+    fn insert(
+        &mut self,
+        addr: SocketAddr,
+        mut peer: PendingPeer,
+        capacity: usize,
+    ) -> Option<SocketAddr> {
+        if let Some(queued) = self.peers.get_mut(&addr) {
+            peer.queued_at = queued.queued_at;
+            *queued = peer;
+            return None;
+        }
+
+        let evicted = if self.peers.len() >= capacity.max(1) {
+            self.pop_oldest()
+        } else {
+            None
+        };
+
+        // Keep skipped entries from piling up when peers keep coming and going
+        if self.order.len() >= 2 * capacity.max(1) {
+            let peers = &self.peers;
+            self.order
+                .retain(|(addr, queued_at)| Self::is_live(peers, addr, *queued_at));
+        }
+
+        self.order.push_back((addr, peer.queued_at));
+        self.peers.insert(addr, peer);
+        evicted
+    }
+
+    /// Forget handshakes that have waited `ttl` or longer, returning how many
+    fn prune(&mut self, now: Instant, ttl: Duration) -> usize {
+        let before = self.peers.len();
+        while let Some((addr, queued_at)) = self.order.front().copied() {
+            if Self::is_live(&self.peers, &addr, queued_at)
+                && now.saturating_duration_since(queued_at) < ttl
+            {
+                break;
+            }
+            self.order.pop_front();
+            if Self::is_live(&self.peers, &addr, queued_at) {
+                self.peers.remove(&addr);
+            }
+        }
+        before - self.peers.len()
+    }
+
+    fn pop_oldest(&mut self) -> Option<SocketAddr> {
+        while let Some((addr, queued_at)) = self.order.pop_front() {
+            if Self::is_live(&self.peers, &addr, queued_at) {
+                self.peers.remove(&addr);
+                return Some(addr);
+            }
+        }
+        None
+    }
+
+    /// Whether an `order` entry still refers to a queued handshake
+    fn is_live(
+        peers: &HashMap<SocketAddr, PendingPeer>,
+        addr: &SocketAddr,
+        queued_at: Instant,
+    ) -> bool {
+        peers
+            .get(addr)
+            .is_some_and(|peer| peer.queued_at == queued_at)
+    }
+}
+
+/// Handshake and liveness bookkeeping shared by concurrently handled packets
+///
+/// The lock is never held across an `.await`. Each cache is updated in a
+/// single call, so a panic elsewhere cannot leave one half-updated and the
+/// lock is used even when poisoned.
+#[derive(Debug, Default)]
+struct NodeState {
+    /// Ping challenges issued and peers that have answered them
+    ping_cache: PingCache,
+
+    /// Handshakes held back until the sender answers our ping
+    pending_peers: PendingPeers,
+
+    /// Recently accepted handshake requests, used to reject replays
+    replay_cache: ReplayCache,
+
+    /// Outstanding liveness probes to registered peers
+    liveness: LivenessTracker,
+
+    /// Token buckets limiting traffic per source and overall
+    rate_limiter: RateLimiter,
+}
+
+/// What a gossip node did before its event loop stopped
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    /// How long the event loop ran
+    pub uptime: Duration,
+
+    /// Packets received, including the ones drained during shutdown
+    pub packets_received: u64,
+
+    /// Packets whose handling failed
+    pub packets_failed: u64,
+
+    /// Packets dropped by a rate limit
+    pub packets_rate_limited: u64,
+
+    /// Packets that were already queued when shutdown was requested
+    pub packets_drained: usize,
+
+    /// Handshakes dropped because the peer had not answered our ping yet
+    pub abandoned_handshakes: usize,
+
+    /// Final state of the peer registry
+    pub peers: Vec<GossipNodeInfo>,
+}
 
 /// Core gossip node for Solana P2P networking
 pub struct GossipNode {
     /// UDP socket for network communication
     socket: UdpSocket,
 
-    /// This node's public key identifier
-    node_pubkey: Pubkey,
+    /// This node's identity keypair, used to sign ping challenges and pongs
+    identity: Keypair,
 
     /// Network ID this node operates on
     network_id: String,
 
+    /// Timeout for sending a packet
+    network_timeout: Duration,
+
+    /// Largest packet received in full, and sent
+    max_packet_size: usize,
+
     /// Registry of known peers and their connection status
-    peer_registry: HashMap<SocketAddr, GossipNodeInfo>,
-    // Note: For future extension, message routing channels can be added here
-    // Currently using direct synchronous handling for simplicity
+    peer_registry: PeerRegistry,
+
+    /// Ping, replay and liveness bookkeeping
+    state: Mutex<NodeState>,
+
+    /// Handshake protocol versions this node accepts
+    supported_versions: VersionRange,
+
+    /// Capabilities this node can agree to
+    supported_capabilities: CapabilitySet,
+
+    /// Capabilities a peer must offer for its handshake to be accepted
+    required_capabilities: CapabilitySet,
+
+    /// Whether rejected requests from unverified senders are answered
+    reject_policy: RejectPolicy,
+
+    /// Pubkeys and address ranges allowed to register
+    access: AccessControl,
+
+    /// Traffic counters
+    stats: NodeStats,
+
+    /// Packet size histogram and exported views of the stats and registry
+    metrics: Metrics,
+
+    /// File the peer registry is saved to and restored from
+    peer_store: Option<PathBuf>,
+
+    /// How often the peer registry is saved to the peer store
+    snapshot_interval: Duration,
+
+    /// Maximum number of packets handled concurrently
+    workers: usize,
+
+    /// Maximum number of received packets waiting for a free worker
+    queue_depth: usize,
+
+    /// Publisher of peer lifecycle events to subscribers
+    events: broadcast::Sender<PeerEvent>,
 }
 
-impl GossipNode {
-    /// Create a new gossip node instance
-    #[tracing::instrument(skip(bind_addr))]
-    pub async fn new(
-        bind_addr: &str,
-        node_pubkey: Pubkey,
-        network_id: String,
-    ) -> Result<Self, SolanaNodeError> {
-        info!(
-            bind_addr = %bind_addr,
-            node_pubkey = %node_pubkey,
-            network_id = %network_id,
-            "Initializing gossip node"
-        );
+/// Where a gossip node gets its socket from
+enum SocketSource {
+    /// Bind a new socket to this address
+    Bind(String),
+    /// Use a socket bound by the caller
+    Bound(UdpSocket),
+}
 
-        let socket = UdpSocket::bind(bind_addr).await.map_err(|e| {
-            error!(error = %e, bind_addr = %bind_addr, "Failed to bind UDP socket");
-            SolanaNodeError::NetworkError(e)
-        })?;
+/// Builder for a `GossipNode` with every setting in one place
+///
+/// Settings left unset keep the defaults of `GossipNode::new`. Without an
+/// identity the node uses a new ephemeral keypair. Traffic counters, packet
+/// sizes and peers are exported through `metrics`; spans go to the installed
+/// `tracing` subscriber, such as the one set up by `setup_telemetry`.
+pub struct GossipNodeBuilder {
+    socket: SocketSource,
+    identity: Option<Keypair>,
+    network_id: String,
+    network_timeout: Duration,
+    max_packet_size: usize,
+    supported_versions: VersionRange,
+    supported_capabilities: CapabilitySet,
+    required_capabilities: CapabilitySet,
+    replay_window: Duration,
+    liveness: LivenessConfig,
+    rate_limits: RateLimitConfig,
+    reject_policy: RejectPolicy,
+    access: AccessControl,
+    peer_store: Option<PathBuf>,
+    snapshot_interval: Duration,
+    workers: usize,
+    queue_depth: usize,
+    metrics: Option<Metrics>,
+}
 
+impl Default for GossipNodeBuilder {
+    fn default() -> Self {
+        Self {
+            socket: SocketSource::Bind(DEFAULT_BIND_ADDR.to_string()),
+            identity: None,
+            network_id: network::LOCALNET.to_string(),
+            network_timeout: DEFAULT_NETWORK_TIMEOUT,
+            max_packet_size: DEFAULT_MAX_PACKET_SIZE,
+            supported_versions: VersionRange::default(),
+            supported_capabilities: DEFAULT_CAPABILITIES,
+            required_capabilities: CapabilitySet::EMPTY,
+            replay_window: DEFAULT_REPLAY_WINDOW,
+            liveness: LivenessConfig::default(),
+            rate_limits: RateLimitConfig::default(),
+            reject_policy: RejectPolicy::default(),
+            access: AccessControl::default(),
+            peer_store: None,
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            workers: DEFAULT_WORKERS,
+            queue_depth: DEFAULT_QUEUE_DEPTH,
+            metrics: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for GossipNodeBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let socket = match &self.socket {
+            SocketSource::Bind(bind_addr) => bind_addr.clone(),
+            SocketSource::Bound(socket) => format!("{:?}", socket.local_addr()),
+        };
+        f.debug_struct("GossipNodeBuilder")
+            .field("socket", &socket)
+            .field(
+                "node_pubkey",
+                &self.identity.as_ref().map(|identity| identity.pubkey()),
+            )
+            .field("network_id", &self.network_id)
+            .field("network_timeout", &self.network_timeout)
+            .field("max_packet_size", &self.max_packet_size)
+            .field("rate_limits", &self.rate_limits)
+            .field("reject_policy", &self.reject_policy)
+            .field("peer_store", &self.peer_store)
+            .field("workers", &self.workers)
+            .field("queue_depth", &self.queue_depth)
+            .finish_non_exhaustive()
+    }
+}
+
+impl GossipNodeBuilder {
+    /// Bind the node's socket to `bind_addr` (default: "0.0.0.0:0")
+    pub fn bind_addr(mut self, bind_addr: impl Into<String>) -> Self {
+        self.socket = SocketSource::Bind(bind_addr.into());
+        self
+    }
+
+    /// Use a socket the caller has already bound instead of binding one
+    pub fn socket(mut self, socket: UdpSocket) -> Self {
+        self.socket = SocketSource::Bound(socket);
+        self
+    }
+
+    /// Sign responses, pings and pongs with `identity`
+    pub fn identity(mut self, identity: Keypair) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    /// Accept handshakes from peers on `network_id` (default: localnet)
+    pub fn network_id(mut self, network_id: impl Into<String>) -> Self {
+        self.network_id = network_id.into();
+        self
+    }
+
+    /// Give up on sending a packet after `timeout` (default: 30 seconds)
+    pub fn network_timeout(mut self, timeout: Duration) -> Self {
+        self.network_timeout = timeout;
+        self
+    }
+
+    /// Receive packets of up to `max_packet_size` bytes in full, and send no larger ones
+    pub fn max_packet_size(mut self, max_packet_size: usize) -> Self {
+        self.max_packet_size = max_packet_size.max(1);
+        self
+    }
+
+    /// Restrict the handshake protocol versions the node accepts
+    pub fn supported_versions(mut self, supported_versions: VersionRange) -> Self {
+        self.supported_versions = supported_versions;
+        self
+    }
+
+    /// Set the capabilities the node agrees to when peers offer them
+    pub fn supported_capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.supported_capabilities = capabilities;
+        self
+    }
+
+    /// Reject handshakes from peers that do not offer all of `capabilities`
+    pub fn required_capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.required_capabilities = capabilities;
+        self
+    }
+
+    /// Set how far a handshake request's timestamp may be from our clock
+    ///
+    /// Requests outside the window are rejected as stale, and accepted
+    /// requests are remembered for as long as the window so that replays of
+    /// them are rejected as duplicates.
+    pub fn replay_window(mut self, window: Duration) -> Self {
+        self.replay_window = window;
+        self
+    }
+
+    /// Configure how registered peers are probed and expired
+    pub fn liveness(mut self, config: LivenessConfig) -> Self {
+        self.liveness = config;
+        self
+    }
+
+    /// Limit incoming traffic globally, per source IP and per pubkey
+    pub fn rate_limits(mut self, config: RateLimitConfig) -> Self {
+        self.rate_limits = config;
+        self
+    }
+
+    /// Choose whether rejected requests from unverified senders are answered
+    pub fn reject_policy(mut self, reject_policy: RejectPolicy) -> Self {
+        self.reject_policy = reject_policy;
+        self
+    }
+
+    /// Only admit peers allowed by `access`
+    ///
+    /// The handle can be kept to replace the policy while the node runs.
+    pub fn access_control(mut self, access: AccessControl) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// Save the peer registry to `path` periodically and on shutdown
+    ///
+    /// Peers saved by a previous run are restored as `Connecting` when the
+    /// node starts and sent a handshake request. They are only `Connected`
+    /// again once they answer it and our ping challenge; peers that do not
+    /// are expired by liveness maintenance like any other unreachable peer.
+    pub fn peer_store(mut self, path: impl Into<PathBuf>) -> Self {
+        self.peer_store = Some(path.into());
+        self
+    }
+
+    /// Set how often the peer registry is saved to the peer store
+    pub fn snapshot_interval(mut self, interval: Duration) -> Self {
+        self.snapshot_interval = interval;
+        self
+    }
+
+    /// Set how many packets are handled concurrently
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Set how many received packets may wait for a free worker
+    ///
+    /// Once the queue is full the node stops reading from the socket, so
+    /// further packets wait in (or are dropped by) the kernel's receive buffer.
+    pub fn queue_depth(mut self, queue_depth: usize) -> Self {
+        self.queue_depth = queue_depth.max(1);
+        self
+    }
+
+    /// Export the node's traffic counters, packet sizes and peers through `metrics`
+    pub fn metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Bind the socket, if not given one, and create the node
+    #[tracing::instrument(skip(self), fields(network_id = %self.network_id))]
+    pub async fn build(self) -> Result<GossipNode, SolanaNodeError> {
+        let socket = match self.socket {
+            SocketSource::Bind(bind_addr) => UdpSocket::bind(&bind_addr).await.map_err(|e| {
+                error!(error = %e, bind_addr = %bind_addr, "Failed to bind UDP socket");
+                SolanaNodeError::NetworkError(e)
+            })?,
+            SocketSource::Bound(socket) => socket,
+        };
         let local_addr = socket.local_addr().map_err(SolanaNodeError::NetworkError)?;
+        let identity = self.identity.unwrap_or_else(Keypair::new);
+
+        info!(
+            local_addr = %local_addr,
+            node_pubkey = %identity.pubkey(),
+            network_id = %self.network_id,
+            "Gossip node socket bound"
+        );
 
-        info!(local_addr = %local_addr, "UDP socket bound successfully");
+        let (events, _) = broadcast::channel(DEFAULT_EVENT_CHANNEL_CAPACITY);
+        let state = NodeState {
+            replay_cache: ReplayCache::new(self.replay_window, DEFAULT_REPLAY_CACHE_CAPACITY),
+            liveness: LivenessTracker::new(self.liveness),
+            rate_limiter: RateLimiter::new(self.rate_limits),
+            ..NodeState::default()
+        };
 
-        // Channels removed for simplicity - using direct synchronous handling
+        let stats = NodeStats::new();
+        let peer_registry = PeerRegistry::new();
+        let metrics = self.metrics.unwrap_or_default();
+        metrics.observe_node_stats(stats.clone());
+        metrics.observe_peer_registry(peer_registry.clone());
 
-        Ok(Self {
+        Ok(GossipNode {
             socket,
-            node_pubkey,
-            network_id,
-            peer_registry: HashMap::new(),
+            identity,
+            network_id: self.network_id,
+            network_timeout: self.network_timeout,
+            max_packet_size: self.max_packet_size,
+            peer_registry,
+            state: Mutex::new(state),
+            supported_versions: self.supported_versions,
+            supported_capabilities: self.supported_capabilities,
+            required_capabilities: self.required_capabilities,
+            reject_policy: self.reject_policy,
+            access: self.access,
+            stats,
+            metrics,
+            peer_store: self.peer_store,
+            snapshot_interval: self.snapshot_interval,
+            workers: self.workers,
+            queue_depth: self.queue_depth,
+            events,
         })
     }
+}
+
+impl GossipNode {
+    /// Start building a node with default settings
+    pub fn builder() -> GossipNodeBuilder {
+        GossipNodeBuilder::default()
+    }
+
+    /// Create a new gossip node instance
+    pub async fn new(
+        bind_addr: &str,
+        identity: Keypair,
+        network_id: String,
+    ) -> Result<Self, SolanaNodeError> {
+        Self::builder()
+            .bind_addr(bind_addr)
+            .identity(identity)
+            .network_id(network_id)
+            .build()
+            .await
+    }
+
+    /// This node's public key
+    pub fn node_pubkey(&self) -> Pubkey {
+        self.identity.pubkey()
+    }
 
     /// Get the local address this node is bound to
     pub fn local_addr(&self) -> Result<SocketAddr, SolanaNodeError> {
@@ -78,16 +587,49 @@ impl GossipNode {
             .map_err(SolanaNodeError::NetworkError)
     }
 
+    fn state(&self) -> MutexGuard<'_, NodeState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Subscribe to peer lifecycle events
+    ///
+    /// Only events published after the call are received.
+    pub fn subscribe(&self) -> broadcast::Receiver<PeerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Publish an event to current subscribers, if any
+    fn emit(&self, event: PeerEvent) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.events.send(event);
+    }
+
+    /// Report a peer that failed verification and build the matching error
+    fn verification_failed(&self, peer_addr: SocketAddr, reason: &str) -> SolanaNodeError {
+        self.emit(PeerEvent::PeerVerificationFailed {
+            peer_addr,
+            reason: reason.to_string(),
+        });
+        SolanaNodeError::PeerVerificationFailed { peer_addr }
+    }
+
     /// Add a peer to the registry or update existing peer info
     #[tracing::instrument(skip(self))]
-    pub async fn register_peer(&mut self, peer_info: GossipNodeInfo) {
+    pub async fn register_peer(&self, peer_info: GossipNodeInfo) {
         debug!(
             peer_addr = %peer_info.gossip_addr,
             peer_pubkey = %peer_info.peer_pubkey,
             "Registering peer in gossip node"
         );
 
-        self.peer_registry.insert(peer_info.gossip_addr, peer_info);
+        let event = match self.peer_registry.upsert(peer_info.clone()) {
+            Some(previous) => PeerEvent::PeerUpdated {
+                peer_info,
+                previous_status: previous.connection_status,
+            },
+            None => PeerEvent::PeerRegistered { peer_info },
+        };
+        self.emit(event);
 
         info!(
             total_peers = self.peer_registry.len(),
@@ -95,40 +637,48 @@ impl GossipNode {
         );
     }
 
+    /// Handle to the traffic counters, usable from other tasks while the node runs
+    pub fn stats(&self) -> NodeStats {
+        self.stats.clone()
+    }
+
+    /// Handle to the peer registry, usable from other tasks while the node runs
+    pub fn peer_registry(&self) -> PeerRegistry {
+        self.peer_registry.clone()
+    }
+
     /// Get information about all registered peers
-    pub fn get_peers(&self) -> Vec<&GossipNodeInfo> {
-        self.peer_registry.values().collect()
+    pub fn get_peers(&self) -> Vec<GossipNodeInfo> {
+        self.peer_registry.snapshot()
     }
 
     /// Get information about a specific peer
-    pub fn get_peer_info(&self, addr: &SocketAddr) -> Option<&GossipNodeInfo> {
+    pub fn get_peer_info(&self, addr: &SocketAddr) -> Option<GossipNodeInfo> {
         self.peer_registry.get(addr)
     }
 
-    /// Send a handshake request to a peer
+    /// Sign a handshake request with this node's identity and send it to a peer
     #[tracing::instrument(skip(self, request))]
     pub async fn send_handshake_request(
         &self,
         peer_addr: SocketAddr,
-        request: HandshakeRequest,
+        mut request: HandshakeRequest,
     ) -> Result<(), SolanaNodeError> {
         debug!(
             peer_addr = %peer_addr,
-            protocol_version = %request.protocol_version,
+            supported_versions = %request.supported_versions,
             "Sending handshake request"
         );
 
+        request.sign(&self.identity);
+
         let serialized_request = borsh::to_vec(&request)
             .map_err(|e| SolanaNodeError::SerializationFailed(e.to_string()))?;
 
-        if serialized_request.len() > MAX_GOSSIP_PACKET_SIZE {
-            return Err(SolanaNodeError::InvalidPeerData {
-                reason: format!("Request too large: {} bytes", serialized_request.len()),
-            });
-        }
+        self.check_packet_size("handshake request", &serialized_request)?;
 
         let result = timeout(
-            DEFAULT_NETWORK_TIMEOUT,
+            self.network_timeout,
             self.socket.send_to(&serialized_request, peer_addr),
         )
         .await;
@@ -153,46 +703,270 @@ impl GossipNode {
             Err(_) => {
                 warn!(
                     peer_addr = %peer_addr,
-                    timeout_secs = DEFAULT_NETWORK_TIMEOUT.as_secs(),
+                    timeout_secs = self.network_timeout.as_secs(),
                     "Handshake request timed out"
                 );
-                Err(SolanaNodeError::TimeoutError)
+                Err(SolanaNodeError::SendTimeout { peer_addr })
             }
         }
     }
 
     /// Start the main event loop for processing network messages
-    #[tracing::instrument(skip(self))]
+    ///
+    /// Runs until the socket fails. Use `run_until` to stop it on demand.
     pub async fn run(&mut self) -> Result<(), SolanaNodeError> {
-        info!("Starting gossip node main event loop");
+        self.run_until(std::future::pending()).await.map(|_| ())
+    }
+
+    /// Run the main event loop until `shutdown` completes
+    ///
+    /// Up to `workers` packets are handled concurrently, with up to
+    /// `queue_depth` more waiting. When `shutdown` completes, packets being
+    /// handled or already queued (on the node or the socket) are finished, and
+    /// handshakes still waiting on a ping challenge are dropped.
+    #[tracing::instrument(skip(self, shutdown))]
+    pub async fn run_until(
+        &mut self,
+        shutdown: impl Future<Output = ()>,
+    ) -> Result<RunSummary, SolanaNodeError> {
+        info!(
+            workers = self.workers,
+            queue_depth = self.queue_depth,
+            "Starting gossip node main event loop"
+        );
 
-        let mut packet_buffer = vec![0u8; MAX_GOSSIP_PACKET_SIZE];
+        let node = &*self;
+        let started = Instant::now();
+        let mut packets_received = 0u64;
+        let mut packets_failed = 0u64;
+        let mut packets_rate_limited = 0u64;
+        let mut packet_buffer = vec![0u8; node.max_packet_size];
+        let mut queue = VecDeque::with_capacity(node.queue_depth);
+        let mut in_flight = FuturesUnordered::new();
+
+        let probe_interval = node.state().liveness.config().probe_interval;
+        let mut maintenance =
+            tokio::time::interval_at(tokio::time::Instant::now() + probe_interval, probe_interval);
+        maintenance.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        let mut snapshots = tokio::time::interval_at(
+            tokio::time::Instant::now() + node.snapshot_interval,
+            node.snapshot_interval,
+        );
+        snapshots.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        // Housekeeping runs alongside packet handling rather than stalling it
+        let restoring = node.restore_peers().fuse();
+        let maintaining = Fuse::terminated();
+        let saving = Fuse::terminated();
+
+        tokio::pin!(shutdown, restoring, maintaining, saving);
 
         loop {
-            // Handle incoming UDP packets
-            match self.socket.recv_from(&mut packet_buffer).await {
-                Ok((packet_length, sender_addr)) => {
-                    debug!(
-                        sender_addr = %sender_addr,
-                        packet_length = packet_length,
-                        "Received UDP packet"
-                    );
+            while in_flight.len() < node.workers {
+                let Some((packet, sender_addr)) = queue.pop_front() else {
+                    break;
+                };
+                in_flight.push(node.handle_packet(packet, sender_addr));
+            }
 
-                    if let Err(e) = self
-                        .process_incoming_packet(&packet_buffer[..packet_length], sender_addr)
-                        .await
-                    {
-                        warn!(
-                            error = %e,
-                            sender_addr = %sender_addr,
-                            "Failed to process incoming packet"
-                        );
+            tokio::select! {
+                _ = &mut shutdown => break,
+                Some(handled) = in_flight.next(), if !in_flight.is_empty() => {
+                    if !handled {
+                        packets_failed += 1;
+                    }
+                }
+                received = node.socket.recv_from(&mut packet_buffer), if queue.len() < node.queue_depth => {
+                    match received {
+                        Ok((packet_length, sender_addr)) => {
+                            debug!(
+                                sender_addr = %sender_addr,
+                                packet_length = packet_length,
+                                "Received UDP packet"
+                            );
+                            packets_received += 1;
+                            node.stats.packet_received();
+                            node.metrics.packet_received(packet_length);
+
+                            let limited = node
+                                .state()
+                                .rate_limiter
+                                .check_packet(Instant::now(), sender_addr.ip());
+                            if let Err(exceeded) = limited {
+                                trace!(
+                                    sender_addr = %sender_addr,
+                                    limit = %exceeded,
+                                    "Dropping rate-limited packet"
+                                );
+                                node.stats.rate_limited(exceeded);
+                                packets_rate_limited += 1;
+                                continue;
+                            }
+
+                            queue.push_back((packet_buffer[..packet_length].to_vec(), sender_addr));
+                        }
+                        Err(e) => {
+                            error!(error = %e, "UDP receive error");
+                            return Err(SolanaNodeError::NetworkError(e));
+                        }
                     }
                 }
-                Err(e) => {
-                    error!(error = %e, "UDP receive error");
-                    return Err(SolanaNodeError::NetworkError(e));
+                _ = &mut restoring, if !restoring.is_terminated() => {}
+                _ = &mut maintaining, if !maintaining.is_terminated() => {}
+                _ = &mut saving, if !saving.is_terminated() => {}
+                _ = maintenance.tick(), if maintaining.is_terminated() => {
+                    maintaining.set(node.run_maintenance().fuse());
                 }
+                _ = snapshots.tick(), if node.peer_store.is_some() && saving.is_terminated() => {
+                    saving.set(node.save_peers().fuse());
+                }
+            }
+        }
+
+        info!(
+            in_flight = in_flight.len(),
+            queued = queue.len(),
+            "Shutdown requested, draining queued packets"
+        );
+
+        let mut packets_drained = queue.len();
+        while packets_drained < MAX_DRAIN_PACKETS {
+            let Ok((packet_length, sender_addr)) = node.socket.try_recv_from(&mut packet_buffer)
+            else {
+                break;
+            };
+            packets_drained += 1;
+            packets_received += 1;
+            node.stats.packet_received();
+            node.metrics.packet_received(packet_length);
+            queue.push_back((packet_buffer[..packet_length].to_vec(), sender_addr));
+        }
+
+        while !queue.is_empty() || !in_flight.is_empty() {
+            while in_flight.len() < node.workers {
+                let Some((packet, sender_addr)) = queue.pop_front() else {
+                    break;
+                };
+                in_flight.push(node.handle_packet(packet, sender_addr));
+            }
+            if let Some(false) = in_flight.next().await {
+                packets_failed += 1;
+            }
+        }
+
+        // Let a snapshot already being written finish before writing the final one
+        if !saving.is_terminated() {
+            saving.await;
+        }
+        node.save_peers().await;
+
+        let abandoned_handshakes = {
+            let mut state = node.state();
+            let abandoned = state.pending_peers.len();
+            state.pending_peers.clear();
+            abandoned
+        };
+
+        let summary = RunSummary {
+            uptime: started.elapsed(),
+            packets_received,
+            packets_failed,
+            packets_rate_limited,
+            packets_drained,
+            abandoned_handshakes,
+            peers: node.peer_registry.snapshot(),
+        };
+
+        info!(
+            uptime_secs = summary.uptime.as_secs(),
+            packets_received = summary.packets_received,
+            packets_failed = summary.packets_failed,
+            packets_rate_limited = summary.packets_rate_limited,
+            packets_drained = summary.packets_drained,
+            abandoned_handshakes = summary.abandoned_handshakes,
+            total_peers = summary.peers.len(),
+            "Gossip node stopped"
+        );
+
+        Ok(summary)
+    }
+
+    /// Load peers saved by a previous run and start re-verifying them
+    async fn restore_peers(&self) {
+        let Some(path) = &self.peer_store else {
+            return;
+        };
+
+        let peers = match PeerRegistry::load(path).await {
+            Ok(peers) => peers,
+            Err(e) => {
+                warn!(error = %e, "Failed to load peer store, starting with no known peers");
+                return;
+            }
+        };
+
+        let mut restored = 0;
+        for mut peer_info in peers {
+            let peer_addr = peer_info.gossip_addr;
+            if peer_info.network_id != self.network_id
+                || self
+                    .access
+                    .check(peer_addr.ip(), &peer_info.peer_pubkey)
+                    .is_err()
+            {
+                debug!(peer_addr = %peer_addr, "Not restoring peer from another network or denied by access policy");
+                continue;
+            }
+
+            peer_info.connection_status = ConnectionStatus::Connecting;
+            self.peer_registry.upsert(peer_info.clone());
+            self.emit(PeerEvent::PeerRegistered { peer_info });
+            restored += 1;
+
+            let mut request = HandshakeRequest::new_local(self.node_pubkey());
+            request.network_id = self.network_id.clone();
+            request.supported_versions = self.supported_versions;
+            request.capabilities = self.supported_capabilities;
+            if let Err(e) = self.send_handshake_request(peer_addr, request).await {
+                warn!(error = %e, peer_addr = %peer_addr, "Failed to re-verify restored peer");
+            }
+        }
+
+        info!(
+            path = %path.display(),
+            restored_peers = restored,
+            "Restored peers from peer store, re-verifying them"
+        );
+    }
+
+    /// Save the peer registry to the peer store, if one is configured
+    async fn save_peers(&self) {
+        let Some(path) = &self.peer_store else {
+            return;
+        };
+
+        match self.peer_registry.save(path).await {
+            Ok(saved) => debug!(path = %path.display(), saved_peers = saved, "Peer registry saved"),
+            Err(e) => warn!(error = %e, "Failed to save peer registry"),
+        }
+    }
+
+    /// Process a packet, logging any failure, and return whether it succeeded
+    async fn handle_packet(&self, packet_data: Vec<u8>, sender_addr: SocketAddr) -> bool {
+        match self
+            .process_incoming_packet(&packet_data, sender_addr)
+            .await
+        {
+            Ok(()) => true,
+            Err(e) => {
+                warn!(
+                    error = %e,
+                    sender_addr = %sender_addr,
+                    "Failed to process incoming packet"
+                );
+                self.stats.packet_failed();
+                false
             }
         }
     }
@@ -200,26 +974,83 @@ impl GossipNode {
     /// Process an incoming network packet
     #[tracing::instrument(skip(self, packet_data))]
     async fn process_incoming_packet(
-        &mut self,
+        &self,
         packet_data: &[u8],
         sender_addr: SocketAddr,
     ) -> Result<(), SolanaNodeError> {
         // Try to deserialize as a handshake request first
-        if let Ok(handshake_request) = HandshakeRequest::try_from_slice(packet_data) {
+        if let Ok(handshake_request) = HandshakeRequest::decode(packet_data) {
             info!(
                 sender_addr = %sender_addr,
                 peer_pubkey = %handshake_request.node_pubkey,
-                protocol_version = %handshake_request.protocol_version,
+                supported_versions = %handshake_request.supported_versions,
                 "Received handshake request"
             );
 
+            if !handshake_request.verify_signature() {
+                warn!(
+                    sender_addr = %sender_addr,
+                    peer_pubkey = %handshake_request.node_pubkey,
+                    "Rejecting handshake request with invalid signature"
+                );
+                self.emit(PeerEvent::PeerVerificationFailed {
+                    peer_addr: sender_addr,
+                    reason: "invalid request signature".to_string(),
+                });
+                return Err(SolanaNodeError::SignatureInvalid {
+                    peer_addr: sender_addr,
+                });
+            }
+
+            let limited = self
+                .state()
+                .rate_limiter
+                .check_request(Instant::now(), handshake_request.node_pubkey);
+            if let Err(exceeded) = limited {
+                debug!(
+                    sender_addr = %sender_addr,
+                    peer_pubkey = %handshake_request.node_pubkey,
+                    limit = %exceeded,
+                    "Dropping rate-limited handshake request"
+                );
+                self.stats.rate_limited(exceeded);
+                return Ok(());
+            }
+
+            self.emit(PeerEvent::HandshakeReceived {
+                peer_addr: sender_addr,
+                peer_pubkey: handshake_request.node_pubkey,
+            });
+
+            if let Err(denied) = self
+                .access
+                .check(sender_addr.ip(), &handshake_request.node_pubkey)
+            {
+                warn!(
+                    sender_addr = %sender_addr,
+                    peer_pubkey = %handshake_request.node_pubkey,
+                    reason = %denied,
+                    "Rejecting handshake request denied by access policy"
+                );
+                self.send_rejection(
+                    sender_addr,
+                    &handshake_request,
+                    RejectReason::AccessDenied,
+                    format!("Access denied: {}", denied),
+                )
+                .await?;
+                return Err(self.verification_failed(sender_addr, &denied.to_string()));
+            }
+
+            self.check_replay(sender_addr, &handshake_request).await?;
+
             return self
                 .handle_handshake_request(sender_addr, handshake_request)
                 .await;
         }
 
         // Try to deserialize as a handshake response
-        if let Ok(handshake_response) = HandshakeResponse::try_from_slice(packet_data) {
+        if let Ok(handshake_response) = HandshakeResponse::decode(packet_data) {
             info!(
                 sender_addr = %sender_addr,
                 peer_pubkey = %handshake_response.peer_pubkey,
@@ -227,91 +1058,600 @@ impl GossipNode {
                 "Received handshake response"
             );
 
+            if !handshake_response.verify_signature() {
+                warn!(
+                    sender_addr = %sender_addr,
+                    peer_pubkey = %handshake_response.peer_pubkey,
+                    "Rejecting handshake response with invalid signature"
+                );
+                return Err(self.verification_failed(sender_addr, "invalid response signature"));
+            }
+
+            // Every response from an unverified sender draws a ping, so
+            // responses count against the signer's request limit
+            let limited = self
+                .state()
+                .rate_limiter
+                .check_request(Instant::now(), handshake_response.peer_pubkey);
+            if let Err(exceeded) = limited {
+                debug!(
+                    sender_addr = %sender_addr,
+                    peer_pubkey = %handshake_response.peer_pubkey,
+                    limit = %exceeded,
+                    "Dropping rate-limited handshake response"
+                );
+                self.stats.rate_limited(exceeded);
+                return Ok(());
+            }
+
+            if let Err(denied) = self
+                .access
+                .check(sender_addr.ip(), &handshake_response.peer_pubkey)
+            {
+                warn!(
+                    sender_addr = %sender_addr,
+                    peer_pubkey = %handshake_response.peer_pubkey,
+                    reason = %denied,
+                    "Ignoring handshake response denied by access policy"
+                );
+                return Err(self.verification_failed(sender_addr, &denied.to_string()));
+            }
+
             return self
                 .handle_handshake_response(sender_addr, handshake_response)
                 .await;
         }
 
+        // Try to deserialize as a gossip protocol message (ping/pong challenges)
+        if let Ok(message) = Protocol::decode(packet_data) {
+            if let Err(denied) = self.access.check_addr(sender_addr.ip()) {
+                debug!(
+                    sender_addr = %sender_addr,
+                    reason = %denied,
+                    "Ignoring gossip message denied by access policy"
+                );
+                return Err(self.verification_failed(sender_addr, &denied.to_string()));
+            }
+            return self.handle_gossip_message(sender_addr, message).await;
+        }
+
         debug!(
             sender_addr = %sender_addr,
             packet_size = packet_data.len(),
             "Received unknown packet type"
         );
+        self.stats.packet_unknown();
+        self.emit(PeerEvent::UnknownPacket {
+            sender_addr,
+            packet_size: packet_data.len(),
+        });
 
         Ok(())
     }
 
+    /// Handle a gossip protocol message from a peer
+    #[tracing::instrument(skip(self, message), fields(message = message.name()))]
+    async fn handle_gossip_message(
+        &self,
+        sender_addr: SocketAddr,
+        message: Protocol,
+    ) -> Result<(), SolanaNodeError> {
+        match message {
+            Protocol::PingMessage(ping) => {
+                if !ping.verify() {
+                    return Err(self.verification_failed(sender_addr, "invalid ping signature"));
+                }
+                let pong = Pong::new(&ping, &self.identity);
+                self.send_gossip_message(sender_addr, &Protocol::PongMessage(pong))
+                    .await
+            }
+            Protocol::PongMessage(pong) => self.handle_pong(sender_addr, pong).await,
+            other => {
+                debug!(
+                    sender_addr = %sender_addr,
+                    message = other.name(),
+                    "Ignoring unsupported gossip message"
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Handle a pong, completing any handshake that was waiting on it
+    async fn handle_pong(
+        &self,
+        sender_addr: SocketAddr,
+        pong: Pong,
+    ) -> Result<(), SolanaNodeError> {
+        let pending = {
+            let mut state = self.state();
+
+            // The pong must come from the key that requested the handshake
+            if let Some(pending) = state.pending_peers.get(&sender_addr) {
+                if pending.peer_info.peer_pubkey != pong.from {
+                    warn!(
+                        sender_addr = %sender_addr,
+                        expected_pubkey = %pending.peer_info.peer_pubkey,
+                        pong_from = %pong.from,
+                        "Pong signed by a different key than the handshake"
+                    );
+                    return Err(self.verification_failed(
+                        sender_addr,
+                        "pong signed by a different key than the handshake",
+                    ));
+                }
+            }
+
+            if !state
+                .ping_cache
+                .add_pong(Instant::now(), &pong, sender_addr)
+            {
+                warn!(
+                    sender_addr = %sender_addr,
+                    pong_from = %pong.from,
+                    "Received pong that does not answer an outstanding ping"
+                );
+                return Err(self
+                    .verification_failed(sender_addr, "pong does not answer an outstanding ping"));
+            }
+
+            let Some(pending) = state.pending_peers.remove(&sender_addr) else {
+                // Otherwise this answers a liveness probe to a registered peer
+                let is_registered_peer = self
+                    .peer_registry
+                    .get(&sender_addr)
+                    .is_some_and(|peer_info| peer_info.peer_pubkey == pong.from);
+                if is_registered_peer {
+                    debug!(sender_addr = %sender_addr, "Peer answered liveness probe");
+                    let previous_status = state.liveness.record_reply(
+                        &sender_addr,
+                        unix_timestamp(),
+                        &self.peer_registry,
+                    );
+                    if let (Some(previous_status), Some(peer_info)) =
+                        (previous_status, self.peer_registry.get(&sender_addr))
+                    {
+                        self.emit(PeerEvent::PeerUpdated {
+                            peer_info,
+                            previous_status,
+                        });
+                    }
+                } else {
+                    debug!(sender_addr = %sender_addr, "Peer verified with no pending handshake");
+                }
+                return Ok(());
+            };
+
+            state.liveness.reset(&sender_addr);
+            pending
+        };
+
+        info!(
+            sender_addr = %sender_addr,
+            peer_pubkey = %pong.from,
+            "Peer answered ping challenge"
+        );
+
+        self.register_peer(pending.peer_info).await;
+
+        match pending.response {
+            Some(response) => self.send_handshake_response(sender_addr, response).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Register a peer now if it is verified, otherwise challenge it with a ping
+    ///
+    /// `response` is sent to the peer as soon as it is verified.
+    async fn register_or_challenge(
+        &self,
+        sender_addr: SocketAddr,
+        peer_info: GossipNodeInfo,
+        response: Option<HandshakeResponse>,
+    ) -> Result<(), SolanaNodeError> {
+        let now = Instant::now();
+
+        let verified = {
+            let mut state = self.state();
+            let verified = state
+                .ping_cache
+                .check(now, &peer_info.peer_pubkey, &sender_addr);
+            if verified {
+                state.liveness.reset(&sender_addr);
+            }
+            verified
+        };
+
+        if verified {
+            self.register_peer(peer_info).await;
+            return match response {
+                Some(response) => self.send_handshake_response(sender_addr, response).await,
+                None => Ok(()),
+            };
+        }
+
+        let ping = {
+            let mut state = self.state();
+
+            let evicted = state.pending_peers.insert(
+                sender_addr,
+                PendingPeer {
+                    peer_info,
+                    response,
+                    queued_at: now,
+                },
+                DEFAULT_PING_CACHE_CAPACITY,
+            );
+            if let Some(evicted) = evicted {
+                warn!(
+                    sender_addr = %sender_addr,
+                    evicted_addr = %evicted,
+                    "Too many unverified peers, dropped the oldest waiting handshake"
+                );
+            }
+
+            state
+                .ping_cache
+                .maybe_ping(now, sender_addr, &self.identity)
+        };
+
+        match ping {
+            Some(ping) => {
+                debug!(sender_addr = %sender_addr, "Challenging unverified peer with ping");
+                self.send_gossip_message(sender_addr, &Protocol::PingMessage(ping))
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Probe registered peers and expire the ones that stopped answering
+    async fn run_maintenance(&self) {
+        let now = Instant::now();
+
+        let (report, probes) = {
+            let mut state = self.state();
+            state.ping_cache.prune(now);
+            state.rate_limiter.prune(now);
+            state.replay_cache.prune(unix_timestamp());
+
+            // A handshake whose ping went unanswered for this long never completes
+            let ttl = state.ping_cache.ttl();
+            let abandoned = state.pending_peers.prune(now, ttl);
+            if abandoned > 0 {
+                debug!(
+                    abandoned,
+                    "Dropped handshakes whose ping was never answered"
+                );
+            }
+
+            let report = state
+                .liveness
+                .maintain(unix_timestamp(), &self.peer_registry);
+
+            for peer_info in &report.expired {
+                state.pending_peers.remove(&peer_info.gossip_addr);
+            }
+
+            let probes: Vec<_> = report
+                .to_probe
+                .iter()
+                .filter_map(|addr| {
+                    state
+                        .ping_cache
+                        .maybe_ping(now, *addr, &self.identity)
+                        .map(|ping| (*addr, ping))
+                })
+                .collect();
+
+            (report, probes)
+        };
+
+        for peer_info in report.disconnected.iter().cloned() {
+            self.emit(PeerEvent::PeerUpdated {
+                peer_info,
+                previous_status: ConnectionStatus::Connected,
+            });
+        }
+
+        for peer_info in report.expired.iter().cloned() {
+            self.emit(PeerEvent::PeerExpired { peer_info });
+        }
+
+        debug!(
+            probed = report.to_probe.len(),
+            disconnected = report.disconnected.len(),
+            expired = report.expired.len(),
+            total_peers = self.peer_registry.len(),
+            "Peer maintenance completed"
+        );
+
+        for (addr, ping) in probes {
+            if let Err(e) = self
+                .send_gossip_message(addr, &Protocol::PingMessage(ping))
+                .await
+            {
+                warn!(error = %e, peer_addr = %addr, "Failed to send liveness probe");
+            }
+        }
+    }
+
+    /// Refuse to send a packet larger than the configured limit
+    fn check_packet_size(&self, what: &str, packet: &[u8]) -> Result<(), SolanaNodeError> {
+        if packet.len() > self.max_packet_size {
+            warn!(
+                packet = what,
+                size = packet.len(),
+                max_packet_size = self.max_packet_size,
+                "Refusing to send oversized packet"
+            );
+            return Err(CodecError::PacketTooLarge {
+                size: packet.len(),
+                max: self.max_packet_size,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Send a gossip protocol message to a peer
+    async fn send_gossip_message(
+        &self,
+        peer_addr: SocketAddr,
+        message: &Protocol,
+    ) -> Result<(), SolanaNodeError> {
+        let packet = message.encode()?;
+        self.check_packet_size(message.name(), &packet)?;
+
+        match timeout(
+            self.network_timeout,
+            self.socket.send_to(&packet, peer_addr),
+        )
+        .await
+        {
+            Ok(Ok(bytes_sent)) => {
+                debug!(
+                    peer_addr = %peer_addr,
+                    bytes_sent = bytes_sent,
+                    message = message.name(),
+                    "Gossip message sent"
+                );
+                Ok(())
+            }
+            Ok(Err(e)) => Err(SolanaNodeError::NetworkError(e)),
+            Err(_) => Err(SolanaNodeError::SendTimeout { peer_addr }),
+        }
+    }
+
+    /// Reject stale and replayed handshake requests
+    ///
+    /// The sender is told why with a failure response, in case the rejection
+    /// comes from clock skew rather than an actual replay.
+    async fn check_replay(
+        &self,
+        sender_addr: SocketAddr,
+        request: &HandshakeRequest,
+    ) -> Result<(), SolanaNodeError> {
+        let now = unix_timestamp();
+
+        let checked = self.state().replay_cache.check_and_insert(
+            now,
+            request.node_pubkey,
+            request.nonce,
+            request.timestamp,
+        );
+        let Err(rejection) = checked else {
+            return Ok(());
+        };
+
+        warn!(
+            sender_addr = %sender_addr,
+            peer_pubkey = %request.node_pubkey,
+            reason = %rejection,
+            "Rejecting stale or replayed handshake request"
+        );
+
+        let reject_reason = match rejection {
+            ReplayRejection::Stale { .. } => RejectReason::StaleRequest,
+            ReplayRejection::Duplicate { .. } => RejectReason::ReplayedRequest,
+        };
+        self.send_rejection(
+            sender_addr,
+            request,
+            reject_reason,
+            format!("Handshake request rejected: {}", rejection),
+        )
+        .await?;
+
+        Err(SolanaNodeError::ReplayedRequest {
+            peer_pubkey: request.node_pubkey,
+            reason: rejection.to_string(),
+        })
+    }
+
+    /// Tell the sender of `request` why it was rejected, if the reject policy allows
+    ///
+    /// A sender whose address has not answered a ping may be spoofed, so
+    /// failure responses to unverified senders draw on their own rate limit,
+    /// and under `RejectPolicy::DropUnverified` are not sent at all. The
+    /// response never echoes text from the request, so it stays about the
+    /// size of the request it answers.
+    async fn send_rejection(
+        &self,
+        sender_addr: SocketAddr,
+        request: &HandshakeRequest,
+        reject_reason: RejectReason,
+        error_message: String,
+    ) -> Result<(), SolanaNodeError> {
+        let suppressed = {
+            let now = Instant::now();
+            let mut state = self.state();
+            let verified = state
+                .ping_cache
+                .check(now, &request.node_pubkey, &sender_addr);
+            !verified
+                && (self.reject_policy == RejectPolicy::DropUnverified
+                    || !state.rate_limiter.check_rejection(now))
+        };
+        if suppressed {
+            debug!(
+                sender_addr = %sender_addr,
+                reason = %error_message,
+                "Not answering rejected request from unverified sender"
+            );
+            self.stats.rejection_suppressed();
+            return Ok(());
+        }
+
+        let error_response = self.failure_response(request, reject_reason, error_message)?;
+        self.send_handshake_response(sender_addr, error_response)
+            .await
+    }
+
+    /// Build an unsuccessful response to `request`
+    fn failure_response(
+        &self,
+        request: &HandshakeRequest,
+        reject_reason: RejectReason,
+        error_message: String,
+    ) -> Result<HandshakeResponse, SolanaNodeError> {
+        Ok(HandshakeResponse {
+            peer_pubkey: self.identity.pubkey(),
+            protocol_version: self.supported_versions.max,
+            supported_versions: self.supported_versions,
+            network_id: self.network_id.clone(),
+            gossip_addr: self.local_addr()?,
+            capabilities: self.supported_capabilities,
+            timestamp: unix_timestamp(),
+            success: false,
+            error_message: Some(error_message),
+            reject_reason: Some(reject_reason),
+            nonce: request.nonce,
+            signature: MessageSignature::default(),
+        })
+    }
+
     /// Handle an incoming handshake request from a peer
-    #[tracing::instrument(skip(self, request))]
+    #[tracing::instrument(skip(self, request), fields(correlation_id = %request.correlation_id))]
     async fn handle_handshake_request(
-        &mut self,
+        &self,
         sender_addr: SocketAddr,
         request: HandshakeRequest,
     ) -> Result<(), SolanaNodeError> {
         // Validate network compatibility
         if request.network_id != self.network_id {
-            let error_response = HandshakeResponse {
-                peer_pubkey: self.node_pubkey,
-                protocol_version: "1.18.0".to_string(),
-                network_id: self.network_id.clone(),
-                gossip_addr: self.local_addr()?,
-                capabilities: vec!["gossip".to_string()],
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs(),
-                success: false,
-                error_message: Some(format!(
-                    "Network ID mismatch: expected {}, got {}",
-                    self.network_id, request.network_id
-                )),
-            };
+            self.send_rejection(
+                sender_addr,
+                &request,
+                RejectReason::NetworkMismatch,
+                format!("Network ID mismatch: expected {}", self.network_id),
+            )
+            .await?;
+            return Err(SolanaNodeError::NetworkMismatch {
+                expected: self.network_id.clone(),
+                received: request.network_id,
+            });
+        }
 
-            return self
-                .send_handshake_response(sender_addr, error_response)
-                .await;
+        // Agree on the highest version both sides speak
+        let Some(negotiated_version) = self
+            .supported_versions
+            .negotiate(&request.supported_versions)
+        else {
+            warn!(
+                sender_addr = %sender_addr,
+                offered_versions = %request.supported_versions,
+                supported_versions = %self.supported_versions,
+                "No common protocol version with peer"
+            );
+            self.send_rejection(
+                sender_addr,
+                &request,
+                RejectReason::UnsupportedVersion,
+                format!(
+                    "Unsupported protocol versions: supported {}",
+                    self.supported_versions
+                ),
+            )
+            .await?;
+            return Err(SolanaNodeError::ProtocolVersionMismatch {
+                expected: self.supported_versions.to_string(),
+                received: request.supported_versions.to_string(),
+            });
+        };
+
+        let missing_capabilities = self.required_capabilities.difference(request.capabilities);
+        if !missing_capabilities.is_empty() {
+            warn!(
+                sender_addr = %sender_addr,
+                offered_capabilities = %request.capabilities,
+                missing_capabilities = %missing_capabilities,
+                "Peer does not offer required capabilities"
+            );
+            self.send_rejection(
+                sender_addr,
+                &request,
+                RejectReason::MissingCapabilities,
+                format!("Missing required capabilities: {}", missing_capabilities),
+            )
+            .await?;
+            return Err(SolanaNodeError::MissingCapabilities {
+                missing: missing_capabilities,
+            });
         }
 
+        // Agree to the offered capabilities this node also supports
+        let agreed_capabilities = request
+            .capabilities
+            .intersection(self.supported_capabilities);
+
+        debug!(
+            sender_addr = %sender_addr,
+            negotiated_version = %negotiated_version,
+            agreed_capabilities = %agreed_capabilities,
+            "Negotiated protocol version and capabilities"
+        );
+
         // Create a successful handshake response
         let response = HandshakeResponse {
-            peer_pubkey: self.node_pubkey,
-            protocol_version: request.protocol_version.clone(),
+            peer_pubkey: self.identity.pubkey(),
+            protocol_version: negotiated_version,
+            supported_versions: self.supported_versions,
             network_id: self.network_id.clone(),
             gossip_addr: self.local_addr()?,
-            capabilities: vec!["gossip".to_string(), "handshake".to_string()],
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
+            capabilities: agreed_capabilities,
+            timestamp: unix_timestamp(),
             success: true,
             error_message: None,
+            reject_reason: None,
+            nonce: request.nonce,
+            signature: MessageSignature::default(),
         };
 
-        // Register the peer
+        // Register the peer once it has answered our ping challenge
         let peer_info = GossipNodeInfo {
             peer_pubkey: request.node_pubkey,
             gossip_addr: sender_addr,
-            protocol_version: request.protocol_version,
+            protocol_version: request.supported_versions.max.to_string(),
+            negotiated_version: Some(negotiated_version),
             network_id: request.network_id,
-            capabilities: request.capabilities,
+            capabilities: agreed_capabilities,
             last_seen: request.timestamp,
             connection_status: ConnectionStatus::Connected,
         };
 
-        self.register_peer(peer_info).await;
-
-        // Send response
-        self.send_handshake_response(sender_addr, response).await
+        self.register_or_challenge(sender_addr, peer_info, Some(response))
+            .await
     }
 
     /// Handle an incoming handshake response from a peer
     #[tracing::instrument(skip(self, response))]
     async fn handle_handshake_response(
-        &mut self,
+        &self,
         sender_addr: SocketAddr,
         response: HandshakeResponse,
     ) -> Result<(), SolanaNodeError> {
-        if let Err(e) = response.verify(&self.network_id) {
+        if let Err(e) = response.verify(&self.network_id, &self.supported_versions) {
             warn!(
                 error = %e,
                 sender_addr = %sender_addr,
@@ -320,25 +1660,43 @@ impl GossipNode {
             return Err(e);
         }
 
-        // Register the peer from a successful handshake
-        let peer_info = GossipNodeInfo::from_handshake_response(response);
-        self.register_peer(peer_info).await;
+        let missing_capabilities = self.required_capabilities.difference(response.capabilities);
+        if !missing_capabilities.is_empty() {
+            warn!(
+                sender_addr = %sender_addr,
+                offered_capabilities = %response.capabilities,
+                missing_capabilities = %missing_capabilities,
+                "Handshake response lacks required capabilities"
+            );
+            return Err(SolanaNodeError::MissingCapabilities {
+                missing: missing_capabilities,
+            });
+        }
 
-        Ok(())
+        // Register the peer from a successful handshake once it is verified,
+        // at the address it actually answered from
+        let mut peer_info = GossipNodeInfo::from_handshake_response(response);
+        peer_info.gossip_addr = sender_addr;
+        self.register_or_challenge(sender_addr, peer_info, None)
+            .await
     }
 
-    /// Send a handshake response to a peer
+    /// Sign a handshake response with this node's identity and send it to a peer
     #[tracing::instrument(skip(self, response))]
     async fn send_handshake_response(
         &self,
         peer_addr: SocketAddr,
-        response: HandshakeResponse,
+        mut response: HandshakeResponse,
     ) -> Result<(), SolanaNodeError> {
+        response.sign(&self.identity);
+
         let serialized_response = borsh::to_vec(&response)
             .map_err(|e| SolanaNodeError::SerializationFailed(e.to_string()))?;
 
+        self.check_packet_size("handshake response", &serialized_response)?;
+
         let result = timeout(
-            DEFAULT_NETWORK_TIMEOUT,
+            self.network_timeout,
             self.socket.send_to(&serialized_response, peer_addr),
         )
         .await;
@@ -354,9 +1712,122 @@ impl GossipNode {
                 Ok(())
             }
             Ok(Err(e)) => Err(SolanaNodeError::NetworkError(e)),
-            Err(_) => Err(SolanaNodeError::TimeoutError),
+            Err(_) => Err(SolanaNodeError::SendTimeout { peer_addr }),
         }
     }
 }
 
 // Re-export for convenience
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending_peer(port: u16, queued_at: Instant) -> PendingPeer {
+        PendingPeer {
+            peer_info: GossipNodeInfo::test_peer(addr(port), ConnectionStatus::Connecting),
+            response: None,
+            queued_at,
+        }
+    }
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([10, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn test_pending_peers_evict_oldest_when_full() {
+        let mut pending = PendingPeers::default();
+        let now = Instant::now();
+
+        for port in 1..=3 {
+            let queued_at = now + Duration::from_secs(port.into());
+            assert_eq!(
+                pending.insert(addr(port), pending_peer(port, queued_at), 3),
+                None
+            );
+        }
+
+        // Answered handshakes leave the queue without blocking eviction
+        assert!(pending.remove(&addr(1)).is_some());
+        assert_eq!(pending.insert(addr(4), pending_peer(4, now), 3), None);
+
+        // Asking again does not make a peer younger
+        let later = now + Duration::from_secs(60);
+        assert_eq!(pending.insert(addr(2), pending_peer(2, later), 3), None);
+
+        assert_eq!(
+            pending.insert(addr(5), pending_peer(5, later), 3),
+            Some(addr(2))
+        );
+        assert_eq!(pending.len(), 3);
+        assert!(pending.get(&addr(5)).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_sends_respect_packet_size_limit() {
+        let node = GossipNode::builder()
+            .bind_addr("127.0.0.1:0")
+            .max_packet_size(64)
+            .build()
+            .await
+            .unwrap();
+        let peer_addr = node.local_addr().unwrap();
+        let request = HandshakeRequest::new_local(Pubkey::new_unique());
+
+        let ping = crate::solana::codec::Ping::new_rand(&Keypair::new());
+        let result = node
+            .send_gossip_message(peer_addr, &Protocol::PingMessage(ping))
+            .await;
+        assert!(matches!(
+            result,
+            Err(SolanaNodeError::CodecError(CodecError::PacketTooLarge {
+                max: 64,
+                ..
+            }))
+        ));
+
+        let response = node
+            .failure_response(&request, RejectReason::Unspecified, String::new())
+            .unwrap();
+        let result = node.send_handshake_response(peer_addr, response).await;
+        assert!(matches!(
+            result,
+            Err(SolanaNodeError::CodecError(CodecError::PacketTooLarge {
+                max: 64,
+                ..
+            }))
+        ));
+
+        let result = node.send_handshake_request(peer_addr, request).await;
+        assert!(matches!(
+            result,
+            Err(SolanaNodeError::CodecError(CodecError::PacketTooLarge {
+                max: 64,
+                ..
+            }))
+        ));
+        assert!(!result.unwrap_err().is_retryable());
+    }
+
+    #[test]
+    fn test_pending_peers_prune_unanswered_handshakes() {
+        let mut pending = PendingPeers::default();
+        let now = Instant::now();
+        let ttl = Duration::from_secs(10);
+
+        pending.insert(addr(1), pending_peer(1, now), 16);
+        pending.insert(addr(2), pending_peer(2, now + Duration::from_secs(5)), 16);
+        pending.remove(&addr(1));
+        pending.insert(addr(1), pending_peer(1, now + Duration::from_secs(8)), 16);
+
+        assert_eq!(pending.prune(now + Duration::from_secs(9), ttl), 0);
+        assert_eq!(pending.prune(now + Duration::from_secs(15), ttl), 1);
+        assert!(pending.get(&addr(2)).is_none());
+        assert!(pending.get(&addr(1)).is_some());
+
+        assert_eq!(pending.prune(now + Duration::from_secs(18), ttl), 1);
+        assert_eq!(pending.len(), 0);
+        assert!(pending.order.is_empty());
+    }
+}
@@ -3,31 +3,195 @@
 //! This module provides high-level client functionality for performing
 //! handshake protocols with remote Solana nodes.
 
-use crate::solana::domain::{GossipNodeInfo, HandshakeRequest, HandshakeResponse, SolanaNodeError};
-use borsh::BorshDeserialize;
+use crate::log_with_context;
+use crate::metrics::Metrics;
+use crate::solana::access_policy::AccessControl;
+use crate::solana::codec::{
+    ClientVersion, ContactInfo, CrdsData, CrdsFilter, CrdsValue, Ping, Pong, Protocol,
+};
+use crate::solana::domain::network;
+use crate::solana::domain::{
+    AdvertisedPeer, Capability, CapabilitySet, ConnectionStatus, GossipNodeInfo, HandshakeMode,
+    HandshakeOutcome, HandshakeRequest, HandshakeResponse, PeerDetails, SolanaNodeError,
+    VersionRange, DEFAULT_CAPABILITIES, DEFAULT_MAX_PACKET_SIZE,
+};
+use crate::solana::retry_policy::RetryPolicy;
+use crate::telemetry::generate_correlation_id;
+use futures::stream::{self, StreamExt};
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use tokio::net::UdpSocket;
-use tokio::time::{timeout, Duration};
-use tracing::{debug, error, info, instrument, warn};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::{timeout, Duration, Instant};
+use tracing::{debug, error, field, info, info_span, instrument, warn, Instrument};
 
 /// Default timeout for handshake operations (15 seconds)
 const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(15);
 
-/// Maximum number of handshake retry attempts
-const MAX_HANDSHAKE_RETRIES: u8 = 3;
+/// Address a client binds to unless given another one or a bound socket
+const DEFAULT_BIND_ADDR: &str = "0.0.0.0:0";
+
+/// Default number of handshakes run at once by `perform_batch_handshakes`
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 64;
+
+/// Gossip messages buffered per in-flight gossip handshake before packets are dropped
+const GOSSIP_SESSION_QUEUE_DEPTH: usize = 64;
+
+/// Longest pause of the receive task after repeated transient socket errors
+const MAX_RECEIVE_ERROR_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Software version advertised in our gossip contact info
+const GOSSIP_CLIENT_VERSION: ClientVersion = ClientVersion {
+    major: 1,
+    minor: 18,
+    patch: 0,
+    commit: None,
+    feature_set: 0,
+    client: 0,
+};
 
 /// Result type for handshake operations
 pub type HandshakeResult<T> = Result<T, SolanaNodeError>;
 
-/// High-level client for performing Solana node handshakes
-#[derive(Debug)]
-pub struct HandshakeClient {
+/// Requests waiting for packets from the background receive task
+#[derive(Default)]
+struct PendingRequests {
+    /// Legacy handshakes awaiting a response, keyed by peer address and request nonce
+    responses: HashMap<(SocketAddr, u64), oneshot::Sender<HandshakeResult<HandshakeResponse>>>,
+
+    /// Gossip handshakes in progress, receiving every message their peer sends
+    gossip_sessions: HashMap<SocketAddr, mpsc::Sender<Protocol>>,
+
+    /// Why the receive task stopped, after which no reply can arrive
+    receiver_stopped: Option<String>,
+}
+
+impl PendingRequests {
+    /// Whether a handshake with `peer_addr` is in flight
+    fn expects(&self, peer_addr: &SocketAddr) -> bool {
+        self.gossip_sessions.contains_key(peer_addr)
+            || self.responses.keys().any(|(addr, _)| addr == peer_addr)
+    }
+
+    /// Error for a handshake that can no longer get a reply, if the receive task stopped
+    fn stopped_error(&self) -> Option<SolanaNodeError> {
+        self.receiver_stopped
+            .as_ref()
+            .map(|reason| SolanaNodeError::ReceiverStopped {
+                reason: reason.clone(),
+            })
+    }
+}
+
+/// State shared between the client and its background receive task
+struct SharedState {
     /// UDP socket for communication
     socket: UdpSocket,
 
-    /// This client's node public key
-    node_pubkey: Pubkey,
+    /// This client's identity keypair, used to sign gossip messages
+    identity: Keypair,
+
+    /// Outstanding requests to route incoming packets to
+    pending: Mutex<PendingRequests>,
+
+    /// Packets dropped because they did not come from, or answer, an outstanding request
+    discarded_packets: AtomicU64,
+
+    /// Largest packet received in full; longer ones are truncated
+    max_packet_size: usize,
+}
+
+impl SharedState {
+    /// Count a packet that does not belong to any outstanding request
+    fn discard_packet(&self) {
+        self.discarded_packets.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn pending(&self) -> std::sync::MutexGuard<'_, PendingRequests> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Error for a handshake whose reply channel closed because the receive task stopped
+    fn receiver_stopped(&self) -> SolanaNodeError {
+        self.pending()
+            .stopped_error()
+            .unwrap_or_else(|| SolanaNodeError::ReceiverStopped {
+                reason: "receive task ended".to_string(),
+            })
+    }
+
+    /// Fail the legacy handshakes awaiting a response from `peer_addr` with a decode error
+    ///
+    /// Returns whether there were any.
+    fn fail_responses(&self, peer_addr: SocketAddr, offset: usize, reason: &str) -> bool {
+        let mut pending = self.pending();
+        let nonces: Vec<u64> = pending
+            .responses
+            .keys()
+            .filter(|(addr, _)| *addr == peer_addr)
+            .map(|(_, nonce)| *nonce)
+            .collect();
+
+        for nonce in &nonces {
+            if let Some(response_tx) = pending.responses.remove(&(peer_addr, *nonce)) {
+                let _ = response_tx.send(Err(SolanaNodeError::DecodeError {
+                    offset,
+                    reason: reason.to_string(),
+                }));
+            }
+        }
+        !nonces.is_empty()
+    }
+
+    /// Stop routing replies after a fatal socket error
+    ///
+    /// Outstanding handshakes see their reply channel close and fail at once
+    /// instead of waiting for their timeout.
+    fn stop_receiving(&self, reason: String) {
+        let mut pending = self.pending();
+        pending.receiver_stopped = Some(reason);
+        pending.responses.clear();
+        pending.gossip_sessions.clear();
+    }
+}
+
+/// Removes a request's routing entry once the handshake attempt finishes or is abandoned
+struct PendingGuard<'a> {
+    shared: &'a SharedState,
+    peer_addr: SocketAddr,
+    nonce: Option<u64>,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        let mut pending = self.shared.pending();
+        match self.nonce {
+            Some(nonce) => {
+                pending.responses.remove(&(self.peer_addr, nonce));
+            }
+            None => {
+                pending.gossip_sessions.remove(&self.peer_addr);
+            }
+        }
+    }
+}
+
+/// High-level client for performing Solana node handshakes
+///
+/// A single UDP socket is shared by all handshakes. A background task owns the
+/// receiving side and routes each packet to the handshake it belongs to, so
+/// any number of handshakes can be in flight at once.
+pub struct HandshakeClient {
+    /// Socket, identity and request routing shared with the receive task
+    shared: Arc<SharedState>,
+
+    /// Background task demultiplexing incoming packets
+    receiver_task: JoinHandle<()>,
 
     /// Network ID to operate on
     network_id: String,
@@ -35,55 +199,345 @@ pub struct HandshakeClient {
     /// Timeout duration for operations
     operation_timeout: Duration,
 
-    /// Maximum retry attempts
-    max_retries: u8,
+    /// When and how soon failed handshakes are attempted again
+    retry_policy: RetryPolicy,
+
+    /// Wire protocol used for handshakes
+    handshake_mode: HandshakeMode,
+
+    /// Shred version advertised in gossip pull requests (0 = unknown)
+    shred_version: u16,
+
+    /// Gossip address advertised in pull requests, instead of one derived per peer
+    advertise_addr: Option<SocketAddr>,
+
+    /// Maximum number of handshakes run at once in a batch
+    concurrency: usize,
+
+    /// Handshake protocol versions offered to peers
+    supported_versions: VersionRange,
+
+    /// Capabilities offered to peers
+    capabilities: CapabilitySet,
+
+    /// Capabilities a peer must agree to for the handshake to succeed
+    required_capabilities: CapabilitySet,
+
+    /// Pubkeys and address ranges this client may handshake with
+    access: AccessControl,
+
+    /// Handshake counters and histograms
+    metrics: Metrics,
 }
 
-impl HandshakeClient {
-    /// Create a new handshake client
-    #[instrument(skip(bind_addr))]
-    pub async fn new(
-        bind_addr: &str,
-        node_pubkey: Pubkey,
-        network_id: String,
-    ) -> HandshakeResult<Self> {
-        info!(
-            bind_addr = %bind_addr,
-            node_pubkey = %node_pubkey,
-            network_id = %network_id,
-            "Creating handshake client"
-        );
+// Implemented by hand so the identity's secret key never ends up in logs
+impl std::fmt::Debug for HandshakeClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HandshakeClient")
+            .field("socket", &self.shared.socket)
+            .field("node_pubkey", &self.shared.identity.pubkey())
+            .field("network_id", &self.network_id)
+            .field("operation_timeout", &self.operation_timeout)
+            .field("retry_policy", &self.retry_policy)
+            .field("handshake_mode", &self.handshake_mode)
+            .field("shred_version", &self.shred_version)
+            .field("advertise_addr", &self.advertise_addr)
+            .field("concurrency", &self.concurrency)
+            .field("supported_versions", &self.supported_versions)
+            .field("capabilities", &self.capabilities)
+            .field("required_capabilities", &self.required_capabilities)
+            .field("access", &self.access)
+            .field("discarded_packets", &self.discarded_packets())
+            .finish()
+    }
+}
+
+impl Drop for HandshakeClient {
+    fn drop(&mut self) {
+        self.receiver_task.abort();
+    }
+}
 
-        let socket = UdpSocket::bind(bind_addr).await.map_err(|e| {
-            error!(error = %e, bind_addr = %bind_addr, "Failed to bind handshake client socket");
-            SolanaNodeError::NetworkError(e)
-        })?;
+/// Where a handshake client gets its socket from
+enum SocketSource {
+    /// Bind a new socket to this address
+    Bind(String),
+    /// Use a socket bound by the caller
+    Bound(UdpSocket),
+}
+
+/// Builder for a `HandshakeClient` with every setting in one place
+///
+/// Settings left unset keep the defaults of `HandshakeClient::new`. Without
+/// an identity the client uses a new ephemeral keypair. Handshake outcomes,
+/// latencies and retries are exported through `metrics`; spans go to the
+/// installed `tracing` subscriber, such as the one set up by `setup_telemetry`.
+pub struct HandshakeClientBuilder {
+    socket: SocketSource,
+    identity: Option<Keypair>,
+    network_id: String,
+    operation_timeout: Duration,
+    retry_policy: RetryPolicy,
+    max_packet_size: usize,
+    handshake_mode: HandshakeMode,
+    shred_version: u16,
+    advertise_addr: Option<SocketAddr>,
+    concurrency: usize,
+    supported_versions: VersionRange,
+    capabilities: CapabilitySet,
+    required_capabilities: CapabilitySet,
+    access: AccessControl,
+    metrics: Metrics,
+}
+
+impl Default for HandshakeClientBuilder {
+    fn default() -> Self {
+        Self {
+            socket: SocketSource::Bind(DEFAULT_BIND_ADDR.to_string()),
+            identity: None,
+            network_id: network::LOCALNET.to_string(),
+            operation_timeout: HANDSHAKE_TIMEOUT,
+            retry_policy: RetryPolicy::default(),
+            max_packet_size: DEFAULT_MAX_PACKET_SIZE,
+            handshake_mode: HandshakeMode::default(),
+            shred_version: 0,
+            advertise_addr: None,
+            concurrency: DEFAULT_BATCH_CONCURRENCY,
+            supported_versions: VersionRange::default(),
+            capabilities: DEFAULT_CAPABILITIES,
+            required_capabilities: CapabilitySet::EMPTY,
+            access: AccessControl::default(),
+            metrics: Metrics::default(),
+        }
+    }
+}
+
+impl std::fmt::Debug for HandshakeClientBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let socket = match &self.socket {
+            SocketSource::Bind(bind_addr) => bind_addr.clone(),
+            SocketSource::Bound(socket) => format!("{:?}", socket.local_addr()),
+        };
+        f.debug_struct("HandshakeClientBuilder")
+            .field("socket", &socket)
+            .field(
+                "node_pubkey",
+                &self.identity.as_ref().map(|identity| identity.pubkey()),
+            )
+            .field("network_id", &self.network_id)
+            .field("operation_timeout", &self.operation_timeout)
+            .field("retry_policy", &self.retry_policy)
+            .field("max_packet_size", &self.max_packet_size)
+            .field("handshake_mode", &self.handshake_mode)
+            .finish_non_exhaustive()
+    }
+}
+
+impl HandshakeClientBuilder {
+    /// Bind the client's socket to `bind_addr` (default: "0.0.0.0:0")
+    pub fn bind_addr(mut self, bind_addr: impl Into<String>) -> Self {
+        self.socket = SocketSource::Bind(bind_addr.into());
+        self
+    }
+
+    /// Use a socket the caller has already bound instead of binding one
+    pub fn socket(mut self, socket: UdpSocket) -> Self {
+        self.socket = SocketSource::Bound(socket);
+        self
+    }
+
+    /// Sign requests and gossip messages with `identity`
+    pub fn identity(mut self, identity: Keypair) -> Self {
+        self.identity = Some(identity);
+        self
+    }
+
+    /// Handshake with peers on `network_id` (default: localnet)
+    pub fn network_id(mut self, network_id: impl Into<String>) -> Self {
+        self.network_id = network_id.into();
+        self
+    }
 
+    /// Give up on sending a packet or waiting for a reply after `timeout`
+    pub fn operation_timeout(mut self, timeout: Duration) -> Self {
+        self.operation_timeout = timeout;
+        self
+    }
+
+    /// Retry failed handshakes according to `retry_policy`
+    pub fn retry_policy(mut self, retry_policy: impl Into<RetryPolicy>) -> Self {
+        self.retry_policy = retry_policy.into();
+        self
+    }
+
+    /// Receive packets of up to `max_packet_size` bytes in full
+    pub fn max_packet_size(mut self, max_packet_size: usize) -> Self {
+        self.max_packet_size = max_packet_size.max(1);
+        self
+    }
+
+    /// Select the wire protocol used by `perform_handshake`
+    pub fn handshake_mode(mut self, handshake_mode: HandshakeMode) -> Self {
+        self.handshake_mode = handshake_mode;
+        self
+    }
+
+    /// Set the shred version advertised to validators in gossip mode
+    ///
+    /// Validators ignore pull requests from nodes whose shred version differs
+    /// from their own, so this must match the cluster for the pull exchange
+    /// to complete.
+    pub fn shred_version(mut self, shred_version: u16) -> Self {
+        self.shred_version = shred_version;
+        self
+    }
+
+    /// Advertise `advertise_addr` as our gossip address in pull requests
+    ///
+    /// By default the address of the interface used to reach each peer is
+    /// advertised, with the port of the client's socket.
+    pub fn advertise_addr(mut self, advertise_addr: SocketAddr) -> Self {
+        self.advertise_addr = Some(advertise_addr);
+        self
+    }
+
+    /// Set how many handshakes `perform_batch_handshakes` runs at once
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Restrict the handshake protocol versions offered to peers
+    pub fn supported_versions(mut self, supported_versions: VersionRange) -> Self {
+        self.supported_versions = supported_versions;
+        self
+    }
+
+    /// Set the capabilities offered to peers
+    pub fn capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Fail handshakes with peers that do not agree to all of `capabilities`
+    ///
+    /// Required capabilities are always offered as well, whatever is passed
+    /// to `capabilities`.
+    pub fn required_capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.required_capabilities = capabilities;
+        self
+    }
+
+    /// Only handshake with peers allowed by `access`
+    ///
+    /// The handle can be kept to replace the policy while the client runs.
+    pub fn access_control(mut self, access: AccessControl) -> Self {
+        self.access = access;
+        self
+    }
+
+    /// Record handshake counts, latencies and retries in `metrics`
+    pub fn metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Bind the socket, if not given one, and start the client's receive task
+    #[instrument(skip(self), fields(network_id = %self.network_id))]
+    pub async fn build(self) -> HandshakeResult<HandshakeClient> {
+        let socket = match self.socket {
+            SocketSource::Bind(bind_addr) => {
+                UdpSocket::bind(&bind_addr).await.map_err(|e| {
+                    error!(error = %e, bind_addr = %bind_addr, "Failed to bind handshake client socket");
+                    SolanaNodeError::NetworkError(e)
+                })?
+            }
+            SocketSource::Bound(socket) => socket,
+        };
         let local_addr = socket.local_addr().map_err(SolanaNodeError::NetworkError)?;
+        let identity = self.identity.unwrap_or_else(Keypair::new);
 
-        info!(local_addr = %local_addr, "Handshake client socket bound");
+        info!(
+            local_addr = %local_addr,
+            node_pubkey = %identity.pubkey(),
+            network_id = %self.network_id,
+            "Handshake client socket bound"
+        );
 
-        Ok(Self {
+        let shared = Arc::new(SharedState {
             socket,
-            node_pubkey,
-            network_id,
-            operation_timeout: HANDSHAKE_TIMEOUT,
-            max_retries: MAX_HANDSHAKE_RETRIES,
+            identity,
+            pending: Mutex::new(PendingRequests::default()),
+            discarded_packets: AtomicU64::new(0),
+            max_packet_size: self.max_packet_size,
+        });
+        let receiver_task = tokio::spawn(run_receiver(Arc::clone(&shared)));
+
+        Ok(HandshakeClient {
+            shared,
+            receiver_task,
+            network_id: self.network_id,
+            operation_timeout: self.operation_timeout,
+            retry_policy: self.retry_policy,
+            handshake_mode: self.handshake_mode,
+            shred_version: self.shred_version,
+            advertise_addr: self.advertise_addr,
+            concurrency: self.concurrency,
+            supported_versions: self.supported_versions,
+            capabilities: self.capabilities.union(self.required_capabilities),
+            required_capabilities: self.required_capabilities,
+            access: self.access,
+            metrics: self.metrics,
         })
     }
+}
+
+impl HandshakeClient {
+    /// Start building a client with default settings
+    pub fn builder() -> HandshakeClientBuilder {
+        HandshakeClientBuilder::default()
+    }
 
-    /// Create a handshake client with custom timeout and retry settings
-    pub async fn with_config(
+    /// Create a new handshake client
+    pub async fn new(
         bind_addr: &str,
-        node_pubkey: Pubkey,
+        identity: Keypair,
         network_id: String,
-        operation_timeout: Duration,
-        max_retries: u8,
     ) -> HandshakeResult<Self> {
-        let mut client = Self::new(bind_addr, node_pubkey, network_id).await?;
-        client.operation_timeout = operation_timeout;
-        client.max_retries = max_retries;
-        Ok(client)
+        Self::builder()
+            .bind_addr(bind_addr)
+            .identity(identity)
+            .network_id(network_id)
+            .build()
+            .await
+    }
+
+    /// This client's public key
+    pub fn node_pubkey(&self) -> Pubkey {
+        self.shared.identity.pubkey()
+    }
+
+    /// Wire protocol used for handshakes
+    pub fn handshake_mode(&self) -> HandshakeMode {
+        self.handshake_mode
+    }
+
+    /// Number of packets discarded because of a mismatched sender or nonce
+    pub fn discarded_packets(&self) -> u64 {
+        self.shared.discarded_packets.load(Ordering::Relaxed)
+    }
+
+    /// Local address of the client's socket
+    pub fn local_addr(&self) -> HandshakeResult<SocketAddr> {
+        self.shared
+            .socket
+            .local_addr()
+            .map_err(SolanaNodeError::NetworkError)
+    }
+
+    /// Maximum number of handshakes run at once in a batch
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
     }
 
     /// Perform a handshake with a remote Solana node
@@ -92,27 +546,94 @@ impl HandshakeClient {
         &self,
         peer_addr: SocketAddr,
     ) -> HandshakeResult<GossipNodeInfo> {
-        info!(peer_addr = %peer_addr, "Starting handshake");
+        self.handshake_with_retries(peer_addr)
+            .await
+            .result
+            .map(|details| details.peer_info)
+    }
+
+    /// Perform a handshake and return everything the peer told us
+    ///
+    /// In gossip mode the advertised peers are the nodes whose signed contact
+    /// info arrived in the peer's pull response. Legacy handshakes carry no
+    /// contact info, so in legacy mode that list is always empty and the shred
+    /// version unknown.
+    #[instrument(skip(self))]
+    pub async fn perform_discovery_handshake(
+        &self,
+        peer_addr: SocketAddr,
+    ) -> HandshakeResult<PeerDetails> {
+        self.handshake_with_retries(peer_addr).await.result
+    }
+
+    /// Attempt a handshake with `peer_addr`, retrying as the retry policy allows
+    ///
+    /// Every attempt runs in its own `handshake_attempt` span. All attempts
+    /// share one correlation id, which legacy handshake requests carry to the
+    /// server so its spans for the handshake can be joined with ours.
+    async fn handshake_with_retries(&self, peer_addr: SocketAddr) -> HandshakeOutcome {
+        let correlation_id = generate_correlation_id();
+        log_with_context!(
+            info,
+            correlation_id = correlation_id.as_str(),
+            peer_addr = %peer_addr,
+            "Starting handshake"
+        );
+        self.metrics.handshake_started();
 
-        let mut last_error = None;
+        let policy = &self.retry_policy;
+        let started = Instant::now();
+        let mut attempt = 0;
+        let mut previous_delay = policy.base_delay;
 
-        for attempt in 1..=self.max_retries {
+        let error = loop {
+            attempt += 1;
             debug!(
                 peer_addr = %peer_addr,
                 attempt = attempt,
-                max_retries = self.max_retries,
+                max_retries = policy.max_attempts,
                 "Handshake attempt"
             );
 
-            match self.attempt_handshake(peer_addr).await {
-                Ok(peer_info) => {
+            let span = info_span!(
+                "handshake_attempt",
+                peer_addr = %peer_addr,
+                correlation_id = %correlation_id,
+                handshake_mode = %self.handshake_mode,
+                attempt = attempt,
+                max_retries = policy.max_attempts,
+                retry = attempt > 1,
+                outcome = field::Empty,
+                error = field::Empty,
+            );
+            let result = self
+                .attempt_handshake(peer_addr, &correlation_id)
+                .instrument(span.clone())
+                .await;
+            match &result {
+                Ok(_) => span.record("outcome", "success"),
+                Err(e) => span
+                    .record("outcome", "failure")
+                    .record("error", field::display(e)),
+            };
+
+            match result {
+                Ok(details) => {
                     info!(
                         peer_addr = %peer_addr,
-                        peer_pubkey = %peer_info.peer_pubkey,
+                        peer_pubkey = %details.peer_info.peer_pubkey,
                         attempt = attempt,
+                        rtt_ms = details.rtt.as_millis(),
+                        advertised_peers = details.advertised.len(),
                         "Handshake completed successfully"
                     );
-                    return Ok(peer_info);
+                    self.metrics
+                        .handshake_succeeded(started.elapsed(), attempt - 1);
+                    return HandshakeOutcome {
+                        peer_addr,
+                        attempts: attempt,
+                        result: Ok(details),
+                    };
                 }
                 Err(e) => {
                     warn!(
@@ -121,60 +642,422 @@ impl HandshakeClient {
                         attempt = attempt,
                         "Handshake attempt failed"
                     );
-                    last_error = Some(e);
-
-                    // Don't retry on certain error types
-                    if matches!(
-                        last_error,
-                        Some(SolanaNodeError::ProtocolVersionMismatch { .. })
-                    ) {
-                        break;
+
+                    // Don't retry failures another attempt cannot fix, and
+                    // make at least one attempt even if the policy allows none
+                    if !policy.should_retry(&e) || attempt >= policy.max_attempts {
+                        break e;
                     }
 
-                    if attempt < self.max_retries {
-                        // Exponential backoff: 1s, 2s, 4s, etc.
-                        let delay = Duration::from_secs(1 << (attempt - 1));
-                        debug!(delay_secs = delay.as_secs(), "Waiting before retry");
-                        tokio::time::sleep(delay).await;
+                    let delay =
+                        policy.delay(attempt.into(), previous_delay, &mut rand::thread_rng());
+                    if !policy.within_deadline(started.elapsed() + delay) {
+                        debug!(
+                            elapsed_ms = started.elapsed().as_millis(),
+                            "Retry deadline reached"
+                        );
+                        break e;
                     }
+                    debug!(delay_ms = delay.as_millis(), "Waiting before retry");
+                    previous_delay = delay;
+                    tokio::time::sleep(delay).await;
                 }
             }
-        }
+        };
 
         error!(
             peer_addr = %peer_addr,
-            max_retries = self.max_retries,
+            attempts = attempt,
             "Handshake failed after all retry attempts"
         );
 
-        Err(last_error.unwrap_or(SolanaNodeError::HandshakeError {
-            message: "Handshake failed after maximum retries".to_string(),
-        }))
+        self.metrics.handshake_failed(&error, attempt - 1);
+        HandshakeOutcome {
+            peer_addr,
+            attempts: attempt,
+            result: Err(error),
+        }
     }
 
-    /// Attempt a single handshake with a peer
-    #[instrument(skip(self))]
-    async fn attempt_handshake(&self, peer_addr: SocketAddr) -> HandshakeResult<GossipNodeInfo> {
+    /// Attempt a single handshake with a peer using the configured wire protocol
+    async fn attempt_handshake(
+        &self,
+        peer_addr: SocketAddr,
+        correlation_id: &str,
+    ) -> HandshakeResult<PeerDetails> {
+        if let Err(denied) = self.access.check_addr(peer_addr.ip()) {
+            warn!(peer_addr = %peer_addr, reason = %denied, "Peer denied by access policy");
+            return Err(SolanaNodeError::PeerVerificationFailed { peer_addr });
+        }
+
+        let details = match self.handshake_mode {
+            HandshakeMode::Legacy => {
+                self.attempt_legacy_handshake(peer_addr, correlation_id)
+                    .await?
+            }
+            HandshakeMode::Gossip => self.attempt_gossip_handshake(peer_addr).await?,
+        };
+        let peer_info = &details.peer_info;
+
+        if let Err(denied) = self.access.check_pubkey(&peer_info.peer_pubkey) {
+            warn!(
+                peer_addr = %peer_addr,
+                peer_pubkey = %peer_info.peer_pubkey,
+                reason = %denied,
+                "Peer denied by access policy"
+            );
+            return Err(SolanaNodeError::PeerVerificationFailed { peer_addr });
+        }
+
+        let missing = self
+            .required_capabilities
+            .difference(peer_info.capabilities);
+        if !missing.is_empty() {
+            warn!(
+                peer_addr = %peer_addr,
+                agreed_capabilities = %peer_info.capabilities,
+                missing_capabilities = %missing,
+                "Peer did not agree to required capabilities"
+            );
+            return Err(SolanaNodeError::MissingCapabilities { missing });
+        }
+
+        Ok(details)
+    }
+
+    /// Attempt a single Borsh request/response handshake with a peer
+    #[instrument(skip(self, correlation_id))]
+    async fn attempt_legacy_handshake(
+        &self,
+        peer_addr: SocketAddr,
+        correlation_id: &str,
+    ) -> HandshakeResult<PeerDetails> {
         // Create a handshake request
-        let request = match self.network_id.as_str() {
-            "mainnet-beta" => HandshakeRequest::new_mainnet(self.node_pubkey),
-            "testnet" => HandshakeRequest::new_testnet(self.node_pubkey),
-            _ => HandshakeRequest::new_local(self.node_pubkey),
+        let node_pubkey = self.node_pubkey();
+        let mut request = match self.network_id.as_str() {
+            "mainnet-beta" => HandshakeRequest::new_mainnet(node_pubkey),
+            "testnet" => HandshakeRequest::new_testnet(node_pubkey),
+            _ => HandshakeRequest::new_local(node_pubkey),
+        };
+        request.supported_versions = self.supported_versions;
+        request.capabilities = self.capabilities;
+        request.correlation_id = correlation_id.to_string();
+        request.sign(&self.shared.identity);
+
+        // Register for the response before sending so it cannot be missed
+        let (response_tx, response_rx) = oneshot::channel();
+        {
+            let mut pending = self.shared.pending();
+            if let Some(stopped) = pending.stopped_error() {
+                return Err(stopped);
+            }
+            pending
+                .responses
+                .insert((peer_addr, request.nonce), response_tx);
+        }
+        let _guard = PendingGuard {
+            shared: &self.shared,
+            peer_addr,
+            nonce: Some(request.nonce),
         };
 
         // Send handshake request
+        let sent_at = Instant::now();
         self.send_handshake_request(peer_addr, &request).await?;
 
-        // Wait for response
-        let response = self.receive_handshake_response().await?;
+        // Wait for the response to this request
+        let response = self
+            .receive_handshake_response(peer_addr, response_rx)
+            .await?;
+        let rtt = sent_at.elapsed();
+
+        // Reject responses not signed by the key they claim to come from
+        if !response.verify_signature() {
+            warn!(
+                peer_addr = %peer_addr,
+                peer_pubkey = %response.peer_pubkey,
+                "Handshake response signature verification failed"
+            );
+            return Err(SolanaNodeError::SignatureInvalid { peer_addr });
+        }
 
         // Verify response
-        response.verify(&self.network_id)?;
+        response.verify(&self.network_id, &self.supported_versions)?;
+
+        // Convert to peer info, agreeing only to capabilities we actually offered
+        let mut peer_info = GossipNodeInfo::from_handshake_response(response);
+        peer_info.capabilities = peer_info.capabilities.intersection(self.capabilities);
+
+        Ok(PeerDetails {
+            peer_info,
+            shred_version: None,
+            rtt,
+            advertised: Vec::new(),
+        })
+    }
+
+    /// Attempt a single gossip handshake with a validator
+    ///
+    /// Sends a ping and a pull request carrying our signed contact info, answers
+    /// the validator's own ping challenge (re-sending the pull request, which the
+    /// validator drops until we have proven our address), and completes once a
+    /// valid pong and a pull response have both been received. Contact info of
+    /// other nodes in the pull response is returned alongside the peer's info,
+    /// and the time until the pong arrived as the round-trip time.
+    #[instrument(skip(self))]
+    async fn attempt_gossip_handshake(
+        &self,
+        peer_addr: SocketAddr,
+    ) -> HandshakeResult<PeerDetails> {
+        let identity = &self.shared.identity;
+        let ping = Ping::new_rand(identity);
+        let pull_request = self.build_pull_request(peer_addr)?;
+
+        let (messages_tx, mut messages) = mpsc::channel(GOSSIP_SESSION_QUEUE_DEPTH);
+        {
+            let mut pending = self.shared.pending();
+            if let Some(stopped) = pending.stopped_error() {
+                return Err(stopped);
+            }
+            // Replies are routed by address, so two sessions with one peer cannot be told apart
+            if pending.gossip_sessions.contains_key(&peer_addr) {
+                return Err(SolanaNodeError::HandshakeInProgress { peer_addr });
+            }
+            pending.gossip_sessions.insert(peer_addr, messages_tx);
+        }
+        let _guard = PendingGuard {
+            shared: &self.shared,
+            peer_addr,
+            nonce: None,
+        };
+
+        let ping_sent_at = Instant::now();
+        self.send_protocol_message(peer_addr, &Protocol::PingMessage(ping.clone()))
+            .await?;
+        self.send_protocol_message(peer_addr, &pull_request).await?;
+
+        let deadline = Instant::now() + self.operation_timeout;
+        let mut rtt = Duration::ZERO;
+        let mut peer_pubkey = None;
+        let mut pulled_values = None;
+
+        while peer_pubkey.is_none() || pulled_values.is_none() {
+            let message = match tokio::time::timeout_at(deadline, messages.recv()).await {
+                Ok(Some(message)) => message,
+                Ok(None) => return Err(self.shared.receiver_stopped()),
+                Err(_) => {
+                    warn!(
+                        peer_addr = %peer_addr,
+                        pong_received = peer_pubkey.is_some(),
+                        pull_response_received = pulled_values.is_some(),
+                        timeout_secs = self.operation_timeout.as_secs(),
+                        "Gossip handshake timed out"
+                    );
+                    return Err(SolanaNodeError::ReceiveTimeout { peer_addr });
+                }
+            };
+
+            match message {
+                // The receive task has already answered the ping, but the
+                // validator discarded our pull request while we were unverified
+                Protocol::PingMessage(_) => {
+                    self.send_protocol_message(peer_addr, &pull_request).await?;
+                }
+                Protocol::PongMessage(pong) => {
+                    if !pong.answers(&ping) {
+                        warn!(peer_addr = %peer_addr, "Received invalid pong");
+                        return Err(SolanaNodeError::PeerVerificationFailed { peer_addr });
+                    }
+                    rtt = ping_sent_at.elapsed();
+                    peer_pubkey = Some(pong.from);
+                }
+                Protocol::PullResponse(from, values) => {
+                    let verified: Vec<CrdsValue> =
+                        values.into_iter().filter(CrdsValue::verify).collect();
+                    debug!(
+                        from = %from,
+                        value_count = verified.len(),
+                        "Received pull response"
+                    );
+                    pulled_values.get_or_insert_with(Vec::new).extend(verified);
+                }
+                _ => {}
+            }
+        }
+
+        let peer_pubkey = peer_pubkey.expect("loop exits once the pong is received");
+        let mut pulled_values = pulled_values.unwrap_or_default();
+
+        // Validators split a pull response over several packets; keep the rest
+        // of the ones that have already arrived
+        while let Ok(message) = messages.try_recv() {
+            if let Protocol::PullResponse(_, values) = message {
+                pulled_values.extend(values.into_iter().filter(CrdsValue::verify));
+            }
+        }
+
+        let shred_version = advertised_contacts(&pulled_values)
+            .find(|contact| contact.pubkey == peer_pubkey)
+            .map(|contact| contact.shred_version);
+
+        Ok(PeerDetails {
+            peer_info: self.peer_info_from_gossip(peer_addr, peer_pubkey, &pulled_values),
+            shred_version,
+            rtt,
+            advertised: self.advertised_peers(peer_pubkey, &pulled_values),
+        })
+    }
+
+    /// Nodes other than `peer_pubkey` and ourselves with contact info in `values`
+    ///
+    /// Addresses nobody could reach are skipped, and each node is listed once.
+    fn advertised_peers(&self, peer_pubkey: Pubkey, values: &[CrdsValue]) -> Vec<AdvertisedPeer> {
+        let node_pubkey = self.node_pubkey();
+        let mut seen = HashSet::new();
+
+        advertised_contacts(values)
+            .filter(|contact| {
+                contact.pubkey != peer_pubkey
+                    && contact.pubkey != node_pubkey
+                    && !contact.gossip_addr.ip().is_unspecified()
+                    && contact.gossip_addr.port() != 0
+            })
+            .filter(|contact| seen.insert(contact.pubkey))
+            .collect()
+    }
+
+    /// Build a pull request to `peer_addr` advertising our contact info
+    fn build_pull_request(&self, peer_addr: SocketAddr) -> HandshakeResult<Protocol> {
+        let advertise_addr = self.advertise_addr_for(peer_addr)?;
+        if advertise_addr.ip().is_unspecified() || advertise_addr.port() == 0 {
+            return Err(SolanaNodeError::InvalidAdvertiseAddr {
+                addr: advertise_addr,
+                reason: "validators cannot reach an unspecified address".to_string(),
+            });
+        }
+
+        let contact_info = ContactInfo::new_gossip_only(
+            self.node_pubkey(),
+            advertise_addr,
+            self.shred_version,
+            GOSSIP_CLIENT_VERSION,
+        );
+        let value =
+            CrdsValue::new_signed(CrdsData::ContactInfo(contact_info), &self.shared.identity);
+
+        Ok(Protocol::PullRequest(CrdsFilter::new_empty(), value))
+    }
+
+    /// Gossip address to advertise to `peer_addr`
+    ///
+    /// A socket bound to every interface is advertised with the address of the
+    /// interface the system routes `peer_addr` through. Connecting a UDP socket
+    /// only looks the route up; nothing is sent.
+    fn advertise_addr_for(&self, peer_addr: SocketAddr) -> HandshakeResult<SocketAddr> {
+        if let Some(advertise_addr) = self.advertise_addr {
+            return Ok(advertise_addr);
+        }
+
+        let local_addr = self.local_addr()?;
+        if !local_addr.ip().is_unspecified() {
+            return Ok(local_addr);
+        }
+
+        let unspecified: SocketAddr = match peer_addr {
+            SocketAddr::V4(_) => (std::net::Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (std::net::Ipv6Addr::UNSPECIFIED, 0).into(),
+        };
+        let probe = std::net::UdpSocket::bind(unspecified)?;
+        probe.connect(peer_addr)?;
+        Ok(SocketAddr::new(probe.local_addr()?.ip(), local_addr.port()))
+    }
+
+    /// Summarize what a validator told us about itself during a gossip handshake
+    fn peer_info_from_gossip(
+        &self,
+        peer_addr: SocketAddr,
+        peer_pubkey: Pubkey,
+        values: &[CrdsValue],
+    ) -> GossipNodeInfo {
+        let mut protocol_version = None;
+        let mut gossip_addr = None;
+
+        for value in values.iter().filter(|value| value.pubkey() == peer_pubkey) {
+            match &value.data {
+                CrdsData::ContactInfo(info) => {
+                    protocol_version = Some(info.version.to_string());
+                    gossip_addr = gossip_addr.or(info.gossip());
+                }
+                CrdsData::LegacyContactInfo(info) => {
+                    gossip_addr = gossip_addr.or(Some(info.gossip));
+                }
+                CrdsData::Version(version) if protocol_version.is_none() => {
+                    protocol_version = Some(format!(
+                        "{}.{}.{}",
+                        version.major, version.minor, version.patch
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        debug!(
+            peer_addr = %peer_addr,
+            advertised_gossip_addr = ?gossip_addr,
+            "Gossip handshake completed"
+        );
 
-        // Convert to peer info
-        let peer_info = GossipNodeInfo::from_handshake_response(response);
+        GossipNodeInfo {
+            peer_pubkey,
+            // The address we actually reached is more useful than the advertised one,
+            // which is frequently a private address behind NAT
+            gossip_addr: peer_addr,
+            protocol_version: protocol_version.unwrap_or_else(|| "unknown".to_string()),
+            // Validators do not take part in handshake version negotiation
+            negotiated_version: None,
+            network_id: self.network_id.clone(),
+            capabilities: Capability::Gossip.into(),
+            last_seen: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            connection_status: ConnectionStatus::Connected,
+        }
+    }
 
-        Ok(peer_info)
+    /// Send a gossip protocol message to a peer
+    #[instrument(skip(self, message), fields(message = message.name()))]
+    async fn send_protocol_message(
+        &self,
+        peer_addr: SocketAddr,
+        message: &Protocol,
+    ) -> HandshakeResult<()> {
+        let packet = message.encode()?;
+
+        match timeout(
+            self.operation_timeout,
+            self.shared.socket.send_to(&packet, peer_addr),
+        )
+        .await
+        {
+            Ok(Ok(bytes_sent)) => {
+                debug!(
+                    peer_addr = %peer_addr,
+                    bytes_sent = bytes_sent,
+                    "Gossip message sent"
+                );
+                Ok(())
+            }
+            Ok(Err(e)) => {
+                error!(
+                    error = %e,
+                    peer_addr = %peer_addr,
+                    "Network error sending gossip message"
+                );
+                Err(SolanaNodeError::NetworkError(e))
+            }
+            Err(_) => Err(SolanaNodeError::SendTimeout { peer_addr }),
+        }
     }
 
     /// Send a handshake request to a peer
@@ -190,13 +1073,13 @@ impl HandshakeClient {
         debug!(
             peer_addr = %peer_addr,
             request_size = serialized_request.len(),
-            protocol_version = %request.protocol_version,
+            supported_versions = %request.supported_versions,
             "Sending handshake request"
         );
 
         let result = timeout(
             self.operation_timeout,
-            self.socket.send_to(&serialized_request, peer_addr),
+            self.shared.socket.send_to(&serialized_request, peer_addr),
         )
         .await;
 
@@ -223,59 +1106,38 @@ impl HandshakeClient {
                     timeout_secs = self.operation_timeout.as_secs(),
                     "Handshake request send timeout"
                 );
-                Err(SolanaNodeError::TimeoutError)
+                Err(SolanaNodeError::SendTimeout { peer_addr })
             }
         }
     }
 
-    /// Receive a handshake response from any peer
-    #[instrument(skip(self))]
-    async fn receive_handshake_response(&self) -> HandshakeResult<HandshakeResponse> {
-        let mut buffer = vec![0u8; 1280]; // Solana gossip packet size limit
-
+    /// Wait for the receive task to deliver the response to an outstanding request
+    #[instrument(skip(self, response_rx))]
+    async fn receive_handshake_response(
+        &self,
+        peer_addr: SocketAddr,
+        response_rx: oneshot::Receiver<HandshakeResult<HandshakeResponse>>,
+    ) -> HandshakeResult<HandshakeResponse> {
         debug!("Waiting for handshake response");
 
-        let result = timeout(self.operation_timeout, self.socket.recv_from(&mut buffer)).await;
-
-        match result {
-            Ok(Ok((bytes_received, sender_addr))) => {
-                debug!(
-                    sender_addr = %sender_addr,
-                    bytes_received = bytes_received,
-                    "Received handshake response"
-                );
-
-                let response = HandshakeResponse::try_from_slice(&buffer[..bytes_received])
-                    .map_err(|e| {
-                        warn!(
-                            error = %e,
-                            sender_addr = %sender_addr,
-                            "Failed to deserialize handshake response"
-                        );
-                        SolanaNodeError::InvalidPeerData {
-                            reason: format!("Failed to deserialize response: {}", e),
-                        }
-                    })?;
-
+        match timeout(self.operation_timeout, response_rx).await {
+            Ok(Ok(Ok(response))) => {
                 debug!(
-                    sender_addr = %sender_addr,
                     peer_pubkey = %response.peer_pubkey,
                     success = response.success,
-                    "Handshake response deserialized"
+                    "Handshake response received"
                 );
-
                 Ok(response)
             }
-            Ok(Err(e)) => {
-                error!(error = %e, "Network error receiving handshake response");
-                Err(SolanaNodeError::NetworkError(e))
-            }
+            Ok(Ok(Err(e))) => Err(e),
+            Ok(Err(_)) => Err(self.shared.receiver_stopped()),
             Err(_) => {
                 warn!(
                     timeout_secs = self.operation_timeout.as_secs(),
+                    discarded_packets = self.discarded_packets(),
                     "Handshake response receive timeout"
                 );
-                Err(SolanaNodeError::TimeoutError)
+                Err(SolanaNodeError::ReceiveTimeout { peer_addr })
             }
         }
     }
@@ -286,25 +1148,279 @@ impl HandshakeClient {
         &self,
         peer_addrs: Vec<SocketAddr>,
     ) -> Vec<(SocketAddr, HandshakeResult<GossipNodeInfo>)> {
-        info!(peer_count = peer_addrs.len(), "Starting batch handshakes");
+        self.perform_batch_handshake_outcomes(peer_addrs)
+            .await
+            .into_iter()
+            .map(|outcome| {
+                let peer_info = outcome.result.map(|details| details.peer_info);
+                (outcome.peer_addr, peer_info)
+            })
+            .collect()
+    }
 
-        // For now, just perform handshakes sequentially to avoid socket sharing issues
-        // In a production implementation; you'd want a proper connection pool
-        let mut results = Vec::new();
+    /// Perform handshakes with multiple peers concurrently, keeping the
+    /// round-trip time and number of attempts of each
+    ///
+    /// Outcomes are returned in the order of `peer_addrs`.
+    #[instrument(skip(self, peer_addrs))]
+    pub async fn perform_batch_handshake_outcomes(
+        &self,
+        peer_addrs: Vec<SocketAddr>,
+    ) -> Vec<HandshakeOutcome> {
+        info!(
+            peer_count = peer_addrs.len(),
+            concurrency = self.concurrency,
+            "Starting batch handshakes"
+        );
 
-        for peer_addr in peer_addrs {
-            let result = self.perform_handshake(peer_addr).await;
-            results.push((peer_addr, result));
-        }
+        // Responses are routed by the receive task, so handshakes only need to
+        // be bounded to keep the number of in-flight requests reasonable
+        let outcomes: Vec<_> = stream::iter(peer_addrs)
+            .map(|peer_addr| self.handshake_with_retries(peer_addr))
+            .buffered(self.concurrency)
+            .collect()
+            .await;
 
-        let successful_count = results.iter().filter(|(_, result)| result.is_ok()).count();
+        let successful_count = outcomes
+            .iter()
+            .filter(|outcome| outcome.result.is_ok())
+            .count();
 
         info!(
-            total_peers = results.len(),
+            total_peers = outcomes.len(),
             successful_handshakes = successful_count,
             "Batch handshakes completed"
         );
 
-        results
+        outcomes
+    }
+}
+
+/// Every contact info record in `values` with a gossip address
+fn advertised_contacts(values: &[CrdsValue]) -> impl Iterator<Item = AdvertisedPeer> + '_ {
+    values.iter().filter_map(|value| match &value.data {
+        CrdsData::ContactInfo(info) => info.gossip().map(|gossip_addr| AdvertisedPeer {
+            pubkey: info.pubkey,
+            gossip_addr,
+            shred_version: info.shred_version,
+            version: Some(info.version.to_string()),
+        }),
+        CrdsData::LegacyContactInfo(info) => Some(AdvertisedPeer {
+            pubkey: info.id,
+            gossip_addr: info.gossip,
+            shred_version: info.shred_version,
+            version: None,
+        }),
+        _ => None,
+    })
+}
+
+/// Whether a receive error leaves the socket usable
+fn is_transient_receive_error(error: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+
+    matches!(
+        error.kind(),
+        ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::HostUnreachable
+            | ErrorKind::NetworkUnreachable
+            | ErrorKind::Interrupted
+            | ErrorKind::WouldBlock
+            | ErrorKind::TimedOut
+    )
+}
+
+/// Receive packets on the shared socket and route them to outstanding requests
+///
+/// Verified ping challenges from peers we are handshaking with are answered
+/// here, since a `GossipNode` or validator only responds once it has verified
+/// our address. Packets that do not belong to any outstanding request are
+/// counted and dropped. Any other socket error ends the task and fails every
+/// outstanding and future handshake.
+async fn run_receiver(shared: Arc<SharedState>) {
+    let mut buffer = vec![0u8; shared.max_packet_size];
+    let mut consecutive_errors = 0u32;
+
+    loop {
+        let (bytes_received, sender_addr) = match shared.socket.recv_from(&mut buffer).await {
+            Ok(received) => {
+                consecutive_errors = 0;
+                received
+            }
+            Err(e) if is_transient_receive_error(&e) => {
+                // Errors such as ICMP unreachable notifications only affect a
+                // single peer, so keep serving the others, backing off if they
+                // keep coming
+                consecutive_errors = consecutive_errors.saturating_add(1);
+                let backoff = Duration::from_millis(1)
+                    .saturating_mul(2u32.saturating_pow(consecutive_errors - 1))
+                    .min(MAX_RECEIVE_ERROR_BACKOFF);
+                debug!(
+                    error = %e,
+                    consecutive_errors,
+                    backoff_ms = backoff.as_millis(),
+                    "Error receiving on handshake client socket"
+                );
+                tokio::time::sleep(backoff).await;
+                continue;
+            }
+            Err(e) => {
+                error!(error = %e, "Handshake client socket failed, no more replies can be received");
+                shared.stop_receiving(e.to_string());
+                return;
+            }
+        };
+        let packet = &buffer[..bytes_received];
+
+        debug!(
+            sender_addr = %sender_addr,
+            bytes_received = bytes_received,
+            "Received packet"
+        );
+
+        let response_error = match HandshakeResponse::decode(packet) {
+            Ok(response) => {
+                let response_tx = shared
+                    .pending()
+                    .responses
+                    .remove(&(sender_addr, response.nonce));
+                match response_tx {
+                    Some(response_tx) => {
+                        let _ = response_tx.send(Ok(response));
+                    }
+                    None => {
+                        debug!(
+                            sender_addr = %sender_addr,
+                            nonce = response.nonce,
+                            "Discarding response that matches no outstanding request"
+                        );
+                        shared.discard_packet();
+                    }
+                }
+                continue;
+            }
+            Err(e) => e,
+        };
+
+        let message = match Protocol::decode(packet) {
+            Ok(message) => message,
+            Err(e) => {
+                // A peer we await a legacy response from only sends responses, so
+                // report where its packet stopped decoding instead of timing out
+                if let SolanaNodeError::DecodeError { offset, reason } = &response_error {
+                    if shared.fail_responses(sender_addr, *offset, reason) {
+                        warn!(
+                            error = %response_error,
+                            sender_addr = %sender_addr,
+                            "Undecodable handshake response"
+                        );
+                        continue;
+                    }
+                }
+                debug!(error = %e, sender_addr = %sender_addr, "Discarding undecodable packet");
+                shared.discard_packet();
+                continue;
+            }
+        };
+
+        let (expected, session) = {
+            let pending = shared.pending();
+            (
+                pending.expects(&sender_addr),
+                pending.gossip_sessions.get(&sender_addr).cloned(),
+            )
+        };
+        if !expected {
+            debug!(
+                sender_addr = %sender_addr,
+                message = message.name(),
+                "Discarding gossip message from unexpected sender"
+            );
+            shared.discard_packet();
+            continue;
+        }
+
+        if let Protocol::PingMessage(ping) = &message {
+            if !ping.verify() {
+                shared.discard_packet();
+                continue;
+            }
+            debug!(sender_addr = %sender_addr, "Answering ping challenge");
+            let pong = Protocol::PongMessage(Pong::new(ping, &shared.identity));
+            match pong.encode() {
+                Ok(packet) => {
+                    if let Err(e) = shared.socket.send_to(&packet, sender_addr).await {
+                        warn!(error = %e, sender_addr = %sender_addr, "Failed to send pong");
+                    }
+                }
+                Err(e) => error!(error = %e, "Failed to encode pong"),
+            }
+        }
+
+        if let Some(session) = session {
+            if session.try_send(message).is_err() {
+                shared.discard_packet();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transient_receive_errors() {
+        let error = |kind| std::io::Error::from(kind);
+
+        assert!(is_transient_receive_error(&error(
+            std::io::ErrorKind::ConnectionRefused
+        )));
+        assert!(is_transient_receive_error(&error(
+            std::io::ErrorKind::Interrupted
+        )));
+        assert!(!is_transient_receive_error(&error(
+            std::io::ErrorKind::NotConnected
+        )));
+        assert!(!is_transient_receive_error(&std::io::Error::other(
+            "bad file descriptor"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_stopped_receiver_fails_handshakes_without_retrying() {
+        let client = HandshakeClient::builder()
+            .bind_addr("127.0.0.1:0")
+            .operation_timeout(Duration::from_secs(5))
+            .retry_policy(5)
+            .build()
+            .await
+            .unwrap();
+        let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let silent_addr = silent.local_addr().unwrap();
+
+        // A handshake waiting for a reply fails as soon as the receiver stops,
+        // well before a retry could be made
+        let started = Instant::now();
+        let waiting = client.perform_handshake(silent_addr);
+        let stop = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            client.shared.stop_receiving("socket closed".to_string());
+        };
+        let (result, ()) = tokio::join!(waiting, stop);
+
+        assert!(matches!(
+            result,
+            Err(SolanaNodeError::ReceiverStopped { ref reason }) if reason == "socket closed"
+        ));
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // Later handshakes fail without sending anything
+        assert!(matches!(
+            client.perform_handshake(silent_addr).await,
+            Err(SolanaNodeError::ReceiverStopped { .. })
+        ));
     }
 }
@@ -0,0 +1,415 @@
+//! Machine-readable results of client-mode handshakes
+//!
+//! A `HandshakeReport` holds one record per peer of a batch, with what the
+//! handshake learned or why it failed, and renders them as JSON, NDJSON, CSV
+//! or an aligned text table. Its exit code tells scripts whether none, some
+//! or all of the handshakes failed.
+
+use crate::solana::domain::{CapabilitySet, HandshakeOutcome, SolanaNodeError};
+use crate::solana::topology::{csv_field, optional};
+use serde::Serialize;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::str::FromStr;
+use tokio::io::AsyncWriteExt;
+
+/// Exit code when every handshake succeeded
+pub const EXIT_ALL_SUCCEEDED: i32 = 0;
+
+/// Exit code when some, but not all, handshakes failed
+pub const EXIT_SOME_FAILED: i32 = 2;
+
+/// Exit code when every handshake failed
+pub const EXIT_ALL_FAILED: i32 = 3;
+
+/// Format handshake results are written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// A pretty-printed JSON array of records
+    Json,
+    /// One compact JSON record per line
+    Ndjson,
+    /// One CSV row per record, with a header row
+    Csv,
+    /// Aligned columns for reading in a terminal
+    Table,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
+            "csv" => Ok(OutputFormat::Csv),
+            "table" | "text" => Ok(OutputFormat::Table),
+            _ => Err(format!(
+                "Invalid output format: '{}'. Valid options: json, ndjson, csv, table",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Ndjson => write!(f, "ndjson"),
+            OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::Table => write!(f, "table"),
+        }
+    }
+}
+
+/// Result of the handshake with one peer
+#[derive(Debug, Clone, Serialize)]
+pub struct HandshakeRecord {
+    /// Address the handshake was sent to
+    pub peer_addr: SocketAddr,
+
+    /// Whether the handshake succeeded
+    pub success: bool,
+
+    /// Peer pubkey in base58
+    pub peer_pubkey: Option<String>,
+
+    /// Software or protocol version reported by the peer
+    pub protocol_version: Option<String>,
+
+    /// Network the peer is on
+    pub network_id: Option<String>,
+
+    /// Capabilities agreed with the peer
+    pub capabilities: Option<CapabilitySet>,
+
+    /// Round-trip time of the successful attempt in milliseconds
+    pub rtt_ms: Option<f64>,
+
+    /// Attempts made, retries included
+    pub attempts: u8,
+
+    /// Kind of failure, as used in the `kind` label of the failure metric
+    pub error_kind: Option<String>,
+
+    /// Why the last attempt failed
+    pub error: Option<String>,
+}
+
+impl HandshakeRecord {
+    /// Build a record from the outcome of a handshake
+    pub fn from_outcome(outcome: &HandshakeOutcome) -> Self {
+        match &outcome.result {
+            Ok(details) => {
+                let peer_info = &details.peer_info;
+                Self {
+                    peer_addr: outcome.peer_addr,
+                    success: true,
+                    peer_pubkey: Some(peer_info.peer_pubkey.to_string()),
+                    protocol_version: Some(peer_info.protocol_version.clone()),
+                    network_id: Some(peer_info.network_id.clone()),
+                    capabilities: Some(peer_info.capabilities),
+                    rtt_ms: Some(details.rtt.as_secs_f64() * 1000.0),
+                    attempts: outcome.attempts,
+                    error_kind: None,
+                    error: None,
+                }
+            }
+            Err(e) => Self {
+                peer_addr: outcome.peer_addr,
+                success: false,
+                peer_pubkey: None,
+                protocol_version: None,
+                network_id: None,
+                capabilities: None,
+                rtt_ms: None,
+                attempts: outcome.attempts,
+                error_kind: Some(e.kind().to_string()),
+                error: Some(e.to_string()),
+            },
+        }
+    }
+
+    /// Fields in the column order of the CSV and table outputs
+    fn fields(&self) -> [String; 10] {
+        [
+            self.peer_addr.to_string(),
+            if self.success { "success" } else { "failure" }.to_string(),
+            self.peer_pubkey.clone().unwrap_or_default(),
+            self.protocol_version.clone().unwrap_or_default(),
+            self.network_id.clone().unwrap_or_default(),
+            optional(self.capabilities),
+            self.rtt_ms
+                .map(|rtt_ms| format!("{:.3}", rtt_ms))
+                .unwrap_or_default(),
+            self.attempts.to_string(),
+            self.error_kind.clone().unwrap_or_default(),
+            self.error.clone().unwrap_or_default(),
+        ]
+    }
+}
+
+/// Column names of the CSV and table outputs
+const COLUMNS: [&str; 10] = [
+    "peer_addr",
+    "status",
+    "peer_pubkey",
+    "protocol_version",
+    "network_id",
+    "capabilities",
+    "rtt_ms",
+    "attempts",
+    "error_kind",
+    "error",
+];
+
+/// Results of a batch of handshakes, in the order the peers were given
+#[derive(Debug, Clone, Default)]
+pub struct HandshakeReport {
+    /// One record per peer
+    pub records: Vec<HandshakeRecord>,
+}
+
+impl HandshakeReport {
+    /// Build a report from the outcomes of `HandshakeClient::perform_batch_handshake_outcomes`
+    pub fn from_outcomes(outcomes: &[HandshakeOutcome]) -> Self {
+        Self {
+            records: outcomes.iter().map(HandshakeRecord::from_outcome).collect(),
+        }
+    }
+
+    /// Number of handshakes that failed
+    pub fn failed_count(&self) -> usize {
+        self.records.iter().filter(|record| !record.success).count()
+    }
+
+    /// Process exit code reflecting how many handshakes failed
+    ///
+    /// `EXIT_ALL_SUCCEEDED` if none did (or there were no peers),
+    /// `EXIT_SOME_FAILED` if some did and `EXIT_ALL_FAILED` if all did.
+    pub fn exit_code(&self) -> i32 {
+        match self.failed_count() {
+            0 => EXIT_ALL_SUCCEEDED,
+            failed if failed == self.records.len() => EXIT_ALL_FAILED,
+            _ => EXIT_SOME_FAILED,
+        }
+    }
+
+    /// The records rendered in `format`
+    pub fn render(&self, format: OutputFormat) -> Result<String, SolanaNodeError> {
+        let serialization_error =
+            |e: serde_json::Error| SolanaNodeError::SerializationFailed(e.to_string());
+
+        match format {
+            OutputFormat::Json => serde_json::to_string_pretty(&self.records)
+                .map(|json| json + "\n")
+                .map_err(serialization_error),
+            OutputFormat::Ndjson => {
+                let mut ndjson = String::new();
+                for record in &self.records {
+                    ndjson.push_str(&serde_json::to_string(record).map_err(serialization_error)?);
+                    ndjson.push('\n');
+                }
+                Ok(ndjson)
+            }
+            OutputFormat::Csv => Ok(self.to_csv()),
+            OutputFormat::Table => Ok(self.to_table()),
+        }
+    }
+
+    /// One CSV row per record, with a header row
+    fn to_csv(&self) -> String {
+        let mut csv = COLUMNS.join(",");
+        csv.push('\n');
+
+        for record in &self.records {
+            let row: Vec<String> = record
+                .fields()
+                .iter()
+                .map(|field| csv_field(field))
+                .collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    /// Records as left-aligned columns, headed by the upper-cased column names
+    fn to_table(&self) -> String {
+        let header = COLUMNS.map(|column| column.to_uppercase());
+        let rows: Vec<[String; 10]> = self.records.iter().map(HandshakeRecord::fields).collect();
+
+        let mut widths = header.clone().map(|column| column.len());
+        for row in &rows {
+            for (width, field) in widths.iter_mut().zip(row) {
+                *width = (*width).max(field.len());
+            }
+        }
+
+        let mut table = String::new();
+        for row in std::iter::once(&header).chain(&rows) {
+            let mut line = String::new();
+            for (field, width) in row.iter().zip(widths) {
+                let _ = write!(line, "{:<width$}  ", field, width = width);
+            }
+            table.push_str(line.trim_end());
+            table.push('\n');
+        }
+
+        table
+    }
+
+    /// Write the records in `format` to `path`, or to stdout if `path` is `None`
+    pub async fn write(
+        &self,
+        format: OutputFormat,
+        path: Option<&Path>,
+    ) -> Result<(), SolanaNodeError> {
+        let output = self.render(format)?;
+
+        match path {
+            Some(path) => {
+                tokio::fs::write(path, output)
+                    .await
+                    .map_err(|e| SolanaNodeError::ReportError {
+                        path: path.display().to_string(),
+                        reason: e.to_string(),
+                    })
+            }
+            None => {
+                let mut stdout = tokio::io::stdout();
+                stdout.write_all(output.as_bytes()).await?;
+                stdout.flush().await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::domain::{network, ConnectionStatus, GossipNodeInfo, PeerDetails};
+    use std::time::Duration;
+
+    fn succeeded(port: u16) -> HandshakeOutcome {
+        HandshakeOutcome {
+            peer_addr: SocketAddr::from(([127, 0, 0, 1], port)),
+            attempts: 2,
+            result: Ok(PeerDetails {
+                peer_info: GossipNodeInfo::test_peer(
+                    SocketAddr::from(([127, 0, 0, 1], port)),
+                    ConnectionStatus::Connected,
+                ),
+                shred_version: None,
+                rtt: Duration::from_micros(1500),
+                advertised: Vec::new(),
+            }),
+        }
+    }
+
+    fn failed(port: u16) -> HandshakeOutcome {
+        HandshakeOutcome {
+            peer_addr: SocketAddr::from(([127, 0, 0, 1], port)),
+            attempts: 3,
+            result: Err(SolanaNodeError::NetworkMismatch {
+                expected: network::LOCALNET.to_string(),
+                received: network::TESTNET.to_string(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_output_format_from_str() {
+        assert_eq!("json".parse::<OutputFormat>().unwrap(), OutputFormat::Json);
+        assert_eq!(
+            "NDJSON".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Ndjson
+        );
+        assert_eq!(
+            "jsonl".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Ndjson
+        );
+        assert_eq!("csv".parse::<OutputFormat>().unwrap(), OutputFormat::Csv);
+        assert_eq!(
+            "table".parse::<OutputFormat>().unwrap(),
+            OutputFormat::Table
+        );
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_records_from_outcomes() {
+        let report = HandshakeReport::from_outcomes(&[succeeded(8001), failed(8002)]);
+
+        let ok = &report.records[0];
+        assert!(ok.success);
+        assert_eq!(ok.attempts, 2);
+        assert_eq!(ok.rtt_ms, Some(1.5));
+        assert_eq!(ok.network_id.as_deref(), Some(network::LOCALNET));
+        assert!(ok.error_kind.is_none());
+
+        let failure = &report.records[1];
+        assert!(!failure.success);
+        assert_eq!(failure.attempts, 3);
+        assert!(failure.peer_pubkey.is_none());
+        assert_eq!(failure.error_kind.as_deref(), Some("network_mismatch"));
+        assert!(failure
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("Network mismatch"));
+    }
+
+    #[test]
+    fn test_exit_code_reflects_failures() {
+        assert_eq!(HandshakeReport::default().exit_code(), EXIT_ALL_SUCCEEDED);
+        assert_eq!(
+            HandshakeReport::from_outcomes(&[succeeded(8001)]).exit_code(),
+            EXIT_ALL_SUCCEEDED
+        );
+        assert_eq!(
+            HandshakeReport::from_outcomes(&[succeeded(8001), failed(8002)]).exit_code(),
+            EXIT_SOME_FAILED
+        );
+        assert_eq!(
+            HandshakeReport::from_outcomes(&[failed(8001), failed(8002)]).exit_code(),
+            EXIT_ALL_FAILED
+        );
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let report = HandshakeReport::from_outcomes(&[succeeded(8001), failed(8002)]);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&report.render(OutputFormat::Json).unwrap()).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 2);
+        assert_eq!(json[1]["error_kind"], "network_mismatch");
+
+        let ndjson = report.render(OutputFormat::Ndjson).unwrap();
+        let lines: Vec<&str> = ndjson.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["peer_addr"], "127.0.0.1:8001");
+        assert_eq!(first["success"], true);
+
+        let csv = report.render(OutputFormat::Csv).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows[0], COLUMNS.join(","));
+        assert!(rows[1].starts_with("127.0.0.1:8001,success,"));
+        assert!(rows[1].contains(",1.500,2,,"));
+        assert!(rows[2].starts_with("127.0.0.1:8002,failure,,,,,,3,network_mismatch,"));
+
+        let table = report.render(OutputFormat::Table).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("PEER_ADDR       STATUS   PEER_PUBKEY"));
+        assert_eq!(lines.len(), 3);
+        // Columns line up under their headers
+        let status_column = lines[0].find("STATUS").unwrap();
+        assert_eq!(&lines[2][status_column..status_column + 7], "failure");
+    }
+}
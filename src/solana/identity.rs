@@ -0,0 +1,136 @@
+//! Persistent node identity
+//!
+//! Nodes identify themselves with an Ed25519 keypair stored in the standard
+//! Solana JSON keypair format (a `[u8; 64]` array, as written by
+//! `solana-keygen new`), so the same identity survives restarts and can be
+//! shared with the regular Solana tooling.
+
+use crate::solana::domain::SolanaNodeError;
+use solana_sdk::signature::{read_keypair_file, write_keypair_file, Keypair, Signer};
+use std::path::Path;
+use tracing::{info, instrument};
+
+/// Load the node identity stored at `path`
+///
+/// When the file does not exist and `generate_if_missing` is set, a fresh
+/// keypair is generated and written to `path` (creating parent directories
+/// as needed) so later runs reuse it.
+#[instrument]
+pub fn load_identity(path: &Path, generate_if_missing: bool) -> Result<Keypair, SolanaNodeError> {
+    if path.exists() {
+        let keypair = read_keypair_file(path).map_err(|e| identity_error(path, e))?;
+        info!(
+            path = %path.display(),
+            node_pubkey = %keypair.pubkey(),
+            "Loaded node identity"
+        );
+        return Ok(keypair);
+    }
+
+    if !generate_if_missing {
+        return Err(identity_error(
+            path,
+            "keypair file not found (use --generate-identity to create one)",
+        ));
+    }
+
+    generate_identity(path)
+}
+
+/// Generate a new keypair and save it to `path`
+///
+/// Refuses to overwrite an existing file.
+#[instrument]
+pub fn generate_identity(path: &Path) -> Result<Keypair, SolanaNodeError> {
+    if path.exists() {
+        return Err(identity_error(path, "refusing to overwrite existing file"));
+    }
+
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent).map_err(|e| identity_error(path, e))?;
+    }
+
+    let keypair = Keypair::new();
+    write_keypair_file(&keypair, path).map_err(|e| identity_error(path, e))?;
+
+    info!(
+        path = %path.display(),
+        node_pubkey = %keypair.pubkey(),
+        "Generated and saved new node identity"
+    );
+
+    Ok(keypair)
+}
+
+fn identity_error(path: &Path, reason: impl ToString) -> SolanaNodeError {
+    SolanaNodeError::IdentityError {
+        path: path.display().to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_identity_is_reloaded() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys").join("identity.json");
+
+        let generated = load_identity(&path, true).unwrap();
+        assert!(path.exists());
+
+        let reloaded = load_identity(&path, false).unwrap();
+        assert_eq!(reloaded.pubkey(), generated.pubkey());
+        assert_eq!(reloaded.to_bytes(), generated.to_bytes());
+    }
+
+    #[test]
+    fn test_reads_solana_keygen_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("identity.json");
+        let keypair = Keypair::new();
+        let bytes: Vec<String> = keypair.to_bytes().iter().map(u8::to_string).collect();
+        std::fs::write(&path, format!("[{}]", bytes.join(","))).unwrap();
+
+        let loaded = load_identity(&path, false).unwrap();
+        assert_eq!(loaded.pubkey(), keypair.pubkey());
+    }
+
+    #[test]
+    fn test_missing_identity_without_generation_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.json");
+
+        let result = load_identity(&path, false);
+        assert!(matches!(result, Err(SolanaNodeError::IdentityError { .. })));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_malformed_identity_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("identity.json");
+        std::fs::write(&path, "[1, 2, 3]").unwrap();
+
+        let result = load_identity(&path, true);
+        assert!(matches!(result, Err(SolanaNodeError::IdentityError { .. })));
+    }
+
+    #[test]
+    fn test_generate_does_not_overwrite() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("identity.json");
+
+        let original = generate_identity(&path).unwrap();
+        assert!(generate_identity(&path).is_err());
+        assert_eq!(
+            load_identity(&path, false).unwrap().pubkey(),
+            original.pubkey()
+        );
+    }
+}
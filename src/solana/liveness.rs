@@ -0,0 +1,327 @@
+//! Liveness tracking for registered peers
+//!
+//! The gossip node periodically probes every registered peer with a ping.
+//! Peers that miss too many probes in a row are marked `Disconnected`, and
+//! peers that stay unreachable past the TTL are purged from the registry.
+
+use crate::solana::domain::{ConnectionStatus, GossipNodeInfo};
+use crate::solana::peer_registry::PeerRegistry;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tracing::info;
+
+/// Default interval between liveness probes
+pub const DEFAULT_PROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Default number of consecutive unanswered probes before a peer is disconnected
+pub const DEFAULT_MAX_MISSED_PROBES: u32 = 3;
+
+/// Default time since a peer was last seen before it is purged
+pub const DEFAULT_PEER_TTL: Duration = Duration::from_secs(300);
+
+/// Liveness probing settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LivenessConfig {
+    /// How often registered peers are probed
+    pub probe_interval: Duration,
+
+    /// Consecutive unanswered probes after which a peer is marked `Disconnected`
+    pub max_missed_probes: u32,
+
+    /// How long after it was last seen an unreachable peer is purged
+    pub peer_ttl: Duration,
+}
+
+impl Default for LivenessConfig {
+    fn default() -> Self {
+        Self {
+            probe_interval: DEFAULT_PROBE_INTERVAL,
+            max_missed_probes: DEFAULT_MAX_MISSED_PROBES,
+            peer_ttl: DEFAULT_PEER_TTL,
+        }
+    }
+}
+
+/// Outcome of one maintenance pass
+#[derive(Debug, Default)]
+pub struct MaintenanceReport {
+    /// Peers to send a probe to
+    pub to_probe: Vec<SocketAddr>,
+
+    /// Peers that just transitioned from `Connected` to `Disconnected`
+    pub disconnected: Vec<GossipNodeInfo>,
+
+    /// Peers removed from the registry
+    pub expired: Vec<GossipNodeInfo>,
+}
+
+/// Probe bookkeeping for a single peer
+#[derive(Debug, Default)]
+struct ProbeState {
+    /// A probe was sent and has not been answered yet
+    awaiting_reply: bool,
+
+    /// Consecutive probes that went unanswered
+    missed_probes: u32,
+}
+
+/// Tracks outstanding probes and applies liveness transitions to a registry
+#[derive(Debug, Default)]
+pub struct LivenessTracker {
+    config: LivenessConfig,
+    probes: HashMap<SocketAddr, ProbeState>,
+}
+
+impl LivenessTracker {
+    pub fn new(config: LivenessConfig) -> Self {
+        Self {
+            config,
+            probes: HashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &LivenessConfig {
+        &self.config
+    }
+
+    /// Forget probe history for `addr`, e.g. after it re-registered
+    pub fn reset(&mut self, addr: &SocketAddr) {
+        self.probes.remove(addr);
+    }
+
+    /// Record that the peer at `addr` answered a probe at `now` (Unix seconds)
+    ///
+    /// Returns `Disconnected` if the peer was reconnected by the reply. A peer
+    /// that has not completed a handshake yet is left to the handshake, so a
+    /// reply neither connects it nor keeps it from expiring.
+    pub fn record_reply(
+        &mut self,
+        addr: &SocketAddr,
+        now: u64,
+        registry: &PeerRegistry,
+    ) -> Option<ConnectionStatus> {
+        self.probes.remove(addr);
+
+        let mut previous = None;
+        registry.update(addr, |peer_info| match peer_info.connection_status {
+            ConnectionStatus::Connected => {
+                peer_info.last_seen = peer_info.last_seen.max(now);
+            }
+            ConnectionStatus::Disconnected => {
+                peer_info.last_seen = peer_info.last_seen.max(now);
+                peer_info.connection_status = ConnectionStatus::Connected;
+                previous = Some(ConnectionStatus::Disconnected);
+            }
+            ConnectionStatus::Connecting | ConnectionStatus::VerificationFailed => {}
+        });
+
+        if let Some(previous) = &previous {
+            info!(
+                peer_addr = %addr,
+                previous_status = ?previous,
+                "Peer answered probe and is connected again"
+            );
+        }
+
+        previous
+    }
+
+    /// Count missed probes, apply status transitions and pick peers to probe
+    ///
+    /// `now` is in Unix seconds, matching `GossipNodeInfo::last_seen`.
+    pub fn maintain(&mut self, now: u64, registry: &PeerRegistry) -> MaintenanceReport {
+        let mut report = MaintenanceReport::default();
+        let peers = registry.snapshot();
+
+        self.probes
+            .retain(|addr, _| peers.iter().any(|peer_info| peer_info.gossip_addr == *addr));
+
+        for mut peer_info in peers {
+            let addr = peer_info.gossip_addr;
+            let state = self.probes.entry(addr).or_default();
+
+            if state.awaiting_reply {
+                state.missed_probes += 1;
+            }
+
+            if peer_info.connection_status == ConnectionStatus::Connected
+                && state.missed_probes >= self.config.max_missed_probes
+            {
+                registry.update(&addr, |entry| {
+                    entry.connection_status = ConnectionStatus::Disconnected;
+                });
+                info!(
+                    peer_addr = %addr,
+                    peer_pubkey = %peer_info.peer_pubkey,
+                    missed_probes = state.missed_probes,
+                    "Peer stopped answering probes, marking disconnected"
+                );
+                peer_info.connection_status = ConnectionStatus::Disconnected;
+                report.disconnected.push(peer_info.clone());
+            }
+
+            let unseen_for = now.saturating_sub(peer_info.last_seen);
+            if peer_info.connection_status != ConnectionStatus::Connected
+                && unseen_for > self.config.peer_ttl.as_secs()
+            {
+                registry.remove(&addr);
+                self.probes.remove(&addr);
+                info!(
+                    peer_addr = %addr,
+                    peer_pubkey = %peer_info.peer_pubkey,
+                    unseen_secs = unseen_for,
+                    "Peer expired, removing from registry"
+                );
+                report.expired.push(peer_info);
+                continue;
+            }
+
+            state.awaiting_reply = true;
+            report.to_probe.push(addr);
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn register(registry: &PeerRegistry, port: u16, last_seen: u64) -> SocketAddr {
+        let gossip_addr = SocketAddr::from(([127, 0, 0, 1], port));
+        registry.upsert(GossipNodeInfo {
+            last_seen,
+            ..GossipNodeInfo::test_peer(gossip_addr, ConnectionStatus::Connected)
+        });
+        gossip_addr
+    }
+
+    fn tracker() -> LivenessTracker {
+        LivenessTracker::new(LivenessConfig {
+            probe_interval: Duration::from_secs(10),
+            max_missed_probes: 2,
+            peer_ttl: Duration::from_secs(60),
+        })
+    }
+
+    #[test]
+    fn test_silent_peer_is_disconnected_then_expired() {
+        let registry = PeerRegistry::new();
+        let mut tracker = tracker();
+        let addr = register(&registry, 8001, NOW);
+
+        // First pass only probes
+        let report = tracker.maintain(NOW, &registry);
+        assert_eq!(report.to_probe, vec![addr]);
+        assert!(report.disconnected.is_empty());
+
+        // One missed probe is tolerated
+        let report = tracker.maintain(NOW + 10, &registry);
+        assert!(report.disconnected.is_empty());
+
+        // The second one disconnects the peer
+        let report = tracker.maintain(NOW + 20, &registry);
+        assert_eq!(report.disconnected.len(), 1);
+        assert_eq!(
+            registry.get(&addr).unwrap().connection_status,
+            ConnectionStatus::Disconnected
+        );
+        // Disconnected peers keep being probed until they expire
+        assert_eq!(report.to_probe, vec![addr]);
+
+        let report = tracker.maintain(NOW + 61, &registry);
+        assert_eq!(report.expired.len(), 1);
+        assert!(report.to_probe.is_empty());
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_reply_keeps_peer_connected() {
+        let registry = PeerRegistry::new();
+        let mut tracker = tracker();
+        let addr = register(&registry, 8001, NOW);
+
+        for round in 0..5 {
+            let now = NOW + round * 10;
+            let report = tracker.maintain(now, &registry);
+            assert!(report.disconnected.is_empty());
+            assert_eq!(tracker.record_reply(&addr, now + 1, &registry), None);
+        }
+
+        let peer_info = registry.get(&addr).unwrap();
+        assert_eq!(peer_info.connection_status, ConnectionStatus::Connected);
+        assert_eq!(peer_info.last_seen, NOW + 41);
+    }
+
+    #[test]
+    fn test_reply_reconnects_disconnected_peer() {
+        let registry = PeerRegistry::new();
+        let mut tracker = tracker();
+        let addr = register(&registry, 8001, NOW);
+
+        for round in 0..3 {
+            tracker.maintain(NOW + round * 10, &registry);
+        }
+        assert_eq!(
+            registry.get(&addr).unwrap().connection_status,
+            ConnectionStatus::Disconnected
+        );
+
+        assert_eq!(
+            tracker.record_reply(&addr, NOW + 25, &registry),
+            Some(ConnectionStatus::Disconnected)
+        );
+        assert_eq!(
+            registry.get(&addr).unwrap().connection_status,
+            ConnectionStatus::Connected
+        );
+
+        // The missed probe count starts over
+        tracker.maintain(NOW + 30, &registry);
+        let report = tracker.maintain(NOW + 40, &registry);
+        assert!(report.disconnected.is_empty());
+    }
+
+    #[test]
+    fn test_reply_does_not_connect_peer_without_handshake() {
+        let registry = PeerRegistry::new();
+        let mut tracker = tracker();
+        // Restored from a peer store and waiting for a handshake response
+        let addr = register(&registry, 8001, NOW);
+        registry.update(&addr, |peer_info| {
+            peer_info.connection_status = ConnectionStatus::Connecting;
+        });
+
+        // The peer answers every probe but never the handshake
+        for round in 0..6 {
+            let now = NOW + round * 10;
+            let report = tracker.maintain(now, &registry);
+            assert!(report.expired.is_empty());
+            assert_eq!(tracker.record_reply(&addr, now + 1, &registry), None);
+            assert_eq!(
+                registry.get(&addr).unwrap().connection_status,
+                ConnectionStatus::Connecting
+            );
+        }
+
+        let report = tracker.maintain(NOW + 61, &registry);
+        assert_eq!(report.expired.len(), 1);
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn test_connected_peer_is_not_expired_while_within_probe_budget() {
+        let registry = PeerRegistry::new();
+        let mut tracker = tracker();
+        // Registered from a request timestamp well in the past
+        register(&registry, 8001, NOW - 600);
+
+        let report = tracker.maintain(NOW, &registry);
+        assert!(report.expired.is_empty());
+        assert_eq!(report.to_probe.len(), 1);
+    }
+}
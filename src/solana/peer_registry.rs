@@ -0,0 +1,336 @@
+//! Shared registry of known peers
+//!
+//! `PeerRegistry` is a cheap, cloneable handle to the set of peers a
+//! `GossipNode` has registered. The node writes to it from its event loop
+//! while any number of other tasks read from it concurrently. The registry
+//! can be saved to and restored from a JSON file so that known peers survive
+//! a restart.
+
+use crate::solana::domain::{CapabilitySet, ConnectionStatus, GossipNodeInfo, SolanaNodeError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Thread-safe handle to a map of peers keyed by gossip address
+///
+/// Clones share the same underlying registry. Reads return owned copies so no
+/// lock is held once a call returns.
+#[derive(Debug, Clone, Default)]
+pub struct PeerRegistry {
+    peers: Arc<RwLock<HashMap<SocketAddr, GossipNodeInfo>>>,
+}
+
+/// On-disk form of a saved registry
+#[derive(Debug, Serialize, Deserialize)]
+struct RegistrySnapshot {
+    /// When the snapshot was taken, in seconds since the Unix epoch
+    saved_at: u64,
+
+    /// Every peer in the registry at that time
+    peers: Vec<GossipNodeInfo>,
+}
+
+/// Criteria for selecting peers from a `PeerRegistry`
+///
+/// Every criterion that is set must match; the default filter matches all peers.
+#[derive(Debug, Clone, Default)]
+pub struct PeerFilter {
+    /// Only peers in this connection state
+    pub status: Option<ConnectionStatus>,
+
+    /// Only peers on this network
+    pub network_id: Option<String>,
+
+    /// Only peers that agreed to all of these capabilities
+    pub capabilities: CapabilitySet,
+}
+
+impl PeerFilter {
+    /// Only match peers in `status`
+    pub fn with_status(mut self, status: ConnectionStatus) -> Self {
+        self.status = Some(status);
+        self
+    }
+
+    /// Only match peers on `network_id`
+    pub fn with_network(mut self, network_id: impl Into<String>) -> Self {
+        self.network_id = Some(network_id.into());
+        self
+    }
+
+    /// Only match peers that agreed to all of `capabilities`
+    pub fn with_capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Whether `peer` satisfies every criterion
+    pub fn matches(&self, peer: &GossipNodeInfo) -> bool {
+        self.status
+            .as_ref()
+            .is_none_or(|status| peer.connection_status == *status)
+            && self
+                .network_id
+                .as_ref()
+                .is_none_or(|network_id| peer.network_id == *network_id)
+            && peer.capabilities.contains_all(self.capabilities)
+    }
+}
+
+impl PeerRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a peer or replace the entry at its gossip address, returning the previous one
+    pub fn upsert(&self, peer_info: GossipNodeInfo) -> Option<GossipNodeInfo> {
+        self.write().insert(peer_info.gossip_addr, peer_info)
+    }
+
+    /// Apply `update` to the peer at `addr`, returning whether it was found
+    pub fn update(&self, addr: &SocketAddr, update: impl FnOnce(&mut GossipNodeInfo)) -> bool {
+        match self.write().get_mut(addr) {
+            Some(peer_info) => {
+                update(peer_info);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the peer at `addr`
+    pub fn remove(&self, addr: &SocketAddr) -> Option<GossipNodeInfo> {
+        self.write().remove(addr)
+    }
+
+    /// Copy of the peer at `addr`
+    pub fn get(&self, addr: &SocketAddr) -> Option<GossipNodeInfo> {
+        self.read().get(addr).cloned()
+    }
+
+    /// Copy of every registered peer
+    pub fn snapshot(&self) -> Vec<GossipNodeInfo> {
+        self.read().values().cloned().collect()
+    }
+
+    /// Copy of every peer matching `filter`
+    pub fn filter(&self, filter: &PeerFilter) -> Vec<GossipNodeInfo> {
+        self.read()
+            .values()
+            .filter(|peer_info| filter.matches(peer_info))
+            .cloned()
+            .collect()
+    }
+
+    /// Number of registered peers
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Whether no peers are registered
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Write every peer to `path` as JSON, returning how many were saved
+    ///
+    /// The file is written next to `path` and renamed into place, so a crash
+    /// mid-write never leaves a truncated snapshot behind.
+    pub async fn save(&self, path: &Path) -> Result<usize, SolanaNodeError> {
+        let snapshot = RegistrySnapshot {
+            saved_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            peers: self.snapshot(),
+        };
+        let contents = serde_json::to_vec_pretty(&snapshot)
+            .map_err(|e| peer_store_error(path, e.to_string()))?;
+
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| peer_store_error(path, e.to_string()))?;
+        }
+
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        tokio::fs::write(&temp_path, contents)
+            .await
+            .map_err(|e| peer_store_error(path, e.to_string()))?;
+        tokio::fs::rename(&temp_path, path)
+            .await
+            .map_err(|e| peer_store_error(path, e.to_string()))?;
+
+        Ok(snapshot.peers.len())
+    }
+
+    /// Read the peers saved by `save`, or none if `path` does not exist
+    pub async fn load(path: &Path) -> Result<Vec<GossipNodeInfo>, SolanaNodeError> {
+        let contents = match tokio::fs::read(path).await {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(peer_store_error(path, e.to_string())),
+        };
+
+        let snapshot: RegistrySnapshot =
+            serde_json::from_slice(&contents).map_err(|e| peer_store_error(path, e.to_string()))?;
+        Ok(snapshot.peers)
+    }
+
+    // A panic while holding the lock cannot leave a map entry half-written,
+    // so a poisoned lock is still safe to use
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<SocketAddr, GossipNodeInfo>> {
+        self.peers.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<SocketAddr, GossipNodeInfo>> {
+        self.peers.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn peer_store_error(path: &Path, reason: String) -> SolanaNodeError {
+    SolanaNodeError::PeerStoreError {
+        path: path.display().to_string(),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::domain::{network, Capability};
+
+    fn peer(port: u16, network_id: &str, status: ConnectionStatus) -> GossipNodeInfo {
+        GossipNodeInfo {
+            network_id: network_id.to_string(),
+            ..GossipNodeInfo::test_peer(SocketAddr::from(([127, 0, 0, 1], port)), status)
+        }
+    }
+
+    #[test]
+    fn test_clones_share_state() {
+        let registry = PeerRegistry::new();
+        let observer = registry.clone();
+
+        registry.upsert(peer(8001, network::LOCALNET, ConnectionStatus::Connected));
+        assert_eq!(observer.len(), 1);
+
+        let addr = SocketAddr::from(([127, 0, 0, 1], 8001));
+        assert!(observer.update(&addr, |peer_info| peer_info.last_seen = 42));
+        assert_eq!(registry.get(&addr).unwrap().last_seen, 42);
+
+        registry.remove(&addr);
+        assert!(observer.is_empty());
+        assert!(!observer.update(&addr, |_| {}));
+    }
+
+    #[test]
+    fn test_upsert_replaces_existing_entry() {
+        let registry = PeerRegistry::new();
+        let first = peer(8001, network::LOCALNET, ConnectionStatus::Connecting);
+        let second = peer(8001, network::LOCALNET, ConnectionStatus::Connected);
+
+        assert!(registry.upsert(first.clone()).is_none());
+        let previous = registry.upsert(second).unwrap();
+        assert_eq!(previous.peer_pubkey, first.peer_pubkey);
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn test_filter() {
+        let registry = PeerRegistry::new();
+        registry.upsert(peer(8001, network::LOCALNET, ConnectionStatus::Connected));
+        registry.upsert(peer(
+            8002,
+            network::LOCALNET,
+            ConnectionStatus::Disconnected,
+        ));
+        let mut gossip_only = peer(8003, network::TESTNET, ConnectionStatus::Connected);
+        gossip_only.capabilities = Capability::Gossip.into();
+        registry.upsert(gossip_only);
+
+        assert_eq!(registry.filter(&PeerFilter::default()).len(), 3);
+        assert_eq!(
+            registry
+                .filter(&PeerFilter::default().with_status(ConnectionStatus::Connected))
+                .len(),
+            2
+        );
+        assert_eq!(
+            registry
+                .filter(&PeerFilter::default().with_network(network::TESTNET))
+                .len(),
+            1
+        );
+        assert_eq!(
+            registry
+                .filter(
+                    &PeerFilter::default()
+                        .with_status(ConnectionStatus::Connected)
+                        .with_capabilities(Capability::Handshake.into())
+                )
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_concurrent_access() {
+        let registry = PeerRegistry::new();
+
+        let writers: Vec<_> = (0..4u16)
+            .map(|thread| {
+                let registry = registry.clone();
+                std::thread::spawn(move || {
+                    for i in 0..100 {
+                        registry.upsert(peer(
+                            thread * 1000 + i,
+                            network::LOCALNET,
+                            ConnectionStatus::Connected,
+                        ));
+                        let _ = registry.snapshot();
+                    }
+                })
+            })
+            .collect();
+
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert_eq!(registry.len(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("peers.json");
+
+        assert!(PeerRegistry::load(&path).await.unwrap().is_empty());
+
+        let registry = PeerRegistry::new();
+        let saved = peer(8001, network::LOCALNET, ConnectionStatus::Connected);
+        registry.upsert(saved.clone());
+        registry.upsert(peer(8002, network::TESTNET, ConnectionStatus::Disconnected));
+        assert_eq!(registry.save(&path).await.unwrap(), 2);
+
+        let mut loaded = PeerRegistry::load(&path).await.unwrap();
+        loaded.sort_by_key(|peer_info| peer_info.gossip_addr);
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].peer_pubkey, saved.peer_pubkey);
+        assert_eq!(loaded[0].capabilities, saved.capabilities);
+        assert_eq!(loaded[1].connection_status, ConnectionStatus::Disconnected);
+
+        std::fs::write(&path, b"not json").unwrap();
+        assert!(matches!(
+            PeerRegistry::load(&path).await,
+            Err(SolanaNodeError::PeerStoreError { .. })
+        ));
+    }
+}
@@ -0,0 +1,254 @@
+//! Ping/pong liveness cache for gossip peers
+//!
+//! Tracks the ping challenges this node has issued and the peers that have
+//! answered them, so that peers are only trusted once they have proven they
+//! own both their public key and the address they are sending from.
+
+use crate::solana::codec::{Ping, Pong};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// How long a verified pong keeps a peer trusted
+pub const DEFAULT_PING_CACHE_TTL: Duration = Duration::from_secs(1280);
+
+/// Minimum delay before re-issuing a ping to an address with one outstanding
+pub const DEFAULT_PING_RATE_LIMIT_DELAY: Duration = Duration::from_secs(1);
+
+/// Maximum number of tracked pings and verified peers
+pub const DEFAULT_PING_CACHE_CAPACITY: usize = 10_000;
+
+/// Cache of outstanding ping challenges and verified peers
+#[derive(Debug)]
+pub struct PingCache {
+    /// How long a verified pong remains valid
+    ttl: Duration,
+
+    /// Minimum delay between pings sent to the same address
+    rate_limit_delay: Duration,
+
+    /// Maximum number of entries in each map
+    capacity: usize,
+
+    /// Outstanding challenges by target address, with the time they were sent
+    pings: HashMap<SocketAddr, (Ping, Instant)>,
+
+    /// Peers that answered a challenge, with the time the pong was received
+    pongs: HashMap<(Pubkey, SocketAddr), Instant>,
+}
+
+impl Default for PingCache {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_PING_CACHE_TTL,
+            DEFAULT_PING_RATE_LIMIT_DELAY,
+            DEFAULT_PING_CACHE_CAPACITY,
+        )
+    }
+}
+
+impl PingCache {
+    /// Create an empty cache
+    pub fn new(ttl: Duration, rate_limit_delay: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            rate_limit_delay,
+            capacity,
+            pings: HashMap::new(),
+            pongs: HashMap::new(),
+        }
+    }
+
+    /// How long a verified pong, or an unanswered ping, is remembered
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Whether `pubkey` has recently proven ownership of `addr`
+    pub fn check(&self, now: Instant, pubkey: &Pubkey, addr: &SocketAddr) -> bool {
+        self.pongs
+            .get(&(*pubkey, *addr))
+            .is_some_and(|verified_at| now.duration_since(*verified_at) < self.ttl)
+    }
+
+    /// Issue a new ping for `addr` unless one was sent too recently
+    pub fn maybe_ping(
+        &mut self,
+        now: Instant,
+        addr: SocketAddr,
+        keypair: &Keypair,
+    ) -> Option<Ping> {
+        if let Some((_, sent_at)) = self.pings.get(&addr) {
+            if now.duration_since(*sent_at) < self.rate_limit_delay {
+                return None;
+            }
+        }
+
+        if self.pings.len() >= self.capacity {
+            self.prune(now);
+            evict_oldest(&mut self.pings, |(_, sent_at)| *sent_at);
+        }
+
+        let ping = Ping::new_rand(keypair);
+        self.pings.insert(addr, (ping.clone(), now));
+        Some(ping)
+    }
+
+    /// Record a pong received from `addr`
+    ///
+    /// Returns `true` if it answers the challenge outstanding for that address
+    /// and is correctly signed, in which case its sender becomes trusted.
+    pub fn add_pong(&mut self, now: Instant, pong: &Pong, addr: SocketAddr) -> bool {
+        let answers_ping = self
+            .pings
+            .get(&addr)
+            .is_some_and(|(ping, _)| pong.answers(ping));
+        if !answers_ping {
+            return false;
+        }
+
+        self.pings.remove(&addr);
+        if self.pongs.len() >= self.capacity {
+            self.prune(now);
+            evict_oldest(&mut self.pongs, |verified_at| *verified_at);
+        }
+        self.pongs.insert((pong.from, addr), now);
+        true
+    }
+
+    /// Drop expired challenges and verifications
+    pub fn prune(&mut self, now: Instant) {
+        let ttl = self.ttl;
+        self.pings
+            .retain(|_, (_, sent_at)| now.duration_since(*sent_at) < ttl);
+        self.pongs
+            .retain(|_, verified_at| now.duration_since(*verified_at) < ttl);
+    }
+
+    /// Number of outstanding ping challenges
+    pub fn pending_pings(&self) -> usize {
+        self.pings.len()
+    }
+
+    /// Number of currently verified peers
+    pub fn verified_peers(&self) -> usize {
+        self.pongs.len()
+    }
+}
+
+/// Remove the entry with the oldest timestamp if the map is non-empty
+fn evict_oldest<K, V>(map: &mut HashMap<K, V>, timestamp: impl Fn(&V) -> Instant)
+where
+    K: Clone + std::hash::Hash + Eq,
+{
+    if let Some(oldest) = map
+        .iter()
+        .min_by_key(|(_, value)| timestamp(value))
+        .map(|(key, _)| key.clone())
+    {
+        map.remove(&oldest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Signer;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn test_valid_pong_verifies_peer() {
+        let node = Keypair::new();
+        let peer = Keypair::new();
+        let mut cache = PingCache::default();
+        let now = Instant::now();
+
+        assert!(!cache.check(now, &peer.pubkey(), &addr(8001)));
+
+        let ping = cache.maybe_ping(now, addr(8001), &node).unwrap();
+        let pong = Pong::new(&ping, &peer);
+        assert!(cache.add_pong(now, &pong, addr(8001)));
+
+        assert!(cache.check(now, &peer.pubkey(), &addr(8001)));
+        // Verification is bound to the address the pong came from
+        assert!(!cache.check(now, &peer.pubkey(), &addr(8002)));
+        assert_eq!(cache.pending_pings(), 0);
+    }
+
+    #[test]
+    fn test_pong_from_other_address_is_rejected() {
+        let node = Keypair::new();
+        let peer = Keypair::new();
+        let mut cache = PingCache::default();
+        let now = Instant::now();
+
+        let ping = cache.maybe_ping(now, addr(8001), &node).unwrap();
+        let pong = Pong::new(&ping, &peer);
+
+        assert!(!cache.add_pong(now, &pong, addr(8002)));
+        assert!(!cache.check(now, &peer.pubkey(), &addr(8002)));
+    }
+
+    #[test]
+    fn test_unsolicited_or_forged_pong_is_rejected() {
+        let node = Keypair::new();
+        let peer = Keypair::new();
+        let mut cache = PingCache::default();
+        let now = Instant::now();
+
+        let unsolicited = Pong::new(&Ping::new_rand(&node), &peer);
+        assert!(!cache.add_pong(now, &unsolicited, addr(8001)));
+
+        let ping = cache.maybe_ping(now, addr(8001), &node).unwrap();
+        let mut forged = Pong::new(&ping, &peer);
+        forged.from = Keypair::new().pubkey();
+        assert!(!cache.add_pong(now, &forged, addr(8001)));
+    }
+
+    #[test]
+    fn test_pings_are_rate_limited() {
+        let node = Keypair::new();
+        let mut cache = PingCache::new(Duration::from_secs(60), Duration::from_secs(5), 16);
+        let now = Instant::now();
+
+        assert!(cache.maybe_ping(now, addr(8001), &node).is_some());
+        assert!(cache.maybe_ping(now, addr(8001), &node).is_none());
+        assert!(cache
+            .maybe_ping(now + Duration::from_secs(6), addr(8001), &node)
+            .is_some());
+    }
+
+    #[test]
+    fn test_verification_expires_after_ttl() {
+        let node = Keypair::new();
+        let peer = Keypair::new();
+        let ttl = Duration::from_secs(10);
+        let mut cache = PingCache::new(ttl, Duration::ZERO, 16);
+        let now = Instant::now();
+
+        let ping = cache.maybe_ping(now, addr(8001), &node).unwrap();
+        assert!(cache.add_pong(now, &Pong::new(&ping, &peer), addr(8001)));
+
+        let later = now + ttl;
+        assert!(!cache.check(later, &peer.pubkey(), &addr(8001)));
+        cache.prune(later);
+        assert_eq!(cache.verified_peers(), 0);
+    }
+
+    #[test]
+    fn test_capacity_is_bounded() {
+        let node = Keypair::new();
+        let mut cache = PingCache::new(Duration::from_secs(60), Duration::ZERO, 2);
+        let now = Instant::now();
+
+        for port in 0..5 {
+            cache.maybe_ping(now + Duration::from_millis(port.into()), addr(port), &node);
+        }
+        assert_eq!(cache.pending_pings(), 2);
+    }
+}
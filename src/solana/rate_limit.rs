@@ -0,0 +1,451 @@
+//! Flood protection for the gossip server
+//!
+//! Every handshake request is answered with a packet, so without limits a
+//! spoofed source can use the server as a UDP reflection amplifier. Packets are
+//! metered by token buckets at three levels: a global ceiling, one bucket per
+//! source IP (checked before any decoding), and one bucket per pubkey (checked
+//! once a request's signature is verified). Over-limit traffic is dropped
+//! without a reply. Failure responses to senders that have not proven their
+//! address have a budget of their own, as those senders may be spoofed.
+
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::Instant;
+
+/// Maximum number of source IPs or pubkeys with their own bucket
+pub const MAX_TRACKED_SOURCES: usize = 65_536;
+
+/// How the server treats requests it rejects before the sender is verified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RejectPolicy {
+    /// Send a failure response explaining the rejection
+    #[default]
+    Respond,
+    /// Only answer senders whose address answered a ping; drop the rest silently
+    DropUnverified,
+}
+
+impl FromStr for RejectPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "respond" => Ok(RejectPolicy::Respond),
+            "drop-unverified" | "drop" => Ok(RejectPolicy::DropUnverified),
+            _ => Err(format!(
+                "Invalid reject policy: '{}'. Valid options: respond, drop-unverified",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for RejectPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RejectPolicy::Respond => write!(f, "respond"),
+            RejectPolicy::DropUnverified => write!(f, "drop-unverified"),
+        }
+    }
+}
+
+/// Rate limits applied by the gossip server; `None` disables a limit
+///
+/// Each limit also allows a burst of up to one second's worth of packets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RateLimitConfig {
+    /// Packets per second accepted from all sources combined
+    pub global_packets_per_sec: Option<u32>,
+
+    /// Packets per second accepted from a single source IP
+    pub ip_packets_per_sec: Option<u32>,
+
+    /// Handshake requests and responses per second accepted for a single pubkey
+    pub pubkey_requests_per_sec: Option<u32>,
+
+    /// Failure responses per second sent to senders that have not answered a ping
+    pub unverified_rejections_per_sec: Option<u32>,
+}
+
+/// Which limit a packet exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitExceeded {
+    /// The global packets-per-second ceiling
+    Global,
+    /// The per-source-IP limit
+    SourceIp,
+    /// The per-pubkey request limit
+    Pubkey,
+}
+
+impl std::fmt::Display for RateLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateLimitExceeded::Global => write!(f, "global packet rate limit"),
+            RateLimitExceeded::SourceIp => write!(f, "source IP packet rate limit"),
+            RateLimitExceeded::Pubkey => write!(f, "pubkey request rate limit"),
+        }
+    }
+}
+
+/// Classic token bucket refilled continuously at `rate` tokens per second
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    tokens: f64,
+    capacity: f64,
+    rate: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// A full bucket holding one second's worth of tokens
+    fn new(rate: u32, now: Instant) -> Self {
+        let rate = f64::from(rate.max(1));
+        Self {
+            tokens: rate,
+            capacity: rate,
+            rate,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Take one token if available
+    fn try_take(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether the bucket has refilled completely, i.e. the source is idle
+    fn is_full(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= self.capacity
+    }
+}
+
+/// Token buckets keyed by source, bounded to `capacity` entries
+///
+/// Once the table is full, sources without a bucket of their own share one
+/// overflow bucket until pruning frees room. Evicting a tracked source instead
+/// would hand a flooding source a fresh bucket.
+#[derive(Debug)]
+struct KeyedBuckets<K> {
+    rate: u32,
+    capacity: usize,
+    buckets: HashMap<K, TokenBucket>,
+    overflow: Option<TokenBucket>,
+}
+
+impl<K: Eq + Hash + Copy> KeyedBuckets<K> {
+    fn new(rate: u32) -> Self {
+        Self::with_capacity(rate, MAX_TRACKED_SOURCES)
+    }
+
+    fn with_capacity(rate: u32, capacity: usize) -> Self {
+        Self {
+            rate,
+            capacity,
+            buckets: HashMap::new(),
+            overflow: None,
+        }
+    }
+
+    fn try_take(&mut self, now: Instant, key: K) -> bool {
+        let rate = self.rate;
+        if let Some(bucket) = self.buckets.get_mut(&key) {
+            return bucket.try_take(now);
+        }
+
+        let bucket = if self.buckets.len() < self.capacity {
+            self.buckets
+                .entry(key)
+                .or_insert(TokenBucket::new(rate, now))
+        } else {
+            self.overflow
+                .get_or_insert_with(|| TokenBucket::new(rate, now))
+        };
+        bucket.try_take(now)
+    }
+
+    /// Forget sources whose bucket has refilled, as a fresh bucket is identical
+    fn prune(&mut self, now: Instant) {
+        self.buckets.retain(|_, bucket| !bucket.is_full(now));
+        if self
+            .overflow
+            .as_mut()
+            .is_some_and(|bucket| bucket.is_full(now))
+        {
+            self.overflow = None;
+        }
+    }
+}
+
+/// Applies a `RateLimitConfig` to incoming traffic
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    global: Option<TokenBucket>,
+    per_ip: Option<KeyedBuckets<IpAddr>>,
+    per_pubkey: Option<KeyedBuckets<Pubkey>>,
+    unverified_rejections: Option<TokenBucket>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            config,
+            global: config
+                .global_packets_per_sec
+                .map(|rate| TokenBucket::new(rate, now)),
+            per_ip: config.ip_packets_per_sec.map(KeyedBuckets::new),
+            per_pubkey: config.pubkey_requests_per_sec.map(KeyedBuckets::new),
+            unverified_rejections: config
+                .unverified_rejections_per_sec
+                .map(|rate| TokenBucket::new(rate, now)),
+        }
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Account for a packet from `ip`, before it is decoded
+    ///
+    /// The source's own limit is checked first, so packets from a source
+    /// already over it do not use up the allowance shared with other sources.
+    pub fn check_packet(&mut self, now: Instant, ip: IpAddr) -> Result<(), RateLimitExceeded> {
+        if let Some(per_ip) = &mut self.per_ip {
+            if !per_ip.try_take(now, ip) {
+                return Err(RateLimitExceeded::SourceIp);
+            }
+        }
+
+        if let Some(global) = &mut self.global {
+            if !global.try_take(now) {
+                return Err(RateLimitExceeded::Global);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Account for a handshake request or response signed by `pubkey`
+    pub fn check_request(&mut self, now: Instant, pubkey: Pubkey) -> Result<(), RateLimitExceeded> {
+        if let Some(per_pubkey) = &mut self.per_pubkey {
+            if !per_pubkey.try_take(now, pubkey) {
+                return Err(RateLimitExceeded::Pubkey);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether a failure response may be sent to an unverified sender
+    pub fn check_rejection(&mut self, now: Instant) -> bool {
+        self.unverified_rejections
+            .as_mut()
+            .is_none_or(|bucket| bucket.try_take(now))
+    }
+
+    /// Forget idle sources
+    pub fn prune(&mut self, now: Instant) {
+        if let Some(per_ip) = &mut self.per_ip {
+            per_ip.prune(now);
+        }
+        if let Some(per_pubkey) = &mut self.per_pubkey {
+            per_pubkey.prune(now);
+        }
+    }
+
+    /// Number of sources currently tracked
+    pub fn tracked_sources(&self) -> usize {
+        self.per_ip.as_ref().map_or(0, |b| b.buckets.len())
+            + self.per_pubkey.as_ref().map_or(0, |b| b.buckets.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_unlimited_by_default() {
+        let mut limiter = RateLimiter::default();
+        let now = Instant::now();
+        let ip = IpAddr::from([10, 0, 0, 1]);
+
+        for _ in 0..10_000 {
+            assert!(limiter.check_packet(now, ip).is_ok());
+            assert!(limiter.check_request(now, Pubkey::default()).is_ok());
+        }
+        assert_eq!(limiter.tracked_sources(), 0);
+    }
+
+    #[test]
+    fn test_per_ip_limit_refills() {
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            ip_packets_per_sec: Some(10),
+            ..Default::default()
+        });
+        let now = Instant::now();
+        let noisy = IpAddr::from([10, 0, 0, 1]);
+        let quiet = IpAddr::from([10, 0, 0, 2]);
+
+        for _ in 0..10 {
+            assert!(limiter.check_packet(now, noisy).is_ok());
+        }
+        assert_eq!(
+            limiter.check_packet(now, noisy),
+            Err(RateLimitExceeded::SourceIp)
+        );
+
+        // Other sources have their own bucket
+        assert!(limiter.check_packet(now, quiet).is_ok());
+
+        // A tenth of a second buys one more packet
+        let later = now + Duration::from_millis(100);
+        assert!(limiter.check_packet(later, noisy).is_ok());
+        assert!(limiter.check_packet(later, noisy).is_err());
+    }
+
+    #[test]
+    fn test_global_limit_applies_across_sources() {
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            global_packets_per_sec: Some(5),
+            ..Default::default()
+        });
+        let now = Instant::now();
+
+        for i in 0..5u8 {
+            assert!(limiter
+                .check_packet(now, IpAddr::from([10, 0, 0, i]))
+                .is_ok());
+        }
+        assert_eq!(
+            limiter.check_packet(now, IpAddr::from([10, 0, 0, 99])),
+            Err(RateLimitExceeded::Global)
+        );
+    }
+
+    #[test]
+    fn test_noisy_source_does_not_drain_global_limit() {
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            global_packets_per_sec: Some(10),
+            ip_packets_per_sec: Some(5),
+            ..Default::default()
+        });
+        let now = Instant::now();
+        let noisy = IpAddr::from([10, 0, 0, 1]);
+        let quiet = IpAddr::from([10, 0, 0, 2]);
+
+        for _ in 0..5 {
+            assert!(limiter.check_packet(now, noisy).is_ok());
+        }
+        for _ in 0..100 {
+            assert_eq!(
+                limiter.check_packet(now, noisy),
+                Err(RateLimitExceeded::SourceIp)
+            );
+        }
+
+        // The flood beyond the source's own limit left the rest for others
+        for _ in 0..5 {
+            assert!(limiter.check_packet(now, quiet).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_unverified_rejection_limit() {
+        let mut limiter = RateLimiter::default();
+        let now = Instant::now();
+        assert!((0..1000).all(|_| limiter.check_rejection(now)));
+
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            unverified_rejections_per_sec: Some(3),
+            ..Default::default()
+        });
+        assert!((0..3).all(|_| limiter.check_rejection(now)));
+        assert!(!limiter.check_rejection(now));
+        assert!(limiter.check_rejection(now + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_per_pubkey_limit_and_pruning() {
+        let mut limiter = RateLimiter::new(RateLimitConfig {
+            pubkey_requests_per_sec: Some(2),
+            ..Default::default()
+        });
+        let now = Instant::now();
+        let pubkey = Pubkey::new_unique();
+
+        assert!(limiter.check_request(now, pubkey).is_ok());
+        assert!(limiter.check_request(now, pubkey).is_ok());
+        assert_eq!(
+            limiter.check_request(now, pubkey),
+            Err(RateLimitExceeded::Pubkey)
+        );
+        assert_eq!(limiter.tracked_sources(), 1);
+
+        // Once the bucket has refilled the entry carries no information
+        limiter.prune(now + Duration::from_secs(1));
+        assert_eq!(limiter.tracked_sources(), 0);
+    }
+
+    #[test]
+    fn test_untracked_sources_share_overflow_bucket() {
+        let mut buckets = KeyedBuckets::with_capacity(2, 3);
+        let now = Instant::now();
+
+        for ip in 0..3u8 {
+            assert!(buckets.try_take(now, ip));
+        }
+        assert_eq!(buckets.buckets.len(), 3);
+
+        // New sources beyond the table share a single bucket
+        assert!(buckets.try_take(now, 10));
+        assert!(buckets.try_take(now, 11));
+        assert!(!buckets.try_take(now, 12));
+        assert_eq!(buckets.buckets.len(), 3);
+
+        // Tracked sources keep their own allowance
+        assert!(buckets.try_take(now, 0));
+        assert!(!buckets.try_take(now, 0));
+
+        // Pruning idle sources makes room again
+        let later = now + Duration::from_secs(1);
+        buckets.prune(later);
+        assert!(buckets.buckets.is_empty());
+        assert!(buckets.overflow.is_none());
+        assert!(buckets.try_take(later, 12));
+        assert_eq!(buckets.buckets.len(), 1);
+    }
+
+    #[test]
+    fn test_reject_policy_parsing() {
+        assert_eq!(
+            "respond".parse::<RejectPolicy>().unwrap(),
+            RejectPolicy::Respond
+        );
+        assert_eq!(
+            "drop-unverified".parse::<RejectPolicy>().unwrap(),
+            RejectPolicy::DropUnverified
+        );
+        assert!("ignore".parse::<RejectPolicy>().is_err());
+        assert_eq!(RejectPolicy::DropUnverified.to_string(), "drop-unverified");
+    }
+}
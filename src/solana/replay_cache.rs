@@ -0,0 +1,271 @@
+//! Replay protection for incoming handshake requests
+//!
+//! Every signed `HandshakeRequest` carries a timestamp and a random nonce. A
+//! request is only accepted if its timestamp falls within the acceptance
+//! window and its (sender, nonce) pair has not been seen before, so a captured
+//! packet cannot be replayed to re-register a peer.
+
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{BTreeSet, HashSet};
+use std::time::Duration;
+
+/// How far a request timestamp may drift from our clock before it is rejected
+pub const DEFAULT_REPLAY_WINDOW: Duration = Duration::from_secs(120);
+
+/// Maximum number of remembered requests
+pub const DEFAULT_REPLAY_CACHE_CAPACITY: usize = 100_000;
+
+/// Why a request was refused by the replay cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayRejection {
+    /// The request timestamp is outside the acceptance window
+    Stale { timestamp: u64, now: u64 },
+    /// The same sender already used this nonce
+    Duplicate { nonce: u64 },
+}
+
+impl std::fmt::Display for ReplayRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayRejection::Stale { timestamp, now } => write!(
+                f,
+                "request timestamp {} is outside the acceptance window (now {})",
+                timestamp, now
+            ),
+            ReplayRejection::Duplicate { nonce } => {
+                write!(f, "request nonce {} was already used", nonce)
+            }
+        }
+    }
+}
+
+/// Bounded record of recently accepted requests
+///
+/// Entries only need to outlive the acceptance window, since older requests
+/// are rejected by their timestamp anyway. When the cache is full of
+/// unexpired entries the oldest one is forgotten, and the window is tightened
+/// so that requests as old as it are rejected as stale. A flood of requests
+/// therefore cannot lock out fresh ones, and a forgotten request still cannot
+/// be replayed. The window is never tightened past the present, so only
+/// requests stamped at or ahead of our clock lose their replay protection
+/// when forgotten.
+#[derive(Debug)]
+pub struct ReplayCache {
+    /// Maximum accepted distance between a request timestamp and now
+    window: Duration,
+
+    /// Maximum number of remembered requests
+    capacity: usize,
+
+    /// Remembered requests by sender and nonce
+    seen: HashSet<(Pubkey, u64)>,
+
+    /// The same requests ordered by timestamp, so expired ones are found first
+    by_timestamp: BTreeSet<(u64, Pubkey, u64)>,
+
+    /// Requests stamped before this are rejected, as they may have been forgotten
+    min_timestamp: u64,
+}
+
+impl Default for ReplayCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_REPLAY_WINDOW, DEFAULT_REPLAY_CACHE_CAPACITY)
+    }
+}
+
+impl ReplayCache {
+    /// Create an empty cache
+    pub fn new(window: Duration, capacity: usize) -> Self {
+        Self {
+            window,
+            capacity,
+            seen: HashSet::new(),
+            by_timestamp: BTreeSet::new(),
+            min_timestamp: 0,
+        }
+    }
+
+    /// Acceptance window for request timestamps
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Accept a request and remember it, or explain why it is refused
+    ///
+    /// `now` and `timestamp` are seconds since the Unix epoch.
+    pub fn check_and_insert(
+        &mut self,
+        now: u64,
+        pubkey: Pubkey,
+        nonce: u64,
+        timestamp: u64,
+    ) -> Result<(), ReplayRejection> {
+        if now.abs_diff(timestamp) > self.window.as_secs() || timestamp < self.min_timestamp {
+            return Err(ReplayRejection::Stale { timestamp, now });
+        }
+
+        if self.seen.contains(&(pubkey, nonce)) {
+            return Err(ReplayRejection::Duplicate { nonce });
+        }
+
+        if self.seen.len() >= self.capacity {
+            self.prune(now);
+        }
+        while self.seen.len() >= self.capacity.max(1) {
+            let Some((oldest, pubkey, nonce)) = self.by_timestamp.pop_first() else {
+                break;
+            };
+            self.seen.remove(&(pubkey, nonce));
+            self.min_timestamp = self.min_timestamp.max(oldest.saturating_add(1).min(now));
+        }
+
+        self.seen.insert((pubkey, nonce));
+        self.by_timestamp.insert((timestamp, pubkey, nonce));
+        Ok(())
+    }
+
+    /// Forget requests that are too old to be accepted again
+    ///
+    /// Only the expired entries are visited.
+    pub fn prune(&mut self, now: u64) {
+        let window = self.window.as_secs();
+        while let Some(&(timestamp, pubkey, nonce)) = self.by_timestamp.first() {
+            if now.saturating_sub(timestamp) <= window {
+                break;
+            }
+            self.by_timestamp.pop_first();
+            self.seen.remove(&(pubkey, nonce));
+        }
+    }
+
+    /// Number of remembered requests
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Whether no requests are remembered
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    #[test]
+    fn test_fresh_request_is_accepted_once() {
+        let mut cache = ReplayCache::default();
+        let pubkey = Pubkey::new_unique();
+
+        assert!(cache.check_and_insert(NOW, pubkey, 7, NOW).is_ok());
+        assert_eq!(
+            cache.check_and_insert(NOW, pubkey, 7, NOW),
+            Err(ReplayRejection::Duplicate { nonce: 7 })
+        );
+
+        // Nonces are scoped to the sender
+        assert!(cache
+            .check_and_insert(NOW, Pubkey::new_unique(), 7, NOW)
+            .is_ok());
+        assert!(cache.check_and_insert(NOW, pubkey, 8, NOW).is_ok());
+    }
+
+    #[test]
+    fn test_requests_outside_window_are_stale() {
+        let mut cache = ReplayCache::new(Duration::from_secs(30), 16);
+        let pubkey = Pubkey::new_unique();
+
+        assert!(matches!(
+            cache.check_and_insert(NOW, pubkey, 1, NOW - 31),
+            Err(ReplayRejection::Stale { .. })
+        ));
+        assert!(matches!(
+            cache.check_and_insert(NOW, pubkey, 2, NOW + 31),
+            Err(ReplayRejection::Stale { .. })
+        ));
+        assert!(cache.check_and_insert(NOW, pubkey, 3, NOW - 30).is_ok());
+        assert!(cache.check_and_insert(NOW, pubkey, 4, NOW + 30).is_ok());
+    }
+
+    #[test]
+    fn test_expired_entries_are_pruned() {
+        let mut cache = ReplayCache::new(Duration::from_secs(30), 16);
+        let pubkey = Pubkey::new_unique();
+
+        cache.check_and_insert(NOW, pubkey, 1, NOW).unwrap();
+        cache.prune(NOW + 30);
+        assert_eq!(cache.len(), 1);
+        cache.prune(NOW + 31);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_capacity_is_bounded() {
+        let mut cache = ReplayCache::new(Duration::from_secs(60), 3);
+        let pubkey = Pubkey::new_unique();
+
+        for nonce in 0..10 {
+            assert!(cache
+                .check_and_insert(NOW, pubkey, nonce, NOW - 10 + nonce)
+                .is_ok());
+        }
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn test_flood_does_not_lock_out_fresh_requests() {
+        let mut cache = ReplayCache::new(Duration::from_secs(60), 3);
+        let peer = Pubkey::new_unique();
+        cache.check_and_insert(NOW - 5, peer, 1, NOW - 5).unwrap();
+
+        // Requests from throwaway identities fill the cache many times over
+        for _ in 0..100 {
+            assert!(cache
+                .check_and_insert(NOW, Pubkey::new_unique(), 0, NOW)
+                .is_ok());
+        }
+        assert_eq!(cache.len(), 3);
+
+        // A fresh request is still accepted
+        assert!(cache.check_and_insert(NOW, peer, 2, NOW).is_ok());
+
+        // The forgotten request cannot be replayed, nor can older ones
+        assert!(matches!(
+            cache.check_and_insert(NOW, peer, 1, NOW - 5),
+            Err(ReplayRejection::Stale { .. })
+        ));
+        assert!(matches!(
+            cache.check_and_insert(NOW, peer, 3, NOW - 10),
+            Err(ReplayRejection::Stale { .. })
+        ));
+    }
+
+    #[test]
+    fn test_forgotten_request_stays_rejected_while_in_window() {
+        let mut cache = ReplayCache::new(Duration::from_secs(60), 3);
+        let pubkey = Pubkey::new_unique();
+
+        for nonce in 0..4 {
+            cache
+                .check_and_insert(NOW, pubkey, nonce, NOW - 10 + nonce)
+                .unwrap();
+        }
+
+        // The first request was forgotten but is now too old to be accepted
+        assert!(matches!(
+            cache.check_and_insert(NOW, pubkey, 0, NOW - 10),
+            Err(ReplayRejection::Stale { .. })
+        ));
+
+        // The rest are still remembered
+        for nonce in 1..4 {
+            assert_eq!(
+                cache.check_and_insert(NOW, pubkey, nonce, NOW - 10 + nonce),
+                Err(ReplayRejection::Duplicate { nonce })
+            );
+        }
+    }
+}
@@ -0,0 +1,300 @@
+//! Retry policy for failed handshakes
+//!
+//! A `RetryPolicy` decides whether a failed handshake is attempted again and
+//! how long the client waits first. Delays grow exponentially, linearly or
+//! not at all up to a cap, and are randomized so that clients started
+//! together do not retry in lockstep.
+
+use crate::solana::domain::{ErrorKind, SolanaNodeError};
+use rand::Rng;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Default number of attempts per handshake, the first one included
+pub const DEFAULT_MAX_ATTEMPTS: u8 = 3;
+
+/// Default delay before the first retry
+pub const DEFAULT_RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Default longest delay between two attempts
+pub const DEFAULT_RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// How the delay between attempts grows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backoff {
+    /// The base delay doubles with every retry
+    #[default]
+    Exponential,
+    /// The base delay is added with every retry
+    Linear,
+    /// Every retry waits the base delay
+    Constant,
+}
+
+impl FromStr for Backoff {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "exponential" | "exp" => Ok(Backoff::Exponential),
+            "linear" => Ok(Backoff::Linear),
+            "constant" | "fixed" => Ok(Backoff::Constant),
+            _ => Err(format!(
+                "Invalid backoff: '{}'. Valid options: exponential, linear, constant",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Backoff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Backoff::Exponential => write!(f, "exponential"),
+            Backoff::Linear => write!(f, "linear"),
+            Backoff::Constant => write!(f, "constant"),
+        }
+    }
+}
+
+/// How the delay between attempts is randomized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Jitter {
+    /// Wait exactly the backoff delay
+    None,
+    /// Wait a random delay between zero and the backoff delay
+    #[default]
+    Full,
+    /// Wait a random delay between the base delay and three times the
+    /// previous delay, regardless of the backoff
+    Decorrelated,
+}
+
+impl FromStr for Jitter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" | "off" => Ok(Jitter::None),
+            "full" => Ok(Jitter::Full),
+            "decorrelated" => Ok(Jitter::Decorrelated),
+            _ => Err(format!(
+                "Invalid jitter: '{}'. Valid options: none, full, decorrelated",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Jitter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Jitter::None => write!(f, "none"),
+            Jitter::Full => write!(f, "full"),
+            Jitter::Decorrelated => write!(f, "decorrelated"),
+        }
+    }
+}
+
+/// When and how soon a failed handshake is attempted again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts made in total, the first one included; the first attempt is
+    /// made even when this is 0
+    pub max_attempts: u8,
+
+    /// How the delay grows from one retry to the next
+    pub backoff: Backoff,
+
+    /// Delay before the first retry, and the unit of later ones
+    pub base_delay: Duration,
+
+    /// Longest delay between two attempts
+    pub max_delay: Duration,
+
+    /// How delays are randomized
+    pub jitter: Jitter,
+
+    /// Time since the first attempt after which no retry is started
+    pub deadline: Option<Duration>,
+
+    /// Kinds of failure never retried, on top of those that cannot succeed on retry
+    pub non_retryable: BTreeSet<ErrorKind>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            backoff: Backoff::default(),
+            base_delay: DEFAULT_RETRY_BASE_DELAY,
+            max_delay: DEFAULT_RETRY_MAX_DELAY,
+            jitter: Jitter::default(),
+            deadline: None,
+            non_retryable: BTreeSet::new(),
+        }
+    }
+}
+
+/// The default policy with `max_attempts` attempts
+impl From<u8> for RetryPolicy {
+    fn from(max_attempts: u8) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether the handshake should be attempted again after `error`
+    pub fn should_retry(&self, error: &SolanaNodeError) -> bool {
+        error.is_retryable() && !self.non_retryable.contains(&error.kind())
+    }
+
+    /// Whether a retry starting `elapsed` after the first attempt is within the deadline
+    pub fn within_deadline(&self, elapsed: Duration) -> bool {
+        self.deadline.is_none_or(|deadline| elapsed < deadline)
+    }
+
+    /// Delay before retry number `retry`, counting from 1, without jitter
+    pub fn backoff_delay(&self, retry: u32) -> Duration {
+        let delay = match self.backoff {
+            Backoff::Exponential => {
+                let factor = 1u32
+                    .checked_shl(retry.saturating_sub(1))
+                    .unwrap_or(u32::MAX);
+                self.base_delay.saturating_mul(factor)
+            }
+            Backoff::Linear => self.base_delay.saturating_mul(retry.max(1)),
+            Backoff::Constant => self.base_delay,
+        };
+        delay.min(self.max_delay)
+    }
+
+    /// Randomized delay before retry number `retry`, counting from 1
+    ///
+    /// `previous` is the delay before the previous retry, or the base delay
+    /// before the first; only decorrelated jitter uses it.
+    pub fn delay<R: Rng + ?Sized>(&self, retry: u32, previous: Duration, rng: &mut R) -> Duration {
+        match self.jitter {
+            Jitter::None => self.backoff_delay(retry),
+            Jitter::Full => rng.gen_range(Duration::ZERO..=self.backoff_delay(retry)),
+            Jitter::Decorrelated => {
+                let low = self.base_delay.min(self.max_delay);
+                let high = previous.saturating_mul(3).clamp(low, self.max_delay);
+                rng.gen_range(low..=high)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::net::SocketAddr;
+
+    fn policy(backoff: Backoff, jitter: Jitter) -> RetryPolicy {
+        RetryPolicy {
+            backoff,
+            jitter,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+            ..RetryPolicy::default()
+        }
+    }
+
+    #[test]
+    fn test_backoff_delays_are_capped() {
+        let delays = |backoff| -> Vec<u128> {
+            (1..=6)
+                .map(|retry| {
+                    policy(backoff, Jitter::None)
+                        .backoff_delay(retry)
+                        .as_millis()
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            delays(Backoff::Exponential),
+            vec![100, 200, 400, 800, 1000, 1000]
+        );
+        assert_eq!(delays(Backoff::Linear), vec![100, 200, 300, 400, 500, 600]);
+        assert_eq!(delays(Backoff::Constant), vec![100; 6]);
+        assert_eq!(
+            policy(Backoff::Exponential, Jitter::None).backoff_delay(200),
+            Duration::from_millis(1000)
+        );
+    }
+
+    #[test]
+    fn test_jittered_delays_stay_in_range() {
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let full = policy(Backoff::Exponential, Jitter::Full);
+        let delays: Vec<_> = (0..100)
+            .map(|_| full.delay(3, Duration::ZERO, &mut rng))
+            .collect();
+        assert!(delays
+            .iter()
+            .all(|delay| *delay <= Duration::from_millis(400)));
+        assert!(delays.iter().any(|delay| *delay != delays[0]));
+
+        let decorrelated = policy(Backoff::Exponential, Jitter::Decorrelated);
+        let mut previous = decorrelated.base_delay;
+        for retry in 1..=20 {
+            let delay = decorrelated.delay(retry, previous, &mut rng);
+            assert!(delay >= Duration::from_millis(100));
+            assert!(delay <= (previous * 3).min(Duration::from_millis(1000)));
+            previous = delay;
+        }
+    }
+
+    #[test]
+    fn test_should_retry_respects_error_and_policy() {
+        let peer_addr: SocketAddr = "127.0.0.1:8001".parse().unwrap();
+        let timeout = SolanaNodeError::ReceiveTimeout { peer_addr };
+        let mismatch = SolanaNodeError::NetworkMismatch {
+            expected: "localnet".to_string(),
+            received: "testnet".to_string(),
+        };
+
+        let policy = RetryPolicy::default();
+        assert!(policy.should_retry(&timeout));
+        assert!(!policy.should_retry(&mismatch));
+
+        let policy = RetryPolicy {
+            non_retryable: [ErrorKind::ReceiveTimeout].into_iter().collect(),
+            ..RetryPolicy::default()
+        };
+        assert!(!policy.should_retry(&timeout));
+    }
+
+    #[test]
+    fn test_deadline() {
+        assert!(RetryPolicy::default().within_deadline(Duration::from_secs(3600)));
+
+        let policy = RetryPolicy {
+            deadline: Some(Duration::from_secs(5)),
+            ..RetryPolicy::default()
+        };
+        assert!(policy.within_deadline(Duration::from_secs(4)));
+        assert!(!policy.within_deadline(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_parsing() {
+        assert_eq!("linear".parse::<Backoff>(), Ok(Backoff::Linear));
+        assert_eq!("FIXED".parse::<Backoff>(), Ok(Backoff::Constant));
+        assert!("random".parse::<Backoff>().is_err());
+        assert_eq!("decorrelated".parse::<Jitter>(), Ok(Jitter::Decorrelated));
+        assert_eq!("none".parse::<Jitter>(), Ok(Jitter::None));
+        assert!("half".parse::<Jitter>().is_err());
+        assert_eq!(format!("{}", Jitter::Full), "full");
+        assert_eq!(RetryPolicy::from(5).max_attempts, 5);
+    }
+}
@@ -0,0 +1,116 @@
+//! Traffic counters for the gossip server
+//!
+//! `NodeStats` is a cheap, cloneable handle to a set of atomic counters that
+//! the node updates while it runs, readable from other tasks at any time.
+
+use crate::solana::rate_limit::RateLimitExceeded;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Counters shared between a `GossipNode` and its observers
+#[derive(Debug, Clone, Default)]
+pub struct NodeStats {
+    counters: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    packets_received: AtomicU64,
+    packets_failed: AtomicU64,
+    packets_unknown: AtomicU64,
+    dropped_global_limit: AtomicU64,
+    dropped_ip_limit: AtomicU64,
+    dropped_pubkey_limit: AtomicU64,
+    rejections_suppressed: AtomicU64,
+}
+
+/// Point-in-time copy of `NodeStats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct NodeStatsSnapshot {
+    /// Packets read from the socket, including rate-limited ones
+    pub packets_received: u64,
+
+    /// Packets whose handling failed
+    pub packets_failed: u64,
+
+    /// Packets that matched no known protocol
+    pub packets_unknown: u64,
+
+    /// Packets dropped by the global packets-per-second ceiling
+    pub dropped_global_limit: u64,
+
+    /// Packets dropped by the per-source-IP limit
+    pub dropped_ip_limit: u64,
+
+    /// Handshake requests and responses dropped by the per-pubkey limit
+    pub dropped_pubkey_limit: u64,
+
+    /// Failure responses not sent because the sender was unverified, under
+    /// the reject policy or the limit on such responses
+    pub rejections_suppressed: u64,
+}
+
+impl NodeStatsSnapshot {
+    /// Total packets dropped by any rate limit
+    pub fn rate_limited(&self) -> u64 {
+        self.dropped_global_limit + self.dropped_ip_limit + self.dropped_pubkey_limit
+    }
+}
+
+impl NodeStats {
+    /// Create a set of zeroed counters
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a packet read from the socket
+    pub(crate) fn packet_received(&self) {
+        self.counters
+            .packets_received
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a packet whose handling failed
+    pub(crate) fn packet_failed(&self) {
+        self.counters.packets_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a packet that matched no known protocol
+    pub(crate) fn packet_unknown(&self) {
+        self.counters
+            .packets_unknown
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a packet dropped by a rate limit
+    pub(crate) fn rate_limited(&self, exceeded: RateLimitExceeded) {
+        let counter = match exceeded {
+            RateLimitExceeded::Global => &self.counters.dropped_global_limit,
+            RateLimitExceeded::SourceIp => &self.counters.dropped_ip_limit,
+            RateLimitExceeded::Pubkey => &self.counters.dropped_pubkey_limit,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a failure response that was not sent
+    pub(crate) fn rejection_suppressed(&self) {
+        self.counters
+            .rejections_suppressed
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Current value of every counter
+    pub fn snapshot(&self) -> NodeStatsSnapshot {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        NodeStatsSnapshot {
+            packets_received: load(&self.counters.packets_received),
+            packets_failed: load(&self.counters.packets_failed),
+            packets_unknown: load(&self.counters.packets_unknown),
+            dropped_global_limit: load(&self.counters.dropped_global_limit),
+            dropped_ip_limit: load(&self.counters.dropped_ip_limit),
+            dropped_pubkey_limit: load(&self.counters.dropped_pubkey_limit),
+            rejections_suppressed: load(&self.counters.rejections_suppressed),
+        }
+    }
+}
@@ -0,0 +1,458 @@
+//! Cluster topology reports built from a discovery run
+//!
+//! A `TopologyReport` lists every node a crawl reached, failed to reach or
+//! was told about, with its version, shred version, capabilities and
+//! round-trip time, plus who advertised whom. It can be rendered as JSON,
+//! as CSV with one row per node, and as a Graphviz DOT graph.
+
+use crate::solana::discovery::DiscoveryReport;
+use crate::solana::domain::{CapabilitySet, SolanaNodeError};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::path::Path;
+
+/// File name of the JSON report written by `TopologyReport::write`
+pub const JSON_REPORT_FILE: &str = "topology.json";
+
+/// File name of the CSV report written by `TopologyReport::write`
+pub const CSV_REPORT_FILE: &str = "topology.csv";
+
+/// File name of the Graphviz report written by `TopologyReport::write`
+pub const DOT_REPORT_FILE: &str = "topology.dot";
+
+/// Whether a crawl managed to handshake with a node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reachability {
+    /// The handshake succeeded
+    Reachable,
+    /// The handshake failed
+    Unreachable,
+    /// The node was advertised but not visited because of the crawl limits
+    Unvisited,
+}
+
+impl std::fmt::Display for Reachability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reachability::Reachable => write!(f, "reachable"),
+            Reachability::Unreachable => write!(f, "unreachable"),
+            Reachability::Unvisited => write!(f, "unvisited"),
+        }
+    }
+}
+
+/// One node of the cluster as seen by the crawl
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeRecord {
+    /// Node pubkey in base58, unknown for an entrypoint that could not be reached
+    pub pubkey: Option<String>,
+
+    /// Gossip address the node was handshaked with or advertised at
+    pub gossip_addr: SocketAddr,
+
+    /// Whether the node answered
+    pub reachability: Reachability,
+
+    /// Hops from the entrypoints, unknown for unvisited nodes
+    pub depth: Option<u32>,
+
+    /// Software version reported by the node or its contact info
+    pub version: Option<String>,
+
+    /// Shred version from the node's contact info
+    pub shred_version: Option<u16>,
+
+    /// Capabilities agreed with the node
+    pub capabilities: Option<CapabilitySet>,
+
+    /// Handshake round-trip time in milliseconds
+    pub rtt_ms: Option<f64>,
+
+    /// Why the handshake failed
+    pub error: Option<String>,
+}
+
+/// A node advertising another node's contact info
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct TopologyEdge {
+    /// Pubkey of the advertising node
+    pub from: String,
+
+    /// Pubkey of the advertised node
+    pub to: String,
+}
+
+/// Every node and advertisement found by a crawl
+#[derive(Debug, Clone, Serialize)]
+pub struct TopologyReport {
+    /// Network the crawl ran against
+    pub network_id: String,
+
+    /// When the report was built, in seconds since the Unix epoch
+    pub crawled_at: u64,
+
+    /// Visited nodes in the order they were reached, then unvisited ones
+    pub nodes: Vec<NodeRecord>,
+
+    /// Who advertised whom, without duplicates
+    pub edges: Vec<TopologyEdge>,
+}
+
+impl TopologyReport {
+    /// Build a report from the outcome of `PeerDiscovery::run`
+    pub fn from_discovery(network_id: impl Into<String>, discovery: &DiscoveryReport) -> Self {
+        let mut nodes: Vec<NodeRecord> = discovery
+            .nodes
+            .iter()
+            .map(|node| {
+                let advertised = node.advertised.as_ref();
+                match &node.result {
+                    Ok(details) => {
+                        let peer_info = &details.peer_info;
+                        let version = match peer_info.protocol_version.as_str() {
+                            "unknown" => advertised.and_then(|peer| peer.version.clone()),
+                            version => Some(version.to_string()),
+                        };
+                        NodeRecord {
+                            pubkey: Some(peer_info.peer_pubkey.to_string()),
+                            gossip_addr: node.addr,
+                            reachability: Reachability::Reachable,
+                            depth: Some(node.depth),
+                            version,
+                            shred_version: details
+                                .shred_version
+                                .or(advertised.map(|peer| peer.shred_version)),
+                            capabilities: Some(peer_info.capabilities),
+                            rtt_ms: Some(details.rtt.as_secs_f64() * 1000.0),
+                            error: None,
+                        }
+                    }
+                    Err(e) => NodeRecord {
+                        pubkey: advertised.map(|peer| peer.pubkey.to_string()),
+                        gossip_addr: node.addr,
+                        reachability: Reachability::Unreachable,
+                        depth: Some(node.depth),
+                        version: advertised.and_then(|peer| peer.version.clone()),
+                        shred_version: advertised.map(|peer| peer.shred_version),
+                        capabilities: None,
+                        rtt_ms: None,
+                        error: Some(e.to_string()),
+                    },
+                }
+            })
+            .collect();
+
+        nodes.extend(discovery.unvisited.iter().map(|peer| NodeRecord {
+            pubkey: Some(peer.pubkey.to_string()),
+            gossip_addr: peer.gossip_addr,
+            reachability: Reachability::Unvisited,
+            depth: None,
+            version: peer.version.clone(),
+            shred_version: Some(peer.shred_version),
+            capabilities: None,
+            rtt_ms: None,
+            error: None,
+        }));
+
+        let mut seen = HashSet::new();
+        let edges = discovery
+            .edges
+            .iter()
+            .map(|(from, to)| TopologyEdge {
+                from: from.to_string(),
+                to: to.pubkey.to_string(),
+            })
+            .filter(|edge| seen.insert(edge.clone()))
+            .collect();
+
+        Self {
+            network_id: network_id.into(),
+            crawled_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            nodes,
+            edges,
+        }
+    }
+
+    /// Number of nodes that answered the crawl
+    pub fn reachable_count(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|node| node.reachability == Reachability::Reachable)
+            .count()
+    }
+
+    /// The report as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String, SolanaNodeError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| SolanaNodeError::SerializationFailed(e.to_string()))
+    }
+
+    /// One CSV row per node, with a header row
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "pubkey,gossip_addr,reachability,depth,version,shred_version,capabilities,rtt_ms,error\n",
+        );
+
+        for node in &self.nodes {
+            let fields = [
+                node.pubkey.clone().unwrap_or_default(),
+                node.gossip_addr.to_string(),
+                node.reachability.to_string(),
+                optional(node.depth),
+                node.version.clone().unwrap_or_default(),
+                optional(node.shred_version),
+                optional(node.capabilities),
+                node.rtt_ms
+                    .map(|rtt_ms| format!("{:.3}", rtt_ms))
+                    .unwrap_or_default(),
+                node.error.clone().unwrap_or_default(),
+            ];
+            let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    /// Graphviz DOT graph with an edge from each node to the nodes it advertised
+    ///
+    /// Unreachable nodes are drawn red and unvisited ones dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cluster_topology {\n    node [shape=box];\n");
+
+        for node in &self.nodes {
+            let id = node_id(node);
+            let mut label = format!("{}\\n{}", id, node.gossip_addr);
+            if let Some(version) = &node.version {
+                let _ = write!(label, "\\n{}", version);
+            }
+            let style = match node.reachability {
+                Reachability::Reachable => "",
+                Reachability::Unreachable => ", color=red",
+                Reachability::Unvisited => ", style=dashed",
+            };
+            let _ = writeln!(
+                dot,
+                "    {} [label={}{}];",
+                dot_string(&id),
+                dot_string(&label),
+                style
+            );
+        }
+
+        for edge in &self.edges {
+            let _ = writeln!(
+                dot,
+                "    {} -> {};",
+                dot_string(&edge.from),
+                dot_string(&edge.to)
+            );
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Write the JSON, CSV and DOT reports into `dir`, creating it if needed
+    pub async fn write(&self, dir: &Path) -> Result<(), SolanaNodeError> {
+        let report_error = |path: &Path, e: std::io::Error| SolanaNodeError::ReportError {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        };
+
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| report_error(dir, e))?;
+
+        for (file_name, contents) in [
+            (JSON_REPORT_FILE, self.to_json()?),
+            (CSV_REPORT_FILE, self.to_csv()),
+            (DOT_REPORT_FILE, self.to_dot()),
+        ] {
+            let path = dir.join(file_name);
+            tokio::fs::write(&path, contents)
+                .await
+                .map_err(|e| report_error(&path, e))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Identifier of a node in the DOT graph: its pubkey, or its address if unknown
+fn node_id(node: &NodeRecord) -> String {
+    node.pubkey
+        .clone()
+        .unwrap_or_else(|| node.gossip_addr.to_string())
+}
+
+pub(crate) fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+pub(crate) fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Quote a DOT identifier or label; `\n` escapes in labels are kept as line breaks
+fn dot_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solana::discovery::DiscoveredNode;
+    use crate::solana::domain::{
+        network, AdvertisedPeer, ConnectionStatus, GossipNodeInfo, PeerDetails,
+    };
+    use solana_sdk::pubkey::Pubkey;
+    use std::time::Duration;
+
+    fn advertised(port: u16) -> AdvertisedPeer {
+        AdvertisedPeer {
+            pubkey: Pubkey::new_unique(),
+            gossip_addr: SocketAddr::from(([10, 0, 0, 1], port)),
+            shred_version: 4242,
+            version: Some("1.18.26".to_string()),
+        }
+    }
+
+    fn reachable(addr: SocketAddr, pubkey: Pubkey, advertised: Vec<AdvertisedPeer>) -> PeerDetails {
+        PeerDetails {
+            peer_info: GossipNodeInfo {
+                peer_pubkey: pubkey,
+                protocol_version: "1.18.26".to_string(),
+                network_id: network::DEVNET.to_string(),
+                ..GossipNodeInfo::test_peer(addr, ConnectionStatus::Connected)
+            },
+            shred_version: Some(4242),
+            rtt: Duration::from_millis(25),
+            advertised,
+        }
+    }
+
+    /// Entrypoint advertising a reachable, an unreachable and an unvisited node
+    fn discovery_report() -> DiscoveryReport {
+        let entrypoint_addr = SocketAddr::from(([10, 0, 0, 1], 8001));
+        let entrypoint = Pubkey::new_unique();
+        let (second, failed, unvisited) = (advertised(8002), advertised(8003), advertised(8004));
+        let all = vec![second.clone(), failed.clone(), unvisited.clone()];
+
+        DiscoveryReport {
+            nodes: vec![
+                DiscoveredNode {
+                    addr: entrypoint_addr,
+                    depth: 0,
+                    advertised: None,
+                    result: Ok(reachable(entrypoint_addr, entrypoint, all.clone())),
+                },
+                DiscoveredNode {
+                    addr: second.gossip_addr,
+                    depth: 1,
+                    advertised: Some(second.clone()),
+                    result: Ok(reachable(second.gossip_addr, second.pubkey, Vec::new())),
+                },
+                DiscoveredNode {
+                    addr: failed.gossip_addr,
+                    depth: 1,
+                    advertised: Some(failed.clone()),
+                    result: Err(SolanaNodeError::ReceiveTimeout {
+                        peer_addr: failed.gossip_addr,
+                    }),
+                },
+            ],
+            // The second node is advertised twice
+            edges: all
+                .iter()
+                .chain([&second])
+                .map(|peer| (entrypoint, peer.clone()))
+                .collect(),
+            unvisited: vec![unvisited],
+            depth_reached: 1,
+        }
+    }
+
+    #[test]
+    fn test_from_discovery() {
+        let report = TopologyReport::from_discovery(network::DEVNET, &discovery_report());
+
+        assert_eq!(report.nodes.len(), 4);
+        assert_eq!(report.reachable_count(), 2);
+        assert_eq!(report.edges.len(), 3);
+
+        let entrypoint = &report.nodes[0];
+        assert_eq!(entrypoint.depth, Some(0));
+        assert_eq!(entrypoint.shred_version, Some(4242));
+        assert_eq!(entrypoint.rtt_ms, Some(25.0));
+
+        let failed = &report.nodes[2];
+        assert_eq!(failed.reachability, Reachability::Unreachable);
+        assert_eq!(failed.version.as_deref(), Some("1.18.26"));
+        assert!(failed.error.is_some());
+
+        let unvisited = &report.nodes[3];
+        assert_eq!(unvisited.reachability, Reachability::Unvisited);
+        assert_eq!(unvisited.depth, None);
+    }
+
+    #[test]
+    fn test_csv_output() {
+        let report = TopologyReport::from_discovery(network::DEVNET, &discovery_report());
+        let csv = report.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("pubkey,gossip_addr,reachability"));
+        assert!(lines[1].contains(",reachable,0,1.18.26,4242,\"gossip,handshake\",25.000,"));
+        assert!(lines[4].ends_with(",unvisited,,1.18.26,4242,,,"));
+
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a \"b\", c"), "\"a \"\"b\"\", c\"");
+    }
+
+    #[test]
+    fn test_dot_output() {
+        let report = TopologyReport::from_discovery(network::DEVNET, &discovery_report());
+        let dot = report.to_dot();
+
+        assert!(dot.starts_with("digraph cluster_topology {"));
+        assert_eq!(dot.matches(" -> ").count(), 3);
+        assert_eq!(dot.matches("color=red").count(), 1);
+        assert_eq!(dot.matches("style=dashed").count(), 1);
+        assert!(dot.contains(&format!(
+            "\"{}\" -> \"{}\"",
+            report.edges[0].from, report.edges[0].to
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_write_report_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let report_dir = dir.path().join("devnet");
+        let report = TopologyReport::from_discovery(network::DEVNET, &discovery_report());
+
+        report.write(&report_dir).await.unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(report_dir.join(JSON_REPORT_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(json["network_id"], network::DEVNET);
+        assert_eq!(json["nodes"][0]["reachability"], "reachable");
+        assert_eq!(json["nodes"][0]["capabilities"][0], "gossip");
+        assert!(report_dir.join(CSV_REPORT_FILE).exists());
+        assert!(report_dir.join(DOT_REPORT_FILE).exists());
+    }
+}
@@ -1,16 +1,100 @@
 //! Telemetry and logging infrastructure
 //!
 //! This module provides a structured logging setup using tracing with support
-//! for both development-friendly and production-ready output formats.
+//! for both development-friendly and production-ready output formats, and
+//! optional export of spans to an OpenTelemetry collector over OTLP/HTTP.
 
 use crate::arguments_parser::{LogFormat, LogLevel};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::registry::LookupSpan;
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
+/// Path of the traces endpoint on an OTLP/HTTP collector
+const OTLP_TRACES_PATH: &str = "/v1/traces";
+
+/// Stream log lines are written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogTarget {
+    /// Standard output
+    #[default]
+    Stdout,
+    /// Standard error, keeping stdout free for machine-readable results
+    Stderr,
+}
+
+impl LogTarget {
+    fn make_writer(self) -> BoxMakeWriter {
+        match self {
+            LogTarget::Stdout => BoxMakeWriter::new(std::io::stdout),
+            LogTarget::Stderr => BoxMakeWriter::new(std::io::stderr),
+        }
+    }
+}
+
+/// Collector that spans are exported to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtlpConfig {
+    /// Base URL of the collector's OTLP/HTTP receiver, e.g. `http://localhost:4318`
+    pub endpoint: String,
+
+    /// Service name the spans are reported under
+    pub service_name: String,
+}
+
+impl OtlpConfig {
+    /// Export spans to the collector at `endpoint` as `service_name`
+    pub fn new(endpoint: impl Into<String>, service_name: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            service_name: service_name.into(),
+        }
+    }
+
+    /// URL the spans are posted to
+    pub fn traces_url(&self) -> String {
+        format!(
+            "{}{}",
+            self.endpoint.trim_end_matches('/'),
+            OTLP_TRACES_PATH
+        )
+    }
+}
+
+/// Keeps span export running, and flushes pending spans when dropped
+#[must_use = "pending spans are only flushed to the collector when the guard is dropped"]
+#[derive(Debug, Default)]
+pub struct TelemetryGuard {
+    tracer_provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(tracer_provider) = self.tracer_provider.take() {
+            if let Err(e) = tracer_provider.shutdown() {
+                eprintln!("Failed to flush spans to the OTLP collector: {}", e);
+            }
+        }
+    }
+}
+
 /// Initialize the telemetry and logging infrastructure
 ///
 /// This function sets up structured logging using the tracing ecosystem with
-/// configurable output formats and log levels.
-pub fn setup_telemetry(log_level: LogLevel, log_format: LogFormat) -> Result<(), String> {
+/// configurable output formats and log levels. When `otlp` is given, spans
+/// are also exported to that collector in batches; keep the returned guard
+/// alive until the application exits so the last batch is flushed.
+pub fn setup_telemetry(
+    log_level: LogLevel,
+    log_format: LogFormat,
+    log_target: LogTarget,
+    otlp: Option<&OtlpConfig>,
+) -> Result<TelemetryGuard, String> {
     // Create base filter from log level
     let base_filter = EnvFilter::from_default_env().add_directive(
         format!("p2p_solana_handshake={}", level_to_str(&log_level))
@@ -18,15 +102,59 @@ pub fn setup_telemetry(log_level: LogLevel, log_format: LogFormat) -> Result<(),
             .map_err(|e| format!("Invalid log level directive: {}", e))?,
     );
 
+    let tracer_provider = otlp.map(otlp_tracer_provider).transpose()?;
+
     match log_format {
-        LogFormat::Pretty => setup_pretty_logging(base_filter),
-        LogFormat::Json => setup_json_logging(base_filter),
+        LogFormat::Pretty => {
+            setup_pretty_logging(base_filter, log_target, tracer_provider.as_ref())?
+        }
+        LogFormat::Json => setup_json_logging(base_filter, log_target, tracer_provider.as_ref())?,
     }
+
+    if let Some(config) = otlp {
+        tracing::info!(traces_url = %config.traces_url(), "Exporting spans over OTLP");
+    }
+
+    Ok(TelemetryGuard { tracer_provider })
+}
+
+/// Create a tracer provider exporting spans to the collector in `config`
+///
+/// Spans are batched and posted as OTLP/HTTP JSON from a background thread.
+pub fn otlp_tracer_provider(config: &OtlpConfig) -> Result<SdkTracerProvider, String> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_protocol(Protocol::HttpJson)
+        .with_endpoint(config.traces_url())
+        .build()
+        .map_err(|e| format!("Failed to create OTLP span exporter: {}", e))?;
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build())
+}
+
+/// Layer recording `tracing` spans with a tracer from `tracer_provider`
+pub fn otlp_layer<S>(tracer_provider: &SdkTracerProvider) -> OpenTelemetryLayer<S, SdkTracer>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer().with_tracer(tracer_provider.tracer("p2p_solana_handshake"))
 }
 
 /// Setup pretty-formatted logging for development
-fn setup_pretty_logging(filter: EnvFilter) -> Result<(), String> {
+fn setup_pretty_logging(
+    filter: EnvFilter,
+    log_target: LogTarget,
+    tracer_provider: Option<&SdkTracerProvider>,
+) -> Result<(), String> {
     let formatting_layer = fmt::layer()
+        .with_writer(log_target.make_writer())
         .with_target(true)
         .with_thread_ids(false)
         .with_thread_names(false)
@@ -39,6 +167,7 @@ fn setup_pretty_logging(filter: EnvFilter) -> Result<(), String> {
     tracing_subscriber::registry()
         .with(filter)
         .with(formatting_layer)
+        .with(tracer_provider.map(otlp_layer))
         .try_init()
         .map_err(|e| format!("Failed to initialize pretty logging: {}", e))?;
 
@@ -47,11 +176,15 @@ fn setup_pretty_logging(filter: EnvFilter) -> Result<(), String> {
 }
 
 /// Setup JSON-formatted logging for production
-fn setup_json_logging(filter: EnvFilter) -> Result<(), String> {
+fn setup_json_logging(
+    filter: EnvFilter,
+    log_target: LogTarget,
+    tracer_provider: Option<&SdkTracerProvider>,
+) -> Result<(), String> {
     // Create a Bunyan-style JSON formatter for structured logs
     let bunyan_formatting_layer = tracing_bunyan_formatter::BunyanFormattingLayer::new(
         "p2p_solana_handshake".to_string(),
-        std::io::stdout,
+        log_target.make_writer(),
     );
 
     let json_layer = tracing_bunyan_formatter::JsonStorageLayer;
@@ -60,6 +193,7 @@ fn setup_json_logging(filter: EnvFilter) -> Result<(), String> {
         .with(filter)
         .with(json_layer)
         .with(bunyan_formatting_layer)
+        .with(tracer_provider.map(otlp_layer))
         .try_init()
         .map_err(|e| format!("Failed to initialize JSON logging: {}", e))?;
 
@@ -0,0 +1,322 @@
+use p2p_solana_handshake::solana::{
+    codec::{ClientVersion, ContactInfo, CrdsData, CrdsValue, Ping, Pong, Protocol},
+    network, DiscoveryConfig, HandshakeClient, HandshakeClientBuilder, HandshakeMode,
+    PeerDiscovery, SolanaNodeError,
+};
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::{Keypair, Signer};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+/// How the fake validator answers pings
+#[derive(Clone, Copy)]
+enum PongBehaviour {
+    Honest,
+    WrongHash,
+}
+
+/// Signed gossip-only contact info for `keypair` at `addr`
+fn signed_contact_info(keypair: &Keypair, addr: SocketAddr) -> CrdsValue {
+    let contact_info = ContactInfo::new_gossip_only(
+        keypair.pubkey(),
+        addr,
+        0,
+        ClientVersion {
+            major: 1,
+            minor: 18,
+            patch: 26,
+            commit: None,
+            feature_set: 0,
+            client: 0,
+        },
+    );
+    CrdsValue::new_signed(CrdsData::ContactInfo(contact_info), keypair)
+}
+
+/// Minimal stand-in for a validator's gossip socket
+///
+/// Answers pings, challenges unverified pull requests with a ping of its own
+/// and serves its signed contact info once the requester has ponged.
+async fn spawn_fake_validator(behaviour: PongBehaviour) -> (SocketAddr, Arc<Keypair>) {
+    spawn_fake_validator_knowing(behaviour, Vec::new()).await
+}
+
+/// Fake validator that also serves the contact info of `known` nodes
+async fn spawn_fake_validator_knowing(
+    behaviour: PongBehaviour,
+    known: Vec<(SocketAddr, Arc<Keypair>)>,
+) -> (SocketAddr, Arc<Keypair>) {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let addr = socket.local_addr().unwrap();
+    let keypair = Arc::new(Keypair::new());
+    let identity = keypair.clone();
+
+    tokio::spawn(async move {
+        let mut buffer = vec![0u8; 1280];
+        let mut pending_pings: HashMap<SocketAddr, Ping> = HashMap::new();
+        let mut verified: HashSet<SocketAddr> = HashSet::new();
+
+        loop {
+            let (len, from) = socket.recv_from(&mut buffer).await.unwrap();
+            let reply = match Protocol::decode(&buffer[..len]).unwrap() {
+                Protocol::PingMessage(ping) => {
+                    assert!(ping.verify());
+                    let mut pong = Pong::new(&ping, &identity);
+                    if let PongBehaviour::WrongHash = behaviour {
+                        pong.hash = Hash::new_unique();
+                    }
+                    Protocol::PongMessage(pong)
+                }
+                Protocol::PongMessage(pong) => {
+                    if let Some(ping) = pending_pings.remove(&from) {
+                        if pong.answers(&ping) {
+                            verified.insert(from);
+                        }
+                    }
+                    continue;
+                }
+                Protocol::PullRequest(_, caller) => {
+                    assert!(caller.verify());
+                    if !verified.contains(&from) {
+                        let ping = Ping::new_rand(&identity);
+                        pending_pings.insert(from, ping.clone());
+                        Protocol::PingMessage(ping)
+                    } else {
+                        let mut values = vec![signed_contact_info(&identity, addr)];
+                        values.extend(
+                            known
+                                .iter()
+                                .map(|(addr, keypair)| signed_contact_info(keypair, *addr)),
+                        );
+                        Protocol::PullResponse(identity.pubkey(), values)
+                    }
+                }
+                _ => continue,
+            };
+            socket
+                .send_to(&reply.encode().unwrap(), from)
+                .await
+                .unwrap();
+        }
+    });
+
+    (addr, keypair)
+}
+
+/// Gossip-mode client bound to `bind_addr`, making a single attempt
+fn gossip_client_builder(bind_addr: &str) -> HandshakeClientBuilder {
+    HandshakeClient::builder()
+        .bind_addr(bind_addr)
+        .network_id(network::LOCALNET)
+        .operation_timeout(Duration::from_secs(2))
+        .retry_policy(1)
+        .handshake_mode(HandshakeMode::Gossip)
+}
+
+async fn create_gossip_client() -> HandshakeClient {
+    gossip_client_builder("127.0.0.1:0").build().await.unwrap()
+}
+
+#[tokio::test]
+async fn test_gossip_handshake_with_validator() {
+    let (validator_addr, validator_keypair) = spawn_fake_validator(PongBehaviour::Honest).await;
+    let client = create_gossip_client().await;
+
+    let result = client.perform_handshake(validator_addr).await;
+
+    let peer_info = result.expect("gossip handshake should succeed");
+    assert_eq!(peer_info.peer_pubkey, validator_keypair.pubkey());
+    assert_eq!(peer_info.gossip_addr, validator_addr);
+    assert_eq!(peer_info.protocol_version, "1.18.26");
+}
+
+#[tokio::test]
+async fn test_gossip_handshake_rejects_invalid_pong() {
+    let (validator_addr, _) = spawn_fake_validator(PongBehaviour::WrongHash).await;
+    let client = create_gossip_client().await;
+
+    let result = client.perform_handshake(validator_addr).await;
+
+    assert!(matches!(
+        result,
+        Err(SolanaNodeError::PeerVerificationFailed { peer_addr }) if peer_addr == validator_addr
+    ));
+}
+
+#[tokio::test]
+async fn test_gossip_handshake_timeout_without_validator() {
+    let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let client = gossip_client_builder("127.0.0.1:0")
+        .operation_timeout(Duration::from_millis(200))
+        .build()
+        .await
+        .unwrap();
+
+    let result = client.perform_handshake(silent.local_addr().unwrap()).await;
+
+    assert!(matches!(
+        result,
+        Err(SolanaNodeError::ReceiveTimeout { .. })
+    ));
+}
+
+/// Gossip address the client advertised in the first pull request `validator` receives
+async fn advertised_gossip_addr(client: HandshakeClient, validator: UdpSocket) -> SocketAddr {
+    let validator_addr = validator.local_addr().unwrap();
+    let handshake = tokio::spawn(async move { client.perform_handshake(validator_addr).await });
+
+    let mut buffer = vec![0u8; 1280];
+    let advertised = loop {
+        let (len, _) = validator.recv_from(&mut buffer).await.unwrap();
+        if let Protocol::PullRequest(_, caller) = Protocol::decode(&buffer[..len]).unwrap() {
+            assert!(caller.verify());
+            match caller.data {
+                CrdsData::ContactInfo(info) => break info.gossip().unwrap(),
+                other => panic!("unexpected caller value {:?}", other),
+            }
+        }
+    };
+
+    handshake.abort();
+    advertised
+}
+
+#[tokio::test]
+async fn test_gossip_client_advertises_routable_address() {
+    // Bound to every interface, the client advertises the one that reaches the peer
+    let client = gossip_client_builder("0.0.0.0:0").build().await.unwrap();
+    let client_port = client.local_addr().unwrap().port();
+    let validator = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+    let advertised = advertised_gossip_addr(client, validator).await;
+    assert_eq!(advertised, SocketAddr::from(([127, 0, 0, 1], client_port)));
+
+    // An explicit advertise address wins
+    let public: SocketAddr = "203.0.113.7:8001".parse().unwrap();
+    let client = gossip_client_builder("0.0.0.0:0")
+        .advertise_addr(public)
+        .build()
+        .await
+        .unwrap();
+    let validator = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+    assert_eq!(advertised_gossip_addr(client, validator).await, public);
+}
+
+#[tokio::test]
+async fn test_gossip_client_refuses_to_advertise_unspecified_address() {
+    let validator = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let client = gossip_client_builder("127.0.0.1:0")
+        .advertise_addr("0.0.0.0:8001".parse().unwrap())
+        .build()
+        .await
+        .unwrap();
+
+    let error = client
+        .perform_handshake(validator.local_addr().unwrap())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        error,
+        SolanaNodeError::InvalidAdvertiseAddr { .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_concurrent_gossip_handshake_with_same_peer_is_not_retried() {
+    let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let silent_addr = silent.local_addr().unwrap();
+    let client = gossip_client_builder("127.0.0.1:0")
+        .operation_timeout(Duration::from_millis(300))
+        .retry_policy(2)
+        .build()
+        .await
+        .unwrap();
+
+    let first = client.perform_handshake(silent_addr);
+    let second = async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let started = std::time::Instant::now();
+        (
+            client.perform_handshake(silent_addr).await,
+            started.elapsed(),
+        )
+    };
+    let (_, (second, elapsed)) = tokio::join!(first, second);
+
+    assert!(matches!(
+        second,
+        Err(SolanaNodeError::HandshakeInProgress { peer_addr }) if peer_addr == silent_addr
+    ));
+    // Retrying would have waited a second before the next attempt
+    assert!(elapsed < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn test_discovery_follows_advertised_contact_info() {
+    // Each validator only knows about the next: entrypoint -> middle -> leaf
+    let (leaf_addr, leaf) = spawn_fake_validator(PongBehaviour::Honest).await;
+    let (middle_addr, middle) =
+        spawn_fake_validator_knowing(PongBehaviour::Honest, vec![(leaf_addr, leaf.clone())]).await;
+    let (entrypoint_addr, entrypoint) =
+        spawn_fake_validator_knowing(PongBehaviour::Honest, vec![(middle_addr, middle.clone())])
+            .await;
+    let client = create_gossip_client().await;
+
+    // One hop reaches the middle node but leaves the leaf unvisited
+    let discovery = PeerDiscovery::new(DiscoveryConfig {
+        max_depth: 1,
+        max_peers: 16,
+    });
+    let report = discovery.run(&client, vec![entrypoint_addr]).await;
+
+    let peers: Vec<_> = report.peers().collect();
+    assert_eq!(peers.len(), 2);
+    assert_eq!(peers[0].peer_pubkey, entrypoint.pubkey());
+    assert_eq!(peers[1].peer_pubkey, middle.pubkey());
+    assert_eq!(report.nodes[1].depth, 1);
+    let entrypoint_details = report.nodes[0].result.as_ref().unwrap();
+    assert_eq!(entrypoint_details.shred_version, Some(0));
+    assert!(entrypoint_details.rtt > Duration::ZERO);
+    assert_eq!(report.depth_reached, 1);
+    assert_eq!(report.unvisited.len(), 1);
+    assert_eq!(report.unvisited[0].pubkey, leaf.pubkey());
+    assert_eq!(report.unvisited[0].version.as_deref(), Some("1.18.26"));
+    assert_eq!(report.failures().count(), 0);
+    assert_eq!(report.edges.len(), 2);
+    assert!(discovery.peer_registry().get(&middle_addr).is_some());
+
+    // Two hops reach every node, each exactly once
+    let discovery = PeerDiscovery::new(DiscoveryConfig {
+        max_depth: 2,
+        max_peers: 16,
+    });
+    let report = discovery
+        .run(&client, vec![entrypoint_addr, entrypoint_addr])
+        .await;
+
+    assert_eq!(report.attempted(), 3);
+    assert!(report.unvisited.is_empty());
+    assert_eq!(
+        discovery
+            .peer_registry()
+            .get(&leaf_addr)
+            .unwrap()
+            .peer_pubkey,
+        leaf.pubkey()
+    );
+
+    // The peer limit counts the entrypoints
+    let discovery = PeerDiscovery::new(DiscoveryConfig {
+        max_depth: 2,
+        max_peers: 1,
+    });
+    let report = discovery.run(&client, vec![entrypoint_addr]).await;
+
+    assert_eq!(report.peers().count(), 1);
+    assert_eq!(report.unvisited.len(), 1);
+}
@@ -1,10 +1,26 @@
+use borsh::BorshDeserialize;
+use p2p_solana_handshake::metrics::Metrics;
+use p2p_solana_handshake::solana::handshake_report::EXIT_SOME_FAILED;
 use p2p_solana_handshake::solana::{
+    codec::{Pong, Protocol},
     domain::{network, ConnectionStatus, GossipNodeInfo},
-    GossipNode, HandshakeClient, SolanaNodeError,
+    AccessControl, AccessPolicy, Capability, CapabilitySet, GossipNode, GossipNodeBuilder,
+    HandshakeClient, HandshakeClientBuilder, HandshakeReport, HandshakeRequest, HandshakeResponse,
+    LivenessConfig, OutputFormat, PeerEvent, PeerFilter, PeerRegistry, ProtocolVersion,
+    RateLimitConfig, RejectPolicy, RunSummary, SolanaNodeError, VersionRange,
 };
+use p2p_solana_handshake::solana::{Backoff, ErrorKind, Jitter, RejectReason, RetryPolicy};
+use p2p_solana_handshake::telemetry::{otlp_layer, otlp_tracer_provider, OtlpConfig};
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
 use std::time::Duration;
-use tokio::time::sleep;
+use tokio::net::UdpSocket;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout};
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
 
 /// Helper function to find an available port
 async fn find_available_port() -> u16 {
@@ -16,45 +32,72 @@ async fn find_available_port() -> u16 {
 
 /// Helper function to create a test gossip node
 async fn create_test_gossip_node(network_id: &str) -> (GossipNode, u16) {
-    let port = find_available_port().await;
-    let bind_addr = format!("127.0.0.1:{}", port);
-    let node_pubkey = Pubkey::new_unique();
+    create_test_gossip_node_with(GossipNode::builder().network_id(network_id)).await
+}
 
-    let gossip_node = GossipNode::new(&bind_addr, node_pubkey, network_id.to_string())
-        .await
-        .unwrap();
+/// Helper function to create a test gossip node from a configured builder
+async fn create_test_gossip_node_with(builder: GossipNodeBuilder) -> (GossipNode, u16) {
+    let gossip_node = builder.bind_addr("127.0.0.1:0").build().await.unwrap();
+    let port = gossip_node.local_addr().unwrap().port();
 
     (gossip_node, port)
 }
 
+/// A gossip node running in the background until it is stopped
+struct RunningNode {
+    shutdown_tx: oneshot::Sender<()>,
+    handle: JoinHandle<Result<RunSummary, SolanaNodeError>>,
+}
+
+impl RunningNode {
+    /// Run the node until `stop` is called
+    fn spawn(mut gossip_node: GossipNode) -> Self {
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+        let handle = tokio::spawn(async move {
+            gossip_node
+                .run_until(async {
+                    let _ = shutdown_rx.await;
+                })
+                .await
+        });
+        Self {
+            shutdown_tx,
+            handle,
+        }
+    }
+
+    /// Signal shutdown and wait for the node to drain
+    async fn stop(self) -> RunSummary {
+        let _ = self.shutdown_tx.send(());
+        timeout(Duration::from_secs(5), self.handle)
+            .await
+            .expect("Gossip node should stop promptly")
+            .unwrap()
+            .unwrap()
+    }
+}
+
 /// Helper function to create a test handshake client
 async fn create_test_handshake_client(network_id: &str) -> HandshakeClient {
-    let node_pubkey = Pubkey::new_unique();
-
-    HandshakeClient::with_config(
-        "127.0.0.1:0",
-        node_pubkey,
-        network_id.to_string(),
-        Duration::from_secs(5),
-        2,
-    )
-    .await
-    .unwrap()
+    test_client_builder(network_id).build().await.unwrap()
+}
+
+/// Helper function to configure a test handshake client
+fn test_client_builder(network_id: &str) -> HandshakeClientBuilder {
+    HandshakeClient::builder()
+        .bind_addr("127.0.0.1:0")
+        .network_id(network_id)
+        .operation_timeout(Duration::from_secs(5))
+        .retry_policy(2)
 }
 
 #[tokio::test]
 async fn test_successful_handshake_localnet() {
     // Create a gossip node (server)
-    let (mut gossip_node, server_port) = create_test_gossip_node("localnet").await;
+    let (gossip_node, server_port) = create_test_gossip_node("localnet").await;
 
     // Start the gossip node in the background
-    let server_handle = tokio::spawn(async move {
-        // Run for a short time to handle the handshake
-        tokio::select! {
-            _ = gossip_node.run() => {},
-            _ = sleep(Duration::from_secs(10)) => {} // Timeout after 10 seconds
-        }
-    });
+    let server_handle = RunningNode::spawn(gossip_node);
 
     // Give the server time to start
     sleep(Duration::from_millis(100)).await;
@@ -74,21 +117,16 @@ async fn test_successful_handshake_localnet() {
     assert_eq!(peer_info.gossip_addr, server_addr);
 
     // Clean up
-    server_handle.abort();
+    server_handle.stop().await;
 }
 
 #[tokio::test]
 async fn test_handshake_network_mismatch() {
     // Create a gossip node on a testnet
-    let (mut gossip_node, server_port) = create_test_gossip_node(network::TESTNET).await;
+    let (gossip_node, server_port) = create_test_gossip_node(network::TESTNET).await;
 
     // Start the gossip node in the background
-    let server_handle = tokio::spawn(async move {
-        tokio::select! {
-            _ = gossip_node.run() => {},
-            _ = sleep(Duration::from_secs(10)) => {}
-        }
-    });
+    let server_handle = RunningNode::spawn(gossip_node);
 
     // Give the server time to start
     sleep(Duration::from_millis(100)).await;
@@ -107,34 +145,26 @@ async fn test_handshake_network_mismatch() {
     );
 
     match result.unwrap_err() {
-        SolanaNodeError::HandshakeError { message } => {
-            assert!(message.contains("Network ID mismatch"));
-            assert!(message.contains(network::TESTNET));
-            assert!(message.contains(network::LOCALNET));
+        SolanaNodeError::NetworkMismatch { expected, received } => {
+            assert_eq!(expected, network::LOCALNET);
+            assert_eq!(received, network::TESTNET);
         }
-        other => panic!(
-            "Expected HandshakeError with network mismatch, got: {:?}",
-            other
-        ),
+        other => panic!("Expected NetworkMismatch, got: {:?}", other),
     }
 
     // Clean up
-    server_handle.abort();
+    server_handle.stop().await;
 }
 
 #[tokio::test]
 async fn test_handshake_timeout() {
     // Create a handshake client with a very short timeout
-    let node_pubkey = Pubkey::new_unique();
-    let client = HandshakeClient::with_config(
-        "127.0.0.1:0",
-        node_pubkey,
-        network::LOCALNET.to_string(),
-        Duration::from_millis(100), // Very short timeout
-        1,                          // Only 1 retry
-    )
-    .await
-    .unwrap();
+    let client = test_client_builder(network::LOCALNET)
+        .operation_timeout(Duration::from_millis(100)) // Very short timeout
+        .retry_policy(1) // Only 1 attempt
+        .build()
+        .await
+        .unwrap();
 
     // Try to connect to a non-existent server
     let non_existent_addr = "127.0.0.1:9999".parse().unwrap();
@@ -142,21 +172,25 @@ async fn test_handshake_timeout() {
 
     // Verify timeout error
     assert!(result.is_err(), "Handshake should timeout");
-    assert!(matches!(result.unwrap_err(), SolanaNodeError::TimeoutError));
+    assert!(matches!(
+        result.unwrap_err(),
+        SolanaNodeError::ReceiveTimeout { peer_addr } if peer_addr == non_existent_addr
+    ));
 }
 
 #[tokio::test]
 async fn test_handshake_multiple_retries() {
-    let node_pubkey = Pubkey::new_unique();
-    let client = HandshakeClient::with_config(
-        "127.0.0.1:0",
-        node_pubkey,
-        network::LOCALNET.to_string(),
-        Duration::from_millis(500),
-        3, // 3 retries
-    )
-    .await
-    .unwrap();
+    let client = test_client_builder(network::LOCALNET)
+        .operation_timeout(Duration::from_millis(500))
+        // 3 attempts, with the backoff delays unrandomized
+        .retry_policy(RetryPolicy {
+            max_attempts: 3,
+            jitter: Jitter::None,
+            ..RetryPolicy::default()
+        })
+        .build()
+        .await
+        .unwrap();
 
     let start_time = std::time::Instant::now();
 
@@ -180,23 +214,13 @@ async fn test_handshake_multiple_retries() {
 #[tokio::test]
 async fn test_batch_handshakes() {
     // Create multiple gossip nodes
-    let (mut node1, port1) = create_test_gossip_node(network::LOCALNET).await;
-    let (mut node2, port2) = create_test_gossip_node(network::LOCALNET).await;
+    let (node1, port1) = create_test_gossip_node(network::LOCALNET).await;
+    let (node2, port2) = create_test_gossip_node(network::LOCALNET).await;
 
     // Start both nodes
-    let handle1 = tokio::spawn(async move {
-        tokio::select! {
-            _ = node1.run() => {},
-            _ = sleep(Duration::from_secs(10)) => {}
-        }
-    });
+    let handle1 = RunningNode::spawn(node1);
 
-    let handle2 = tokio::spawn(async move {
-        tokio::select! {
-            _ = node2.run() => {},
-            _ = sleep(Duration::from_secs(10)) => {}
-        }
-    });
+    let handle2 = RunningNode::spawn(node2);
 
     // Give servers time to start
     sleep(Duration::from_millis(100)).await;
@@ -229,37 +253,28 @@ async fn test_batch_handshakes() {
     }
 
     // Clean up
-    handle1.abort();
-    handle2.abort();
+    handle1.stop().await;
+    handle2.stop().await;
 }
 
 #[tokio::test]
 async fn test_batch_handshakes_mixed_results() {
     // Create one working gossip node
-    let (mut gossip_node, working_port) = create_test_gossip_node(network::LOCALNET).await;
+    let (gossip_node, working_port) = create_test_gossip_node(network::LOCALNET).await;
 
     // Start the working node
-    let handle = tokio::spawn(async move {
-        tokio::select! {
-            _ = gossip_node.run() => {},
-            _ = sleep(Duration::from_secs(10)) => {}
-        }
-    });
+    let handle = RunningNode::spawn(gossip_node);
 
     // Give server time to start
     sleep(Duration::from_millis(100)).await;
 
     // Create client with short timeout for faster test
-    let node_pubkey = Pubkey::new_unique();
-    let client = HandshakeClient::with_config(
-        "127.0.0.1:0",
-        node_pubkey,
-        network::LOCALNET.to_string(),
-        Duration::from_millis(500),
-        1,
-    )
-    .await
-    .unwrap();
+    let client = test_client_builder(network::LOCALNET)
+        .operation_timeout(Duration::from_millis(500))
+        .retry_policy(1)
+        .build()
+        .await
+        .unwrap();
 
     // Try handshakes with one working and one non-existent server
     let peer_addrs = vec![
@@ -279,12 +294,12 @@ async fn test_batch_handshakes_mixed_results() {
     assert_eq!(failed_count, 1, "One handshake should fail");
 
     // Clean up
-    handle.abort();
+    handle.stop().await;
 }
 
 #[tokio::test]
 async fn test_gossip_node_peer_registry() {
-    let (mut gossip_node, _) = create_test_gossip_node(network::LOCALNET).await;
+    let (gossip_node, _) = create_test_gossip_node(network::LOCALNET).await;
 
     // Initially no peers
     assert_eq!(gossip_node.get_peers().len(), 0);
@@ -294,8 +309,9 @@ async fn test_gossip_node_peer_registry() {
         peer_pubkey: Pubkey::new_unique(),
         gossip_addr: "127.0.0.1:8001".parse().unwrap(),
         protocol_version: "1.18.0".to_string(),
+        negotiated_version: None,
         network_id: network::LOCALNET.to_string(),
-        capabilities: vec!["gossip".to_string()],
+        capabilities: Capability::Gossip.into(),
         last_seen: std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
@@ -319,14 +335,9 @@ async fn test_gossip_node_peer_registry() {
 async fn test_different_network_types() {
     for network_id in [network::LOCALNET, network::TESTNET] {
         // Create a gossip node and client for the same network
-        let (mut gossip_node, server_port) = create_test_gossip_node(network_id).await;
+        let (gossip_node, server_port) = create_test_gossip_node(network_id).await;
 
-        let server_handle = tokio::spawn(async move {
-            tokio::select! {
-                _ = gossip_node.run() => {},
-                _ = sleep(Duration::from_secs(10)) => {}
-            }
-        });
+        let server_handle = RunningNode::spawn(gossip_node);
 
         sleep(Duration::from_millis(100)).await;
 
@@ -345,7 +356,1470 @@ async fn test_different_network_types() {
         let peer_info = result.unwrap();
         assert_eq!(peer_info.network_id, network_id);
 
-        server_handle.abort();
+        server_handle.stop().await;
         sleep(Duration::from_millis(100)).await; // Small delay between tests
     }
 }
+
+#[tokio::test]
+async fn test_gossip_node_requires_pong_before_responding() {
+    let (gossip_node, server_port) = create_test_gossip_node(network::LOCALNET).await;
+    let node_pubkey = gossip_node.node_pubkey();
+
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+
+    let peer = Keypair::new();
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let server_addr = format!("127.0.0.1:{}", server_port);
+    let mut request = HandshakeRequest::new_local(peer.pubkey());
+    request.sign(&peer);
+    socket
+        .send_to(&borsh::to_vec(&request).unwrap(), &server_addr)
+        .await
+        .unwrap();
+
+    // The node answers an unverified request with a ping challenge
+    let mut buffer = vec![0u8; 1280];
+    let (len, _) = socket.recv_from(&mut buffer).await.unwrap();
+    let ping = match Protocol::decode(&buffer[..len]).unwrap() {
+        Protocol::PingMessage(ping) => ping,
+        other => panic!("Expected ping challenge, got: {:?}", other),
+    };
+    assert_eq!(ping.from, node_pubkey);
+    assert!(ping.verify());
+
+    // A pong signed by a different key is ignored
+    let forged = Pong::new(&ping, &Keypair::new());
+    socket
+        .send_to(
+            &Protocol::PongMessage(forged).encode().unwrap(),
+            &server_addr,
+        )
+        .await
+        .unwrap();
+    let no_reply = timeout(Duration::from_millis(300), socket.recv_from(&mut buffer)).await;
+    assert!(
+        no_reply.is_err(),
+        "Forged pong must not complete the handshake"
+    );
+
+    // The genuine pong completes the handshake
+    let pong = Pong::new(&ping, &peer);
+    socket
+        .send_to(&Protocol::PongMessage(pong).encode().unwrap(), &server_addr)
+        .await
+        .unwrap();
+    let (len, _) = timeout(Duration::from_secs(2), socket.recv_from(&mut buffer))
+        .await
+        .expect("Handshake response should follow a valid pong")
+        .unwrap();
+    let response = HandshakeResponse::try_from_slice(&buffer[..len]).unwrap();
+    assert!(response.success);
+    assert_eq!(response.peer_pubkey, node_pubkey);
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_gossip_node_ignores_spoofed_request() {
+    let (gossip_node, server_port) = create_test_gossip_node(network::LOCALNET).await;
+
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+
+    // Claim an identity we do not hold the key for
+    let attacker = Keypair::new();
+    let mut request = HandshakeRequest::new_local(Pubkey::new_unique());
+    request.sign(&attacker);
+
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    socket
+        .send_to(
+            &borsh::to_vec(&request).unwrap(),
+            format!("127.0.0.1:{}", server_port),
+        )
+        .await
+        .unwrap();
+
+    let mut buffer = vec![0u8; 1280];
+    let reply = timeout(Duration::from_millis(500), socket.recv_from(&mut buffer)).await;
+    assert!(reply.is_err(), "Spoofed request must not be answered");
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_client_rejects_unsigned_response() {
+    // A fake server that answers with a response it did not sign
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let server_addr = server.local_addr().unwrap();
+
+    let server_handle = tokio::spawn(async move {
+        let mut buffer = vec![0u8; 1280];
+        let (len, client_addr) = server.recv_from(&mut buffer).await.unwrap();
+        let request = HandshakeRequest::try_from_slice(&buffer[..len]).unwrap();
+        assert!(request.verify_signature());
+
+        let response = HandshakeResponse {
+            peer_pubkey: Pubkey::new_unique(),
+            protocol_version: request.supported_versions.max,
+            supported_versions: request.supported_versions,
+            network_id: request.network_id,
+            gossip_addr: server_addr,
+            capabilities: request.capabilities,
+            timestamp: request.timestamp,
+            success: true,
+            error_message: None,
+            reject_reason: None,
+            nonce: request.nonce,
+            signature: Default::default(),
+        };
+        server
+            .send_to(&borsh::to_vec(&response).unwrap(), client_addr)
+            .await
+            .unwrap();
+    });
+
+    let client = create_test_handshake_client(network::LOCALNET).await;
+    let result = client.perform_handshake(server_addr).await;
+
+    assert!(matches!(
+        result,
+        Err(SolanaNodeError::SignatureInvalid { peer_addr }) if peer_addr == server_addr
+    ));
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn test_client_reports_undecodable_response() {
+    // A fake server that answers with a response cut short after the pubkey
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let server_addr = server.local_addr().unwrap();
+
+    let server_handle = tokio::spawn(async move {
+        let mut buffer = vec![0u8; 1280];
+        let (_, client_addr) = server.recv_from(&mut buffer).await.unwrap();
+        let truncated = Pubkey::new_unique().to_bytes();
+        server.send_to(&truncated, client_addr).await.unwrap();
+    });
+
+    let client = test_client_builder(network::LOCALNET)
+        .operation_timeout(Duration::from_secs(5))
+        .retry_policy(1)
+        .build()
+        .await
+        .unwrap();
+    let result = timeout(
+        Duration::from_secs(1),
+        client.perform_handshake(server_addr),
+    )
+    .await
+    .expect("Undecodable response should fail without waiting for the timeout");
+
+    let error = result.unwrap_err();
+    assert!(matches!(
+        error,
+        SolanaNodeError::DecodeError { offset: 32, .. }
+    ));
+    assert_eq!(error.kind(), ErrorKind::Decode);
+
+    server_handle.abort();
+}
+
+#[tokio::test]
+async fn test_client_discards_mismatched_responses() {
+    // A fake server that first delivers a stale reply and a reply from another
+    // address, both validly signed, before answering the actual request
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let server_addr = server.local_addr().unwrap();
+    let stray = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let server_identity = Keypair::new();
+
+    let server_handle = tokio::spawn(async move {
+        let mut buffer = vec![0u8; 1280];
+        let (len, client_addr) = server.recv_from(&mut buffer).await.unwrap();
+        let request = HandshakeRequest::try_from_slice(&buffer[..len]).unwrap();
+
+        let make_response = |nonce: u64| {
+            let mut response = HandshakeResponse {
+                peer_pubkey: server_identity.pubkey(),
+                protocol_version: request.supported_versions.max,
+                supported_versions: request.supported_versions,
+                network_id: request.network_id.clone(),
+                gossip_addr: server_addr,
+                capabilities: request.capabilities,
+                timestamp: request.timestamp,
+                success: true,
+                error_message: None,
+                reject_reason: None,
+                nonce,
+                signature: Default::default(),
+            };
+            response.sign(&server_identity);
+            borsh::to_vec(&response).unwrap()
+        };
+
+        let stale = make_response(request.nonce.wrapping_add(1));
+        server.send_to(&stale, client_addr).await.unwrap();
+
+        let misrouted = make_response(request.nonce);
+        stray.send_to(&misrouted, client_addr).await.unwrap();
+
+        // Give the client a chance to see the bogus packets first
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        server
+            .send_to(&make_response(request.nonce), client_addr)
+            .await
+            .unwrap();
+    });
+
+    let client = create_test_handshake_client(network::LOCALNET).await;
+    let peer_info = client.perform_handshake(server_addr).await.unwrap();
+
+    assert_eq!(peer_info.gossip_addr, server_addr);
+    assert_eq!(client.discarded_packets(), 2);
+
+    server_handle.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_batch_handshakes_run_concurrently() {
+    // Peers that never answer; run one after another this would take 10 timeouts
+    let mut silent_peers = Vec::new();
+    let mut peer_addrs = Vec::new();
+    for _ in 0..10 {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        peer_addrs.push(socket.local_addr().unwrap());
+        silent_peers.push(socket);
+    }
+
+    let client = test_client_builder(network::LOCALNET)
+        .operation_timeout(Duration::from_millis(500))
+        .retry_policy(1)
+        .concurrency(10)
+        .build()
+        .await
+        .unwrap();
+
+    let start = std::time::Instant::now();
+    let results = client.perform_batch_handshakes(peer_addrs.clone()).await;
+    let elapsed = start.elapsed();
+
+    assert_eq!(results.len(), 10);
+    assert!(results
+        .iter()
+        .all(|(_, result)| matches!(result, Err(SolanaNodeError::ReceiveTimeout { .. }))));
+    // Results are reported in the order the peers were given
+    let reported: Vec<_> = results.iter().map(|(addr, _)| *addr).collect();
+    assert_eq!(reported, peer_addrs);
+    assert!(
+        elapsed < Duration::from_secs(2),
+        "Batch took {:?}, handshakes did not overlap",
+        elapsed
+    );
+}
+
+/// Send `request` and complete the node's ping challenge, returning the response
+async fn handshake_over_raw_socket(
+    socket: &UdpSocket,
+    server_addr: &str,
+    peer: &Keypair,
+    request: &HandshakeRequest,
+) -> HandshakeResponse {
+    socket
+        .send_to(&borsh::to_vec(request).unwrap(), server_addr)
+        .await
+        .unwrap();
+
+    let mut buffer = vec![0u8; 1280];
+    loop {
+        let (len, _) = timeout(Duration::from_secs(2), socket.recv_from(&mut buffer))
+            .await
+            .expect("Node should answer the request")
+            .unwrap();
+        if let Ok(response) = HandshakeResponse::try_from_slice(&buffer[..len]) {
+            return response;
+        }
+        if let Ok(Protocol::PingMessage(ping)) = Protocol::decode(&buffer[..len]) {
+            let pong = Pong::new(&ping, peer);
+            socket
+                .send_to(&Protocol::PongMessage(pong).encode().unwrap(), server_addr)
+                .await
+                .unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_gossip_node_rejects_replayed_request() {
+    let (gossip_node, server_port) = create_test_gossip_node(network::LOCALNET).await;
+
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+
+    let peer = Keypair::new();
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let server_addr = format!("127.0.0.1:{}", server_port);
+    let mut request = HandshakeRequest::new_local(peer.pubkey());
+    request.sign(&peer);
+
+    let response = handshake_over_raw_socket(&socket, &server_addr, &peer, &request).await;
+    assert!(response.success);
+
+    // The same captured packet is refused the second time
+    let replayed = handshake_over_raw_socket(&socket, &server_addr, &peer, &request).await;
+    assert!(!replayed.success);
+    assert_eq!(replayed.nonce, request.nonce);
+    assert!(replayed.verify_signature());
+    assert!(replayed.error_message.unwrap().contains("was already used"));
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_gossip_node_rejects_stale_request() {
+    let (gossip_node, server_port) =
+        create_test_gossip_node_with(GossipNode::builder().replay_window(Duration::from_secs(30)))
+            .await;
+
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+
+    let peer = Keypair::new();
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let server_addr = format!("127.0.0.1:{}", server_port);
+    let mut request = HandshakeRequest::new_local(peer.pubkey());
+    request.timestamp -= 60;
+    request.sign(&peer);
+
+    let response = handshake_over_raw_socket(&socket, &server_addr, &peer, &request).await;
+    assert!(!response.success);
+    assert!(response
+        .error_message
+        .unwrap()
+        .contains("outside the acceptance window"));
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_protocol_version_negotiation() {
+    let v = ProtocolVersion::new;
+    let (gossip_node, server_port) = create_test_gossip_node_with(
+        GossipNode::builder().supported_versions(VersionRange::new(v(1, 16, 0), v(1, 17, 4))),
+    )
+    .await;
+
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+    let server_addr = format!("127.0.0.1:{}", server_port).parse().unwrap();
+
+    // The highest version both sides support is chosen
+    let client = create_test_handshake_client(network::LOCALNET).await;
+    let peer_info = client.perform_handshake(server_addr).await.unwrap();
+    assert_eq!(peer_info.negotiated_version, Some(v(1, 17, 4)));
+    assert_eq!(peer_info.protocol_version, "1.17.4");
+
+    // Without a common version the handshake fails without retrying
+    let client = test_client_builder(network::LOCALNET)
+        .supported_versions(VersionRange::new(v(1, 18, 0), v(2, 0, 0)))
+        .build()
+        .await
+        .unwrap();
+    let result = client.perform_handshake(server_addr).await;
+    assert!(
+        matches!(result, Err(SolanaNodeError::ProtocolVersionMismatch { .. })),
+        "Expected ProtocolVersionMismatch, got: {:?}",
+        result
+    );
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_capability_negotiation() {
+    let (gossip_node, server_port) = create_test_gossip_node_with(
+        GossipNode::builder().supported_capabilities(Capability::Gossip.into()),
+    )
+    .await;
+
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+    let server_addr = format!("127.0.0.1:{}", server_port).parse().unwrap();
+
+    // Only capabilities both sides support are agreed
+    let client = create_test_handshake_client(network::LOCALNET).await;
+    let peer_info = client.perform_handshake(server_addr).await.unwrap();
+    assert_eq!(
+        peer_info.capabilities,
+        CapabilitySet::from(Capability::Gossip)
+    );
+
+    // Requiring a capability the node does not support fails the handshake
+    let client = test_client_builder(network::LOCALNET)
+        .required_capabilities(Capability::Handshake.into())
+        .build()
+        .await
+        .unwrap();
+    let result = client.perform_handshake(server_addr).await;
+    match result {
+        Err(SolanaNodeError::MissingCapabilities { missing }) => {
+            assert_eq!(missing, CapabilitySet::from(Capability::Handshake));
+        }
+        other => panic!("Expected MissingCapabilities, got: {:?}", other),
+    }
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_builder_offers_required_capabilities() {
+    let (gossip_node, server_port) = create_test_gossip_node(network::LOCALNET).await;
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+    let server_addr = format!("127.0.0.1:{}", server_port).parse().unwrap();
+
+    // A required capability left out of the offered set is offered anyway
+    let client = HandshakeClient::builder()
+        .bind_addr("127.0.0.1:0")
+        .required_capabilities(Capability::Handshake.into())
+        .capabilities(Capability::Gossip.into())
+        .operation_timeout(Duration::from_secs(2))
+        .retry_policy(1)
+        .build()
+        .await
+        .unwrap();
+    let peer_info = client.perform_handshake(server_addr).await.unwrap();
+    assert!(peer_info.capabilities.contains(Capability::Handshake));
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_gossip_node_requires_capabilities() {
+    let (gossip_node, server_port) = create_test_gossip_node_with(
+        GossipNode::builder().required_capabilities(Capability::Handshake.into()),
+    )
+    .await;
+
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+
+    let peer = Keypair::new();
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let server_addr = format!("127.0.0.1:{}", server_port);
+    let mut request = HandshakeRequest::new_local(peer.pubkey());
+    request.capabilities = Capability::Gossip.into();
+    request.sign(&peer);
+
+    let response = handshake_over_raw_socket(&socket, &server_addr, &peer, &request).await;
+    assert!(!response.success);
+    assert!(response
+        .error_message
+        .unwrap()
+        .contains("Missing required capabilities: handshake"));
+
+    // The rejected request counts as a failed packet
+    let summary = server_handle.stop().await;
+    assert_eq!(summary.packets_failed, 1);
+}
+
+/// A successful localnet response signed by `peer`, as if answering a request
+fn unsolicited_response(
+    peer: &Keypair,
+    socket: &UdpSocket,
+    capabilities: CapabilitySet,
+) -> HandshakeResponse {
+    let versions = VersionRange::default();
+    let mut response = HandshakeResponse {
+        peer_pubkey: peer.pubkey(),
+        protocol_version: versions.max,
+        supported_versions: versions,
+        network_id: network::LOCALNET.to_string(),
+        gossip_addr: socket.local_addr().unwrap(),
+        capabilities,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        success: true,
+        error_message: None,
+        reject_reason: None,
+        nonce: rand::random(),
+        signature: Default::default(),
+    };
+    response.sign(peer);
+    response
+}
+
+#[tokio::test]
+async fn test_gossip_node_ignores_response_without_required_capabilities() {
+    let gossip_node = GossipNode::builder()
+        .bind_addr("127.0.0.1:0")
+        .required_capabilities(Capability::Handshake.into())
+        .build()
+        .await
+        .unwrap();
+    let server_addr = gossip_node.local_addr().unwrap();
+    let registry = gossip_node.peer_registry();
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    // An unsolicited, signed response offering only gossip
+    let peer = Keypair::new();
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let response = unsolicited_response(&peer, &socket, Capability::Gossip.into());
+    socket
+        .send_to(&borsh::to_vec(&response).unwrap(), server_addr)
+        .await
+        .unwrap();
+
+    // Answer any ping challenge, as a real peer would
+    let mut buffer = vec![0u8; 1280];
+    while let Ok(received) =
+        timeout(Duration::from_millis(500), socket.recv_from(&mut buffer)).await
+    {
+        let (len, _) = received.unwrap();
+        if let Ok(Protocol::PingMessage(ping)) = Protocol::decode(&buffer[..len]) {
+            let pong = Pong::new(&ping, &peer);
+            socket
+                .send_to(&Protocol::PongMessage(pong).encode().unwrap(), server_addr)
+                .await
+                .unwrap();
+        }
+    }
+
+    assert!(registry.is_empty());
+    let summary = server_handle.stop().await;
+    assert_eq!(summary.packets_failed, 1);
+}
+
+#[tokio::test]
+async fn test_peer_registry_observable_while_running() {
+    let (gossip_node, server_port) = create_test_gossip_node(network::LOCALNET).await;
+    let registry = gossip_node.peer_registry();
+
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+    assert!(registry.is_empty());
+
+    let client = create_test_handshake_client(network::LOCALNET).await;
+    let client_pubkey = client.node_pubkey();
+    let client_addr = client.local_addr().unwrap();
+    let server_addr = format!("127.0.0.1:{}", server_port).parse().unwrap();
+    client.perform_handshake(server_addr).await.unwrap();
+
+    // The running node's registry is visible from this task
+    let connected = registry.filter(
+        &PeerFilter::default()
+            .with_status(ConnectionStatus::Connected)
+            .with_network(network::LOCALNET)
+            .with_capabilities(Capability::Handshake.into()),
+    );
+    assert_eq!(connected.len(), 1);
+    assert_eq!(connected[0].peer_pubkey, client_pubkey);
+    assert_eq!(
+        registry.get(&client_addr).unwrap().peer_pubkey,
+        client_pubkey
+    );
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_gossip_node_expires_unresponsive_peers() {
+    let (gossip_node, server_port) =
+        create_test_gossip_node_with(GossipNode::builder().liveness(LivenessConfig {
+            probe_interval: Duration::from_millis(200),
+            max_missed_probes: 2,
+            peer_ttl: Duration::from_secs(1),
+        }))
+        .await;
+    let registry = gossip_node.peer_registry();
+
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+    let server_addr = format!("127.0.0.1:{}", server_port);
+
+    // A peer that keeps answering probes
+    let live_peer = Keypair::new();
+    let live_socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let live_addr = live_socket.local_addr().unwrap();
+    let mut request = HandshakeRequest::new_local(live_peer.pubkey());
+    request.sign(&live_peer);
+    let response =
+        handshake_over_raw_socket(&live_socket, &server_addr, &live_peer, &request).await;
+    assert!(response.success);
+
+    let responder = tokio::spawn(async move {
+        let mut buffer = vec![0u8; 1280];
+        loop {
+            let (len, from) = live_socket.recv_from(&mut buffer).await.unwrap();
+            if let Ok(Protocol::PingMessage(ping)) = Protocol::decode(&buffer[..len]) {
+                let pong = Pong::new(&ping, &live_peer);
+                live_socket
+                    .send_to(&Protocol::PongMessage(pong).encode().unwrap(), from)
+                    .await
+                    .unwrap();
+            }
+        }
+    });
+
+    // A peer that completed its handshake and then went silent
+    let client = create_test_handshake_client(network::LOCALNET).await;
+    let silent_addr = client.local_addr().unwrap();
+    client
+        .perform_handshake(server_addr.parse().unwrap())
+        .await
+        .unwrap();
+    drop(client);
+    assert_eq!(registry.len(), 2);
+
+    // Two missed probes mark the silent peer disconnected
+    let mut disconnected = false;
+    for _ in 0..20 {
+        sleep(Duration::from_millis(100)).await;
+        if registry
+            .get(&silent_addr)
+            .is_some_and(|peer| peer.connection_status == ConnectionStatus::Disconnected)
+        {
+            disconnected = true;
+            break;
+        }
+    }
+    assert!(disconnected, "Silent peer should be marked disconnected");
+
+    // After the TTL it is purged, while the responsive peer stays connected
+    let mut expired = false;
+    for _ in 0..40 {
+        sleep(Duration::from_millis(100)).await;
+        if registry.get(&silent_addr).is_none() {
+            expired = true;
+            break;
+        }
+    }
+    assert!(expired, "Silent peer should be removed after its TTL");
+
+    let live_info = registry.get(&live_addr).unwrap();
+    assert_eq!(live_info.connection_status, ConnectionStatus::Connected);
+
+    responder.abort();
+    server_handle.stop().await;
+}
+
+/// Wait for the next event published by a gossip node
+async fn next_event(events: &mut tokio::sync::broadcast::Receiver<PeerEvent>) -> PeerEvent {
+    timeout(Duration::from_secs(2), events.recv())
+        .await
+        .expect("Event expected")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_gossip_node_publishes_peer_events() {
+    let (gossip_node, server_port) = create_test_gossip_node(network::LOCALNET).await;
+    let mut events = gossip_node.subscribe();
+
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+    let server_addr = format!("127.0.0.1:{}", server_port);
+
+    // A successful handshake is received, then registered
+    let client = create_test_handshake_client(network::LOCALNET).await;
+    let client_addr = client.local_addr().unwrap();
+    client
+        .perform_handshake(server_addr.parse().unwrap())
+        .await
+        .unwrap();
+
+    match next_event(&mut events).await {
+        PeerEvent::HandshakeReceived {
+            peer_addr,
+            peer_pubkey,
+        } => {
+            assert_eq!(peer_addr, client_addr);
+            assert_eq!(peer_pubkey, client.node_pubkey());
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+    match next_event(&mut events).await {
+        PeerEvent::PeerRegistered { peer_info } => {
+            assert_eq!(peer_info.gossip_addr, client_addr);
+            assert_eq!(peer_info.connection_status, ConnectionStatus::Connected);
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+
+    // A second handshake from the same peer updates it
+    client
+        .perform_handshake(server_addr.parse().unwrap())
+        .await
+        .unwrap();
+    assert!(matches!(
+        next_event(&mut events).await,
+        PeerEvent::HandshakeReceived { .. }
+    ));
+    match next_event(&mut events).await {
+        PeerEvent::PeerUpdated {
+            peer_info,
+            previous_status,
+        } => {
+            assert_eq!(peer_info.gossip_addr, client_addr);
+            assert_eq!(previous_status, ConnectionStatus::Connected);
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let socket_addr = socket.local_addr().unwrap();
+
+    // Garbage is reported as an unknown packet
+    socket.send_to(&[0xff; 3], &server_addr).await.unwrap();
+    match next_event(&mut events).await {
+        PeerEvent::UnknownPacket {
+            sender_addr,
+            packet_size,
+        } => {
+            assert_eq!(sender_addr, socket_addr);
+            assert_eq!(packet_size, 3);
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+
+    // A request signed by the wrong key fails verification
+    let mut request = HandshakeRequest::new_local(Pubkey::new_unique());
+    request.sign(&Keypair::new());
+    socket
+        .send_to(&borsh::to_vec(&request).unwrap(), &server_addr)
+        .await
+        .unwrap();
+    let event = next_event(&mut events).await;
+    assert!(
+        matches!(event, PeerEvent::PeerVerificationFailed { peer_addr, .. } if peer_addr == socket_addr),
+        "Unexpected event: {:?}",
+        event
+    );
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_gossip_node_shuts_down_on_signal() {
+    let (mut gossip_node, server_port) = create_test_gossip_node(network::LOCALNET).await;
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+
+    let server_handle = tokio::spawn(async move {
+        gossip_node
+            .run_until(async {
+                let _ = shutdown_rx.await;
+            })
+            .await
+    });
+
+    sleep(Duration::from_millis(100)).await;
+
+    let client = create_test_handshake_client(network::LOCALNET).await;
+    client
+        .perform_handshake(format!("127.0.0.1:{}", server_port).parse().unwrap())
+        .await
+        .unwrap();
+
+    shutdown_tx.send(()).unwrap();
+    let summary = timeout(Duration::from_secs(2), server_handle)
+        .await
+        .expect("Gossip node should stop promptly")
+        .unwrap()
+        .unwrap();
+
+    // A handshake request and the pong answering our ping challenge
+    assert!(summary.packets_received >= 2);
+    assert_eq!(summary.packets_failed, 0);
+    assert_eq!(summary.abandoned_handshakes, 0);
+    assert_eq!(summary.peers.len(), 1);
+    assert_eq!(summary.peers[0].peer_pubkey, client.node_pubkey());
+}
+
+#[tokio::test]
+async fn test_gossip_node_handles_packets_concurrently() {
+    let (mut gossip_node, server_port) =
+        create_test_gossip_node_with(GossipNode::builder().workers(8).queue_depth(16)).await;
+    let registry = gossip_node.peer_registry();
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+
+    let server_handle = tokio::spawn(async move {
+        gossip_node
+            .run_until(async {
+                let _ = shutdown_rx.await;
+            })
+            .await
+    });
+
+    sleep(Duration::from_millis(100)).await;
+    let server_addr = format!("127.0.0.1:{}", server_port);
+
+    // More simultaneous handshakes than workers and queue slots combined
+    let handshakes = (0..100).map(|_| {
+        let server_addr = server_addr.clone();
+        async move {
+            let peer = Keypair::new();
+            let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let mut request = HandshakeRequest::new_local(peer.pubkey());
+            request.sign(&peer);
+            handshake_over_raw_socket(&socket, &server_addr, &peer, &request).await
+        }
+    });
+    let responses = futures::future::join_all(handshakes).await;
+
+    assert!(responses.iter().all(|response| response.success));
+    assert_eq!(registry.len(), 100);
+
+    shutdown_tx.send(()).unwrap();
+    let summary = server_handle.await.unwrap().unwrap();
+    assert_eq!(summary.packets_received, 200);
+    assert_eq!(summary.packets_failed, 0);
+}
+
+#[tokio::test]
+async fn test_gossip_node_drops_flooding_source() {
+    let (gossip_node, server_port) =
+        create_test_gossip_node_with(GossipNode::builder().rate_limits(RateLimitConfig {
+            ip_packets_per_sec: Some(5),
+            ..Default::default()
+        }))
+        .await;
+    let stats = gossip_node.stats();
+
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+
+    // Network mismatches are answered, so replies can be counted
+    let peer = Keypair::new();
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    for _ in 0..20 {
+        let mut request = HandshakeRequest::new_local(peer.pubkey());
+        request.network_id = network::TESTNET.to_string();
+        request.sign(&peer);
+        socket
+            .send_to(
+                &borsh::to_vec(&request).unwrap(),
+                format!("127.0.0.1:{}", server_port),
+            )
+            .await
+            .unwrap();
+    }
+
+    let mut replies = 0;
+    let mut buffer = vec![0u8; 1280];
+    while timeout(Duration::from_millis(300), socket.recv_from(&mut buffer))
+        .await
+        .is_ok()
+    {
+        replies += 1;
+    }
+
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.packets_received, 20);
+    assert!(
+        replies <= 6,
+        "Flood should be cut off, got {} replies",
+        replies
+    );
+    assert_eq!(snapshot.dropped_ip_limit, 20 - replies);
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_gossip_node_limits_unverified_rejections() {
+    let (gossip_node, server_port) =
+        create_test_gossip_node_with(GossipNode::builder().rate_limits(RateLimitConfig {
+            unverified_rejections_per_sec: Some(1),
+            ..RateLimitConfig::default()
+        }))
+        .await;
+    let stats = gossip_node.stats();
+
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let server_addr = format!("127.0.0.1:{}", server_port);
+    let mut buffer = vec![0u8; 1280];
+    let mut replies = Vec::new();
+    for _ in 0..2 {
+        let peer = Keypair::new();
+        let mut request = HandshakeRequest::new_local(peer.pubkey());
+        request.network_id = "x".repeat(512);
+        request.sign(&peer);
+        let packet = borsh::to_vec(&request).unwrap();
+        socket.send_to(&packet, &server_addr).await.unwrap();
+
+        if let Ok(reply) = timeout(Duration::from_millis(500), socket.recv_from(&mut buffer)).await
+        {
+            let (len, _) = reply.unwrap();
+            replies.push((packet.len(), buffer[..len].to_vec()));
+        }
+    }
+
+    // One failure response, no larger than the request and without its text
+    assert_eq!(replies.len(), 1);
+    let (request_len, reply) = &replies[0];
+    assert!(reply.len() < *request_len);
+    let response = HandshakeResponse::try_from_slice(reply).unwrap();
+    assert_eq!(response.reject_reason, Some(RejectReason::NetworkMismatch));
+    assert!(!response.error_message.unwrap().contains("xxx"));
+    assert_eq!(stats.snapshot().rejections_suppressed, 1);
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_gossip_node_rate_limits_responses_per_pubkey() {
+    let gossip_node = GossipNode::builder()
+        .bind_addr("127.0.0.1:0")
+        .rate_limits(RateLimitConfig {
+            pubkey_requests_per_sec: Some(2),
+            ..RateLimitConfig::default()
+        })
+        .build()
+        .await
+        .unwrap();
+    let server_addr = gossip_node.local_addr().unwrap();
+    let stats = gossip_node.stats();
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    // Each response from an unverified sender would draw a ping
+    let peer = Keypair::new();
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    for _ in 0..5 {
+        let response = unsolicited_response(&peer, &socket, Capability::Gossip.into());
+        socket
+            .send_to(&borsh::to_vec(&response).unwrap(), server_addr)
+            .await
+            .unwrap();
+    }
+
+    let mut buffer = vec![0u8; 1280];
+    let mut pings = 0;
+    while let Ok(received) =
+        timeout(Duration::from_millis(300), socket.recv_from(&mut buffer)).await
+    {
+        let (len, _) = received.unwrap();
+        if let Ok(Protocol::PingMessage(_)) = Protocol::decode(&buffer[..len]) {
+            pings += 1;
+        }
+    }
+
+    assert!(pings <= 2, "Expected at most 2 pings, got {}", pings);
+    assert_eq!(stats.snapshot().dropped_pubkey_limit, 3);
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_gossip_node_does_not_answer_unverified_rejections() {
+    let (gossip_node, server_port) = create_test_gossip_node_with(
+        GossipNode::builder().reject_policy(RejectPolicy::DropUnverified),
+    )
+    .await;
+    let stats = gossip_node.stats();
+
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+
+    let peer = Keypair::new();
+    let mut request = HandshakeRequest::new_local(peer.pubkey());
+    request.network_id = network::TESTNET.to_string();
+    request.sign(&peer);
+
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    socket
+        .send_to(
+            &borsh::to_vec(&request).unwrap(),
+            format!("127.0.0.1:{}", server_port),
+        )
+        .await
+        .unwrap();
+
+    let mut buffer = vec![0u8; 1280];
+    let reply = timeout(Duration::from_millis(500), socket.recv_from(&mut buffer)).await;
+    assert!(reply.is_err(), "Unverified sender must not be answered");
+    assert_eq!(stats.snapshot().rejections_suppressed, 1);
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_gossip_node_enforces_reloadable_access_policy() {
+    let peer = Keypair::new();
+    let access = AccessControl::new(AccessPolicy {
+        deny_pubkeys: [peer.pubkey()].into_iter().collect(),
+        ..Default::default()
+    });
+
+    let (gossip_node, server_port) =
+        create_test_gossip_node_with(GossipNode::builder().access_control(access.clone())).await;
+    let registry = gossip_node.peer_registry();
+
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+    let server_addr = format!("127.0.0.1:{}", server_port);
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+    let mut request = HandshakeRequest::new_local(peer.pubkey());
+    request.sign(&peer);
+    let response = handshake_over_raw_socket(&socket, &server_addr, &peer, &request).await;
+    assert!(!response.success);
+    assert!(response
+        .error_message
+        .unwrap()
+        .contains("Access denied: pubkey"));
+    assert_eq!(response.reject_reason, Some(RejectReason::AccessDenied));
+    assert!(registry.is_empty());
+
+    // Lifting the denial takes effect without restarting the node
+    access.replace(AccessPolicy::default());
+
+    let mut request = HandshakeRequest::new_local(peer.pubkey());
+    request.sign(&peer);
+    let response = handshake_over_raw_socket(&socket, &server_addr, &peer, &request).await;
+    assert!(response.success);
+    assert_eq!(registry.len(), 1);
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_client_refuses_denied_peers() {
+    let client = test_client_builder(network::LOCALNET)
+        .access_control(AccessControl::new(AccessPolicy {
+            deny_cidrs: vec!["127.0.0.0/8".parse().unwrap()],
+            ..Default::default()
+        }))
+        .build()
+        .await
+        .unwrap();
+
+    let peer_addr = format!("127.0.0.1:{}", find_available_port().await)
+        .parse()
+        .unwrap();
+    let result = timeout(Duration::from_secs(1), client.perform_handshake(peer_addr))
+        .await
+        .expect("Denied peer should fail without waiting for a response");
+
+    assert!(matches!(
+        result,
+        Err(SolanaNodeError::PeerVerificationFailed { peer_addr: addr }) if addr == peer_addr
+    ));
+}
+
+#[tokio::test]
+async fn test_gossip_node_restores_and_reverifies_saved_peers() {
+    let (known_node, known_port) = create_test_gossip_node(network::LOCALNET).await;
+    let known_pubkey = known_node.node_pubkey();
+    let known_handle = RunningNode::spawn(known_node);
+
+    // A peer store left behind by a previous run
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("peers.json");
+    let known_addr = format!("127.0.0.1:{}", known_port).parse().unwrap();
+    let saved = PeerRegistry::new();
+    saved.upsert(GossipNodeInfo {
+        peer_pubkey: known_pubkey,
+        gossip_addr: known_addr,
+        protocol_version: "1.0".to_string(),
+        negotiated_version: None,
+        network_id: network::LOCALNET.to_string(),
+        capabilities: CapabilitySet::default(),
+        last_seen: 0,
+        connection_status: ConnectionStatus::Connected,
+    });
+    saved.save(&path).await.unwrap();
+
+    let (mut gossip_node, _) =
+        create_test_gossip_node_with(GossipNode::builder().peer_store(&path)).await;
+    let mut events = gossip_node.subscribe();
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    let server_handle = tokio::spawn(async move {
+        gossip_node
+            .run_until(async {
+                let _ = shutdown_rx.await;
+            })
+            .await
+    });
+
+    // Restored peers are not trusted until they answer a handshake again
+    match next_event(&mut events).await {
+        PeerEvent::PeerRegistered { peer_info } => {
+            assert_eq!(peer_info.gossip_addr, known_addr);
+            assert_eq!(peer_info.connection_status, ConnectionStatus::Connecting);
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+    match next_event(&mut events).await {
+        PeerEvent::PeerUpdated {
+            peer_info,
+            previous_status,
+        } => {
+            assert_eq!(peer_info.peer_pubkey, known_pubkey);
+            assert_eq!(peer_info.connection_status, ConnectionStatus::Connected);
+            assert_eq!(previous_status, ConnectionStatus::Connecting);
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+
+    // The registry is saved again on shutdown
+    shutdown_tx.send(()).unwrap();
+    timeout(Duration::from_secs(2), server_handle)
+        .await
+        .expect("Gossip node should stop promptly")
+        .unwrap()
+        .unwrap();
+    let peers = PeerRegistry::load(&path).await.unwrap();
+    assert_eq!(peers.len(), 1);
+    assert_eq!(peers[0].connection_status, ConnectionStatus::Connected);
+    assert!(peers[0].last_seen > 0);
+
+    known_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_metrics_record_handshakes_and_traffic() {
+    let metrics = Metrics::new();
+    let (gossip_node, server_port) =
+        create_test_gossip_node_with(GossipNode::builder().metrics(metrics.clone())).await;
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+
+    let client = test_client_builder(network::LOCALNET)
+        .metrics(metrics.clone())
+        .build()
+        .await
+        .unwrap();
+    let server_addr = format!("127.0.0.1:{}", server_port).parse().unwrap();
+    client.perform_handshake(server_addr).await.unwrap();
+
+    let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let client = test_client_builder(network::LOCALNET)
+        .operation_timeout(Duration::from_millis(100))
+        .retry_policy(1)
+        .metrics(metrics.clone())
+        .build()
+        .await
+        .unwrap();
+    let result = client.perform_handshake(silent.local_addr().unwrap()).await;
+    assert!(matches!(
+        result,
+        Err(SolanaNodeError::ReceiveTimeout { .. })
+    ));
+
+    let rendered = metrics.render();
+    assert!(rendered.contains("p2p_handshakes_attempted_total 2\n"));
+    assert!(rendered.contains("p2p_handshakes_succeeded_total 1\n"));
+    assert!(rendered.contains("p2p_handshakes_failed_total{kind=\"receive_timeout\"} 1\n"));
+    assert!(rendered.contains("p2p_handshake_duration_seconds_count 1\n"));
+    assert!(rendered.contains("p2p_registered_peers{status=\"connected\"} 1\n"));
+    assert!(!rendered.contains("p2p_packets_received_total 0\n"));
+    assert!(!rendered.contains("p2p_packet_size_bytes_count 0\n"));
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_batch_handshake_report_reflects_outcomes() {
+    let (gossip_node, server_port) = create_test_gossip_node(network::LOCALNET).await;
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    sleep(Duration::from_millis(100)).await;
+
+    let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let server_addr = format!("127.0.0.1:{}", server_port).parse().unwrap();
+    let silent_addr = silent.local_addr().unwrap();
+    let client = test_client_builder(network::LOCALNET)
+        .operation_timeout(Duration::from_millis(200))
+        .retry_policy(1)
+        .build()
+        .await
+        .unwrap();
+
+    let outcomes = client
+        .perform_batch_handshake_outcomes(vec![server_addr, silent_addr])
+        .await;
+    let report = HandshakeReport::from_outcomes(&outcomes);
+
+    assert_eq!(report.records.len(), 2);
+    assert_eq!(report.records[0].peer_addr, server_addr);
+    assert!(report.records[0].success);
+    assert_eq!(report.records[0].attempts, 1);
+    assert!(report.records[0].rtt_ms.is_some());
+    assert_eq!(report.records[1].peer_addr, silent_addr);
+    assert!(!report.records[1].success);
+    assert_eq!(
+        report.records[1].error_kind.as_deref(),
+        Some("receive_timeout")
+    );
+    assert_eq!(report.exit_code(), EXIT_SOME_FAILED);
+
+    let ndjson = report.render(OutputFormat::Ndjson).unwrap();
+    assert_eq!(ndjson.lines().count(), 2);
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_retry_policy_controls_attempts() {
+    let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let silent_addr = silent.local_addr().unwrap();
+    let policy = RetryPolicy {
+        max_attempts: 3,
+        backoff: Backoff::Constant,
+        base_delay: Duration::from_millis(50),
+        jitter: Jitter::None,
+        ..RetryPolicy::default()
+    };
+    let client_with = |policy: RetryPolicy| async move {
+        test_client_builder(network::LOCALNET)
+            .operation_timeout(Duration::from_millis(100))
+            .retry_policy(policy)
+            .build()
+            .await
+            .unwrap()
+    };
+
+    let client = client_with(policy.clone()).await;
+    let outcomes = client
+        .perform_batch_handshake_outcomes(vec![silent_addr])
+        .await;
+    assert_eq!(outcomes[0].attempts, 3);
+
+    // A policy allowing no attempts still makes the first one
+    let client = client_with(RetryPolicy {
+        max_attempts: 0,
+        ..policy.clone()
+    })
+    .await;
+    let outcomes = client
+        .perform_batch_handshake_outcomes(vec![silent_addr])
+        .await;
+    assert_eq!(outcomes[0].attempts, 1);
+    assert!(matches!(
+        outcomes[0].result,
+        Err(SolanaNodeError::ReceiveTimeout { .. })
+    ));
+
+    // Kinds listed as non-retryable fail on the first attempt
+    let client = client_with(RetryPolicy {
+        non_retryable: [ErrorKind::ReceiveTimeout].into_iter().collect(),
+        ..policy.clone()
+    })
+    .await;
+    let outcomes = client
+        .perform_batch_handshake_outcomes(vec![silent_addr])
+        .await;
+    assert_eq!(outcomes[0].attempts, 1);
+
+    // No retry starts past the deadline
+    let client = client_with(RetryPolicy {
+        deadline: Some(Duration::from_millis(120)),
+        ..policy
+    })
+    .await;
+    let outcomes = client
+        .perform_batch_handshake_outcomes(vec![silent_addr])
+        .await;
+    assert_eq!(outcomes[0].attempts, 1);
+    assert!(matches!(
+        outcomes[0].result,
+        Err(SolanaNodeError::ReceiveTimeout { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_builders_with_pre_bound_sockets() {
+    let node_identity = Keypair::new();
+    let node_pubkey = node_identity.pubkey();
+    let node_socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let node_addr = node_socket.local_addr().unwrap();
+    let node_builder = GossipNode::builder()
+        .socket(node_socket)
+        .identity(node_identity)
+        .network_id(network::TESTNET)
+        .network_timeout(Duration::from_secs(5));
+    assert!(!format!("{:?}", node_builder).contains("secret"));
+
+    let gossip_node = node_builder.build().await.unwrap();
+    assert_eq!(gossip_node.local_addr().unwrap(), node_addr);
+    assert_eq!(gossip_node.node_pubkey(), node_pubkey);
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    let client_socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let client_addr = client_socket.local_addr().unwrap();
+    let client = HandshakeClient::builder()
+        .socket(client_socket)
+        .network_id(network::TESTNET)
+        .operation_timeout(Duration::from_secs(2))
+        .retry_policy(1)
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(client.local_addr().unwrap(), client_addr);
+
+    let peer_info = client.perform_handshake(node_addr).await.unwrap();
+    assert_eq!(peer_info.peer_pubkey, node_pubkey);
+    assert_eq!(peer_info.network_id, network::TESTNET);
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_gossip_node_builder_applies_server_settings() {
+    let gossip_node = GossipNode::builder()
+        .bind_addr("127.0.0.1:0")
+        .replay_window(Duration::from_secs(30))
+        .rate_limits(RateLimitConfig {
+            unverified_rejections_per_sec: Some(10),
+            ..RateLimitConfig::default()
+        })
+        .workers(2)
+        .queue_depth(4)
+        .build()
+        .await
+        .unwrap();
+    let server_addr = gossip_node.local_addr().unwrap().to_string();
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    let peer = Keypair::new();
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let mut request = HandshakeRequest::new_local(peer.pubkey());
+    request.timestamp -= 60;
+    request.sign(&peer);
+
+    let response = handshake_over_raw_socket(&socket, &server_addr, &peer, &request).await;
+    assert_eq!(response.reject_reason, Some(RejectReason::StaleRequest));
+
+    server_handle.stop().await;
+}
+
+#[tokio::test]
+async fn test_gossip_node_packet_size_limit() {
+    // A request truncated to the node's packet size limit does not decode
+    let gossip_node = GossipNode::builder()
+        .bind_addr("127.0.0.1:0")
+        .max_packet_size(64)
+        .build()
+        .await
+        .unwrap();
+    let node_addr = gossip_node.local_addr().unwrap();
+    let server_handle = RunningNode::spawn(gossip_node);
+
+    let client = HandshakeClient::builder()
+        .bind_addr("127.0.0.1:0")
+        .operation_timeout(Duration::from_millis(300))
+        .retry_policy(1)
+        .build()
+        .await
+        .unwrap();
+    let result = client.perform_handshake(node_addr).await;
+    assert!(
+        matches!(result, Err(SolanaNodeError::ReceiveTimeout { .. })),
+        "Oversized request should go unanswered: {:?}",
+        result
+    );
+
+    server_handle.stop().await;
+}
+
+/// Value of the string attribute `key` on every exported span named `name`
+fn exported_span_attributes(batches: &[serde_json::Value], name: &str, key: &str) -> Vec<String> {
+    batches
+        .iter()
+        .flat_map(|batch| {
+            batch["resourceSpans"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+        })
+        .flat_map(|resource| {
+            resource["scopeSpans"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default()
+        })
+        .flat_map(|scope| scope["spans"].as_array().cloned().unwrap_or_default())
+        .filter(|span| span["name"] == name)
+        .filter_map(|span| {
+            span["attributes"]
+                .as_array()?
+                .iter()
+                .find(|attribute| attribute["key"] == key)
+                .and_then(|attribute| attribute["value"]["stringValue"].as_str())
+                .map(str::to_string)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_handshake_spans_exported_with_shared_correlation_id() {
+    // Stand-in for an OpenTelemetry collector's OTLP/HTTP receiver
+    let collector = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/traces"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&collector)
+        .await;
+
+    let tracer_provider =
+        otlp_tracer_provider(&OtlpConfig::new(collector.uri(), "handshake-test")).unwrap();
+    let subscriber = tracing_subscriber::registry().with(otlp_layer(&tracer_provider));
+    let tracing_default = tracing::subscriber::set_default(subscriber);
+
+    let (gossip_node, server_port) = create_test_gossip_node(network::LOCALNET).await;
+    let server_handle = RunningNode::spawn(gossip_node);
+    sleep(Duration::from_millis(100)).await;
+
+    let client = create_test_handshake_client(network::LOCALNET).await;
+    let server_addr = format!("127.0.0.1:{}", server_port).parse().unwrap();
+    client.perform_handshake(server_addr).await.unwrap();
+    server_handle.stop().await;
+
+    drop(tracing_default);
+    tokio::task::spawn_blocking(move || tracer_provider.force_flush())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let batches: Vec<serde_json::Value> = collector
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| request.body_json().unwrap())
+        .collect();
+
+    let client_ids = exported_span_attributes(&batches, "handshake_attempt", "correlation_id");
+    let server_ids =
+        exported_span_attributes(&batches, "handle_handshake_request", "correlation_id");
+    assert_eq!(client_ids.len(), 1);
+    assert_eq!(server_ids, client_ids);
+    assert_eq!(
+        exported_span_attributes(&batches, "handshake_attempt", "outcome"),
+        vec!["success".to_string()]
+    );
+}
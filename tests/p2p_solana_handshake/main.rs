@@ -1 +1,2 @@
+mod gossip_protocol_integration;
 mod handshake_integration;